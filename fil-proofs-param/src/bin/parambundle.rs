@@ -0,0 +1,114 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::exit;
+
+use anyhow::Result;
+use fil_proofs_param::bundle::{export_bundle, import_bundle, select_parameters, ParameterKind};
+use filecoin_proofs::constants::PUBLISHED_SECTOR_SIZES;
+use log::{error, info};
+use storage_proofs_core::{api_version::ApiVersion, parameter_cache::parameter_cache_dir};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "parambundle",
+    version = "1.0",
+    about = "Exports parameters from the parameter cache into a bundle and imports them from it, \
+        to move them to machines without network access.\n\n\
+        Set the $FIL_PROOFS_PARAMETER_CACHE env-var to specify the path to the parameter cache \
+        directory."
+)]
+enum Cli {
+    #[structopt(about = "Writes the selected parameters of the parameter cache into a bundle.")]
+    Export {
+        #[structopt(
+            long,
+            short = "o",
+            value_name = "PATH",
+            help = "The tar archive to write."
+        )]
+        output: PathBuf,
+        #[structopt(
+            long = "sector-sizes",
+            short = "z",
+            value_name = "SECTOR SIZES",
+            use_delimiter = true,
+            help = "A comma-separated list of sector sizes (in bytes), defaults to all published \
+                ones."
+        )]
+        sector_sizes: Vec<u64>,
+        #[structopt(
+            long,
+            short = "k",
+            value_name = "KINDS",
+            use_delimiter = true,
+            help = "A comma-separated list of porep, post and update, defaults to all of them."
+        )]
+        kinds: Vec<ParameterKind>,
+        #[structopt(
+            long = "api-versions",
+            value_name = "SEMANTIC VERSIONS",
+            use_delimiter = true,
+            help = "A comma-separated list of rust-fil-proofs API versions, defaults to all of \
+                them."
+        )]
+        api_versions: Vec<ApiVersion>,
+        #[structopt(long, help = "Include the SRS for proof aggregation.")]
+        srs: bool,
+    },
+    #[structopt(
+        about = "Verifies the parameters of a bundle and adds them to the parameter cache."
+    )]
+    Import {
+        #[structopt(value_name = "PATH", help = "The tar archive to read.")]
+        input: PathBuf,
+    },
+}
+
+fn run(cli: Cli) -> Result<()> {
+    match cli {
+        Cli::Export {
+            output,
+            mut sector_sizes,
+            mut kinds,
+            mut api_versions,
+            srs,
+        } => {
+            if sector_sizes.is_empty() {
+                sector_sizes = PUBLISHED_SECTOR_SIZES.to_vec();
+            }
+            if kinds.is_empty() {
+                kinds = vec![
+                    ParameterKind::Porep,
+                    ParameterKind::Post,
+                    ParameterKind::Update,
+                ];
+            }
+            if api_versions.is_empty() {
+                api_versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
+            }
+
+            let files = select_parameters(&sector_sizes, &kinds, &api_versions, srs)?;
+            info!("exporting {} files: {:?}", files.len(), files.keys());
+            export_bundle(&parameter_cache_dir(), &files, &output)?;
+            info!("wrote bundle {:?}", output);
+        }
+        Cli::Import { input } => {
+            let imported = import_bundle(&input, &parameter_cache_dir())?;
+            info!("imported {} files: {:?}", imported.len(), imported);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn main() {
+    // Log all log levels to stderr.
+    env::set_var("RUST_LOG", "parambundle");
+    fil_logger::init();
+
+    if let Err(err) = run(Cli::from_args()) {
+        error!("{:?}", err);
+        exit(1);
+    }
+}
@@ -1,11 +1,15 @@
 use std::env;
 use std::fs::{create_dir_all, rename, File};
-use std::io::{self, copy, stderr, stdout, Read, Stdout, Write};
-use std::path::{Path, PathBuf};
-use std::process::{exit, Command};
+use std::io::copy;
+use std::path::PathBuf;
+use std::process::exit;
 
-use anyhow::{ensure, Context, Result};
+use anyhow::Result;
 use dialoguer::{theme::ColorfulTheme, MultiSelect, Select};
+use fil_proofs_param::source::{
+    fetch_parameter_file, parse_source_specs, FetchProgress, HttpSource, IpgetSource, LocalSource,
+    ParamSource, SourceSpec,
+};
 use filecoin_proofs::param::{
     get_digest_for_file_within_cache, get_full_path_for_file_within_cache, has_extension,
 };
@@ -13,17 +17,20 @@ use flate2::read::GzDecoder;
 use humansize::{file_size_opts, FileSize};
 use lazy_static::lazy_static;
 use log::{error, info, trace, warn};
-use pbr::{ProgressBar, Units};
 use reqwest::{blocking::Client, header, Proxy, Url};
-use storage_proofs_core::parameter_cache::{
-    parameter_cache_dir, parameter_cache_dir_name, ParameterMap, GROTH_PARAMETER_EXT,
+use storage_proofs_core::{
+    parameter_cache::{
+        parameter_cache_dir, parameter_cache_dir_name, ParameterMap, GROTH_PARAMETER_EXT,
+    },
+    settings::SETTINGS,
 };
 use structopt::StructOpt;
 use tar::Archive;
 
 lazy_static! {
     static ref CLI_ABOUT: String = format!(
-        "Downloads missing or outdated Groth parameter files from ipfs using ipget.\n\n
+        "Downloads missing or outdated Groth parameter files from ipfs using ipget, HTTP(S) \
+        mirrors or local directories.\n\n
         Set the $FIL_PROOFS_PARAMETER_CACHE env-var to specify the path to the parameter cache
         directory (location where params are written), otherwise params will be written to '{}'.",
         parameter_cache_dir_name(),
@@ -43,32 +50,6 @@ fn get_ipget_path(version: &str) -> String {
     format!("{}/ipget/ipget", get_ipget_dir(version))
 }
 
-/// Reader with progress bar.
-struct FetchProgress<R> {
-    reader: R,
-    progress_bar: ProgressBar<Stdout>,
-}
-
-impl<R: Read> Read for FetchProgress<R> {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.reader.read(buf).map(|n| {
-            self.progress_bar.add(n as u64);
-            n
-        })
-    }
-}
-
-impl<R: Read> FetchProgress<R> {
-    fn new(reader: R, size: u64) -> Self {
-        let mut progress_bar = ProgressBar::new(size);
-        progress_bar.set_units(Units::Bytes);
-        FetchProgress {
-            reader,
-            progress_bar,
-        }
-    }
-}
-
 /// Download a version of ipget.
 fn download_ipget(version: &str, verbose: bool) -> Result<()> {
     info!("downloading ipget");
@@ -184,36 +165,33 @@ fn get_filenames_requiring_download(
         .collect()
 }
 
-fn download_file_with_ipget(
-    cid: &str,
-    path: &Path,
-    ipget_path: &Path,
-    ipget_args: &Option<String>,
-    verbose: bool,
-) -> Result<()> {
-    let mut args = vec![cid, "-o", path.to_str().unwrap()];
-    if let Some(ipget_args) = ipget_args {
-        args.extend(ipget_args.split_whitespace());
-    }
-    trace!(
-        "spawning subprocess: {} {}",
-        ipget_path.display(),
-        args.join(" ")
-    );
-    let output = Command::new(ipget_path.as_os_str())
-        .args(&args)
-        .output()
-        .with_context(|| "failed to spawn ipget subprocess")?;
-    if verbose {
-        stdout()
-            .write_all(&output.stdout)
-            .with_context(|| "failed to write ipget's stdout")?;
-        stderr()
-            .write_all(&output.stderr)
-            .with_context(|| "failed to write ipget's stderr")?;
+/// Returns the path of the ipget binary, downloading it if needed.
+fn get_ipget(cli: &Cli) -> PathBuf {
+    if let Some(path_str) = &cli.ipget_bin {
+        let path = PathBuf::from(path_str);
+        if !path.exists() {
+            error!(
+                "provided ipget binary not found: {}, exiting",
+                path.display()
+            );
+            exit(1);
+        }
+
+        path
+    } else {
+        let ipget_version = cli
+            .ipget_version
+            .clone()
+            .unwrap_or_else(|| DEFAULT_IPGET_VERSION.to_string());
+        let tmp_path = get_ipget_path(&ipget_version);
+        let path = PathBuf::from(&tmp_path);
+        if !path.exists() {
+            info!("ipget binary not found: {}", path.display());
+            download_ipget(&ipget_version, cli.verbose).expect("ipget download failed");
+        }
+
+        path
     }
-    ensure!(output.status.success(), "ipget returned non-zero exit code");
-    Ok(())
 }
 
 #[derive(Debug, StructOpt)]
@@ -249,6 +227,15 @@ struct Cli {
     sector_sizes: Option<Vec<u64>>,
     #[structopt(long, short = "v")]
     verbose: bool,
+    #[structopt(
+        long,
+        short = "s",
+        value_name = "SOURCES",
+        long_help = "A comma-separated list of sources to download from, tried in order: ipfs, \
+            the base URL of a HTTP(S) mirror or the path of a local directory. If this argument \
+            is not given, the parameter_sources setting is used, which defaults to ipfs."
+    )]
+    sources: Option<String>,
     #[structopt(
         long = "ipget-bin",
         short = "i",
@@ -282,10 +269,10 @@ pub fn main() {
     let cli = Cli::from_args();
 
     // Parse parameters.json file.
-    let parameter_map: ParameterMap = match cli.json {
+    let parameter_map: ParameterMap = match &cli.json {
         Some(json_path) => {
             trace!("using json file: {}", json_path);
-            let mut json_file = File::open(&json_path)
+            let mut json_file = File::open(json_path)
                 .map_err(|e| {
                     error!("failed to open json file, exiting\n{:?}", e);
                     exit(1);
@@ -369,46 +356,54 @@ pub fn main() {
         return;
     }
 
-    let ipget_path = if let Some(path_str) = cli.ipget_bin {
-        let path = PathBuf::from(path_str);
-        if !path.exists() {
-            error!(
-                "provided ipget binary not found: {}, exiting",
-                path.display()
-            );
+    let source_specs = cli.sources.as_ref().unwrap_or(&SETTINGS.parameter_sources);
+    let source_specs = parse_source_specs(source_specs)
+        .map_err(|e| {
+            error!("failed to parse sources, exiting\n{:?}", e);
             exit(1);
-        }
+        })
+        .unwrap();
+    if source_specs.is_empty() {
+        error!("no sources given, exiting");
+        exit(1);
+    }
 
-        path
-    } else {
-        let ipget_version = cli
-            .ipget_version
-            .unwrap_or_else(|| DEFAULT_IPGET_VERSION.to_string());
-        let tmp_path = get_ipget_path(&ipget_version);
-        let path = PathBuf::from(&tmp_path);
-        if !path.exists() {
-            info!("ipget binary not found: {}", path.display());
-            download_ipget(&ipget_version, cli.verbose).expect("ipget download failed");
+    let mut sources: Vec<Box<dyn ParamSource>> = Vec::new();
+    for spec in source_specs {
+        match spec {
+            SourceSpec::Ipfs => {
+                let ipget_path = get_ipget(&cli);
+                trace!("using ipget binary: {}", ipget_path.display());
+                sources.push(Box::new(IpgetSource::new(
+                    ipget_path,
+                    cli.ipget_args.clone(),
+                    cli.verbose,
+                )));
+            }
+            SourceSpec::Http(base_url) => {
+                let source = HttpSource::new(base_url, cli.verbose).expect("invalid HTTP source");
+                sources.push(Box::new(source));
+            }
+            SourceSpec::Local(dir) => sources.push(Box::new(LocalSource::new(dir))),
         }
-
-        path
-    };
-    trace!("using ipget binary: {}", ipget_path.display());
+    }
+    info!(
+        "using sources: {}",
+        sources
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
 
     trace!("creating param cache dir(s) if they don't exist");
     create_dir_all(parameter_cache_dir()).expect("failed to create param cache dir");
 
     loop {
         for filename in &filenames {
-            info!("downloading params file with ipget: {}", filename);
+            info!("downloading params file: {}", filename);
             let path = get_full_path_for_file_within_cache(filename);
-            match download_file_with_ipget(
-                &parameter_map[filename].cid,
-                &path,
-                &ipget_path,
-                &cli.ipget_args,
-                cli.verbose,
-            ) {
+            match fetch_parameter_file(&sources, filename, &parameter_map[filename], &path) {
                 Ok(_) => info!("finished downloading params file"),
                 Err(e) => warn!("failed to download params file: {}", e),
             };
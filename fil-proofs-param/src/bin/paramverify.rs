@@ -0,0 +1,97 @@
+use std::env;
+use std::process::exit;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use log::{error, info};
+use storage_proofs_core::parameter_cache::{
+    parameter_cache_dir_name, verify_parameter_cache, CacheFileStatus,
+};
+use structopt::StructOpt;
+
+lazy_static! {
+    static ref CLI_ABOUT: String = format!(
+        "Verifies the files of the parameter cache against parameters.json and \
+        srs-inner-product.json.\n\n
+        Every valid parameter and SRS file gets a verified marker, sealed with the secret key \
+        of the cache, so that loading it with FIL_PROOFS_VERIFY_PRODUCTION_PARAMS set does not \
+        hash it again. Verifying keys are always hashed. Set the \
+        $FIL_PROOFS_PARAMETER_CACHE env-var to specify the path to the parameter cache directory, \
+        otherwise '{}' is verified.",
+        parameter_cache_dir_name(),
+    );
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "paramverify", version = "1.0", about = CLI_ABOUT.as_str())]
+struct Cli {
+    #[structopt(
+        long,
+        short = "f",
+        help = "Hash all files, even if they have a valid verified marker."
+    )]
+    force: bool,
+    #[structopt(
+        long,
+        short = "i",
+        value_name = "SECONDS",
+        help = "Run as a daemon, verifying the cache again after every interval."
+    )]
+    interval: Option<u64>,
+}
+
+/// Verifies the parameter cache once, returns the number of invalid files.
+fn verify(force: bool) -> usize {
+    let start = Instant::now();
+    let results = match verify_parameter_cache(force) {
+        Ok(results) => results,
+        Err(err) => {
+            error!("failed to verify parameter cache: {:?}", err);
+            return 1;
+        }
+    };
+
+    let mut invalid = 0;
+    for result in &results {
+        match &result.status {
+            CacheFileStatus::Verified => info!("verified {:?}", result.path),
+            CacheFileStatus::PreviouslyVerified => {
+                info!("previously verified {:?}", result.path)
+            }
+            CacheFileStatus::Unknown => info!("ignoring unknown file {:?}", result.path),
+            CacheFileStatus::Invalid(reason) => {
+                error!("invalid file {:?}: {}", result.path, reason);
+                invalid += 1;
+            }
+        }
+    }
+    info!(
+        "checked {} files in {:?}, {} invalid",
+        results.len(),
+        start.elapsed(),
+        invalid
+    );
+
+    invalid
+}
+
+pub fn main() {
+    // Log all log levels to stderr.
+    env::set_var("RUST_LOG", "paramverify");
+    fil_logger::init();
+
+    let cli = Cli::from_args();
+
+    match cli.interval {
+        Some(interval) => loop {
+            verify(cli.force);
+            sleep(Duration::from_secs(interval));
+        },
+        None => {
+            if verify(cli.force) > 0 {
+                exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,426 @@
+//! Offline bundles of parameters, for machines which can not fetch them from IPFS.
+//!
+//! A bundle is a tar archive of parameter files, starting with a `manifest.json`, which lists them
+//! with their entries of `parameters.json` and `srs-inner-product.json`.
+
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, format_err, Context, Error, Result};
+use filecoin_proofs::{
+    constants::{
+        PUBLISHED_SECTOR_SIZES, WINDOW_POST_CHALLENGE_COUNT, WINDOW_POST_SECTOR_COUNT,
+        WINNING_POST_CHALLENGE_COUNT, WINNING_POST_SECTOR_COUNT,
+    },
+    types::{PoRepConfig, PoStConfig, SectorSize},
+    with_shape, PoStType,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use storage_proofs_core::{
+    api_version::ApiVersion,
+    merkle::MerkleTreeTrait,
+    parameter_cache::{
+        parameter_id, verified_marker_path, verify_parameter_cache_dir,
+        verify_parameter_cache_file, verifying_key_id, CacheFileStatus, CacheableParameters,
+        ParameterMap, PARAMETERS, SRS_PARAMETERS, VERSION,
+    },
+};
+use storage_proofs_update::{
+    circuit::EmptySectorUpdateCircuit,
+    compound::EmptySectorUpdateCompound,
+    constants::{TreeRHasher, ALLOWED_SECTOR_SIZES},
+    poseidon, PublicParams,
+};
+use tar::{Archive, Builder};
+
+/// The name of the manifest within a bundle.
+pub const BUNDLE_MANIFEST: &str = "manifest.json";
+
+/// The proofs whose parameters are bundled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterKind {
+    /// SDR PoRep.
+    Porep,
+    /// Winning and Window PoSt.
+    Post,
+    /// EmptySectorUpdate, including its Poseidon variant.
+    Update,
+}
+
+impl FromStr for ParameterKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "porep" => Ok(ParameterKind::Porep),
+            "post" => Ok(ParameterKind::Post),
+            "update" => Ok(ParameterKind::Update),
+            _ => Err(format_err!(
+                "unknown parameter kind {}, expected porep, post or update",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// The version of the parameter cache, see `storage_proofs_core::parameter_cache::VERSION`.
+    pub version: usize,
+    /// The bundled files and their manifest entries.
+    pub files: ParameterMap,
+}
+
+fn porep_identifier<Tree: 'static + MerkleTreeTrait>(config: &PoRepConfig) -> Result<String> {
+    config.get_cache_identifier::<Tree>()
+}
+
+fn post_identifier<Tree: 'static + MerkleTreeTrait>(config: &PoStConfig) -> Result<String> {
+    config.get_cache_identifier::<Tree>()
+}
+
+fn update_identifier<Tree: 'static + MerkleTreeTrait<Hasher = TreeRHasher>>(
+    sector_size: u64,
+) -> Result<String> {
+    let public_params = PublicParams::from_sector_size(sector_size);
+    Ok(<EmptySectorUpdateCompound<Tree> as CacheableParameters<
+        EmptySectorUpdateCircuit<Tree>,
+        _,
+    >>::cache_identifier(&public_params))
+}
+
+fn update_poseidon_identifier<Tree: 'static + MerkleTreeTrait<Hasher = TreeRHasher>>(
+    sector_size: u64,
+) -> Result<String> {
+    let public_params = PublicParams::from_sector_size_poseidon(sector_size);
+    Ok(
+        <poseidon::EmptySectorUpdateCompound<Tree> as CacheableParameters<
+            poseidon::EmptySectorUpdateCircuit<Tree>,
+            _,
+        >>::cache_identifier(&public_params),
+    )
+}
+
+/// Returns the cache identifiers of the parameters of all given kinds, sector sizes and api
+/// versions.
+fn cache_identifiers(
+    sector_sizes: &[u64],
+    kinds: &[ParameterKind],
+    api_versions: &[ApiVersion],
+) -> Result<BTreeSet<String>> {
+    let mut identifiers = BTreeSet::new();
+    for &sector_size in sector_sizes {
+        ensure!(
+            PUBLISHED_SECTOR_SIZES.contains(&sector_size),
+            "unsupported sector size: {}",
+            sector_size
+        );
+
+        for &api_version in api_versions {
+            for kind in kinds {
+                match kind {
+                    ParameterKind::Porep => {
+                        let config = PoRepConfig::new_groth16(sector_size, [0; 32], api_version);
+                        identifiers.insert(with_shape!(sector_size, porep_identifier, &config)?);
+                    }
+                    ParameterKind::Post => {
+                        let winning_config = PoStConfig {
+                            sector_size: SectorSize(sector_size),
+                            challenge_count: WINNING_POST_CHALLENGE_COUNT,
+                            sector_count: WINNING_POST_SECTOR_COUNT,
+                            typ: PoStType::Winning,
+                            priority: true,
+                            api_version,
+                        };
+                        let window_config = PoStConfig {
+                            sector_size: SectorSize(sector_size),
+                            challenge_count: WINDOW_POST_CHALLENGE_COUNT,
+                            sector_count: *WINDOW_POST_SECTOR_COUNT
+                                .read()
+                                .expect("WINDOW_POST_SECTOR_COUNT poisoned")
+                                .get(&sector_size)
+                                .expect("unknown sector size"),
+                            typ: PoStType::Window,
+                            priority: true,
+                            api_version,
+                        };
+                        identifiers.insert(with_shape!(
+                            sector_size,
+                            post_identifier,
+                            &winning_config
+                        )?);
+                        identifiers.insert(with_shape!(
+                            sector_size,
+                            post_identifier,
+                            &window_config
+                        )?);
+                    }
+                    ParameterKind::Update => {
+                        let supported = ALLOWED_SECTOR_SIZES
+                            .iter()
+                            .any(|nodes| (*nodes as u64) << 5 == sector_size);
+                        if !supported {
+                            info!(
+                                "no EmptySectorUpdate parameters for sector size {}",
+                                sector_size
+                            );
+                            continue;
+                        }
+                        identifiers.insert(with_shape!(
+                            sector_size,
+                            update_identifier,
+                            sector_size
+                        )?);
+                        identifiers.insert(with_shape!(
+                            sector_size,
+                            update_poseidon_identifier,
+                            sector_size
+                        )?);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(identifiers)
+}
+
+/// Selects the published parameter and verifying key files of the given kinds, sector sizes and
+/// api versions from `parameters.json`, and optionally the SRS files of
+/// `srs-inner-product.json`.
+pub fn select_parameters(
+    sector_sizes: &[u64],
+    kinds: &[ParameterKind],
+    api_versions: &[ApiVersion],
+    include_srs: bool,
+) -> Result<ParameterMap> {
+    let mut files = ParameterMap::new();
+    for identifier in cache_identifiers(sector_sizes, kinds, api_versions)? {
+        for file_name in [parameter_id(&identifier), verifying_key_id(&identifier)] {
+            match PARAMETERS.get(&file_name) {
+                Some(data) => {
+                    files.insert(file_name, data.clone());
+                }
+                None => info!("skipping unpublished parameters {}", file_name),
+            }
+        }
+    }
+    if include_srs {
+        files.extend(
+            SRS_PARAMETERS
+                .iter()
+                .map(|(file_name, data)| (file_name.clone(), data.clone())),
+        );
+    }
+
+    Ok(files)
+}
+
+/// Writes a bundle of `files` from `cache_dir` to `archive_path`. Every file is verified against
+/// its digest before it is added, trusting valid verified markers.
+pub fn export_bundle(cache_dir: &Path, files: &ParameterMap, archive_path: &Path) -> Result<()> {
+    ensure!(!files.is_empty(), "no parameters selected");
+
+    for file_name in files.keys() {
+        let path = cache_dir.join(file_name);
+        match verify_parameter_cache_file(&path, &[files], false) {
+            CacheFileStatus::Verified | CacheFileStatus::PreviouslyVerified => {}
+            CacheFileStatus::Unknown => bail!("{:?} is not in the parameters manifest", path),
+            CacheFileStatus::Invalid(reason) => bail!("invalid file {:?}: {}", path, reason),
+        }
+    }
+
+    let manifest = serde_json::to_vec_pretty(&BundleManifest {
+        version: VERSION,
+        files: files.clone(),
+    })?;
+
+    let archive = File::create(archive_path)
+        .with_context(|| format!("failed to create bundle {:?}", archive_path))?;
+    let mut builder = Builder::new(archive);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, BUNDLE_MANIFEST, &manifest[..])?;
+
+    for file_name in files.keys() {
+        info!("adding {} to bundle", file_name);
+        let mut file = File::open(cache_dir.join(file_name))?;
+        builder.append_file(file_name, &mut file)?;
+    }
+    builder.into_inner()?.sync_all()?;
+
+    Ok(())
+}
+
+/// Imports a bundle into the parameter cache, verifying every file against the digests in
+/// `parameters.json` and `srs-inner-product.json`, see `import_bundle_with_manifests`.
+pub fn import_bundle(archive_path: &Path, cache_dir: &Path) -> Result<Vec<String>> {
+    import_bundle_with_manifests(archive_path, cache_dir, &[&PARAMETERS, &SRS_PARAMETERS])
+}
+
+/// Imports a bundle into `cache_dir` and returns the names of the imported files.
+///
+/// The files are unpacked next to the cache and only moved into it, if all of them match the
+/// digests of the given manifests. Parameter and SRS files get verified markers, so
+/// that they are not hashed again when they are loaded. If moving a file into the cache fails,
+/// the files moved so far are moved back and the files they replaced restored. The move is not
+/// atomic as a whole, until it completes, the cache may hold some of the new files.
+pub fn import_bundle_with_manifests(
+    archive_path: &Path,
+    cache_dir: &Path,
+    manifests: &[&ParameterMap],
+) -> Result<Vec<String>> {
+    fs::create_dir_all(cache_dir)?;
+    // Unpacking within the cache directory keeps the final move atomic.
+    let staging_dir = tempfile::Builder::new()
+        .prefix(".bundle-import-")
+        .tempdir_in(cache_dir)?;
+
+    let archive = File::open(archive_path)
+        .with_context(|| format!("failed to open bundle {:?}", archive_path))?;
+    let mut archive = Archive::new(archive);
+    let mut manifest: Option<BundleManifest> = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let file_name = bundle_entry_name(&entry.path()?)?;
+
+        match &manifest {
+            None => {
+                ensure!(
+                    file_name == BUNDLE_MANIFEST,
+                    "bundle does not start with {}",
+                    BUNDLE_MANIFEST
+                );
+                let bundle_manifest: BundleManifest = serde_json::from_reader(&mut entry)
+                    .context("failed to parse bundle manifest")?;
+                ensure!(
+                    bundle_manifest.version == VERSION,
+                    "bundle of parameters v{}, expected v{}",
+                    bundle_manifest.version,
+                    VERSION
+                );
+                manifest = Some(bundle_manifest);
+            }
+            Some(manifest) => {
+                ensure!(
+                    manifest.files.contains_key(&file_name),
+                    "{} is not in the bundle manifest",
+                    file_name
+                );
+                info!("unpacking {}", file_name);
+                let mut file = File::create(staging_dir.path().join(&file_name))?;
+                io::copy(&mut entry, &mut file)?;
+                file.sync_all()?;
+            }
+        }
+    }
+    let manifest = manifest.ok_or_else(|| format_err!("empty bundle"))?;
+
+    let mut imported = Vec::new();
+    for verification in verify_parameter_cache_dir(staging_dir.path(), manifests, true)? {
+        let file_name = verification
+            .path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .expect("staged files are named after the manifest")
+            .to_string();
+        match verification.status {
+            CacheFileStatus::Verified => imported.push(file_name),
+            CacheFileStatus::PreviouslyVerified => {
+                bail!(
+                    "{} was not verified although verification is forced",
+                    file_name
+                )
+            }
+            CacheFileStatus::Unknown => bail!("{} is not a published parameter file", file_name),
+            CacheFileStatus::Invalid(reason) => bail!("invalid file {}: {}", file_name, reason),
+        }
+    }
+    let missing = manifest
+        .files
+        .keys()
+        .filter(|file_name| !imported.contains(file_name))
+        .collect::<Vec<_>>();
+    ensure!(missing.is_empty(), "bundle is missing {:?}", missing);
+
+    // The files replaced in the cache are kept until all files are moved, to restore them.
+    let replaced_dir = staging_dir.path().join("replaced");
+    fs::create_dir(&replaced_dir)?;
+    let mut moves = Vec::new();
+    if let Err(err) = move_into_cache(
+        staging_dir.path(),
+        cache_dir,
+        &replaced_dir,
+        &imported,
+        &mut moves,
+    ) {
+        for (from, to) in moves.iter().rev() {
+            if let Err(err) = fs::rename(to, from) {
+                warn!("failed to move back {:?} to {:?}: {}", to, from, err);
+            }
+        }
+        return Err(err.context("failed to move the bundle into the cache"));
+    }
+
+    Ok(imported)
+}
+
+/// Moves the staged files and their verified markers into `cache_dir`, moving files they replace
+/// to `replaced_dir`. Every move is recorded in `moves`, as `(from, to)`.
+fn move_into_cache(
+    staging_dir: &Path,
+    cache_dir: &Path,
+    replaced_dir: &Path,
+    file_names: &[String],
+    moves: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<()> {
+    for file_name in file_names {
+        let staged_path = staging_dir.join(file_name);
+        let cache_path = cache_dir.join(file_name);
+        let paths = [
+            (
+                verified_marker_path(&staged_path),
+                verified_marker_path(&cache_path),
+            ),
+            (staged_path, cache_path),
+        ];
+        for (staged_path, cache_path) in paths {
+            // Only parameter and SRS files have verified markers.
+            if !staged_path.exists() {
+                continue;
+            }
+            if cache_path.exists() {
+                let replaced_path = replaced_dir.join(
+                    cache_path
+                        .file_name()
+                        .expect("cache paths are named after the manifest"),
+                );
+                fs::rename(&cache_path, &replaced_path)?;
+                moves.push((cache_path.clone(), replaced_path));
+            }
+            fs::rename(&staged_path, &cache_path)?;
+            moves.push((staged_path, cache_path));
+        }
+        info!("imported {:?}", cache_dir.join(file_name));
+    }
+
+    Ok(())
+}
+
+/// Returns the file name of an entry of a bundle, which must not be anywhere but in its root.
+fn bundle_entry_name(path: &Path) -> Result<String> {
+    let mut components = path.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(file_name)), None) => file_name
+            .to_str()
+            .map(ToString::to_string)
+            .ok_or_else(|| format_err!("invalid file name in bundle: {:?}", path)),
+        _ => bail!("invalid path in bundle: {:?}", path),
+    }
+}
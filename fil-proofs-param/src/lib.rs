@@ -1,2 +1,5 @@
 #![deny(clippy::all, clippy::perf, clippy::correctness)]
 #![warn(clippy::unwrap_used)]
+
+pub mod bundle;
+pub mod source;
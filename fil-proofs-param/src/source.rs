@@ -0,0 +1,320 @@
+//! Backends to fetch parameter files from, see `ParamSource`.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, copy, stderr, stdout, Read, Seek, SeekFrom, Stdout, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Context, Error, Result};
+use blake2b_simd::State as Blake2b;
+use log::{info, trace, warn};
+use pbr::{ProgressBar, Units};
+use reqwest::{blocking::Client, header, Proxy, StatusCode, Url};
+use storage_proofs_core::parameter_cache::ParameterData;
+
+/// The extension of files which are being fetched, appended to the name of the parameter file.
+pub const PARTIAL_EXT: &str = "partial";
+
+/// A backend to fetch parameter files from.
+pub trait ParamSource: Display {
+    /// Fetches the parameter file `file_name` to `partial_path`. If `partial_path` exists, it
+    /// holds the start of the file from an interrupted fetch, which should be resumed if possible.
+    ///
+    /// The digest is checked by the caller, see `fetch_parameter_file`.
+    fn fetch(&self, file_name: &str, data: &ParameterData, partial_path: &Path) -> Result<()>;
+}
+
+/// Reader with progress bar.
+pub struct FetchProgress<R> {
+    reader: R,
+    progress_bar: ProgressBar<Stdout>,
+}
+
+impl<R: Read> Read for FetchProgress<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf).map(|n| {
+            self.progress_bar.add(n as u64);
+            n
+        })
+    }
+}
+
+impl<R: Read> FetchProgress<R> {
+    pub fn new(reader: R, size: u64) -> Self {
+        let mut progress_bar = ProgressBar::new(size);
+        progress_bar.set_units(Units::Bytes);
+        FetchProgress {
+            reader,
+            progress_bar,
+        }
+    }
+}
+
+/// Fetches files from IPFS, by their `cid`, using an `ipget` binary.
+pub struct IpgetSource {
+    ipget_path: PathBuf,
+    ipget_args: Option<String>,
+    verbose: bool,
+}
+
+impl IpgetSource {
+    pub fn new(ipget_path: PathBuf, ipget_args: Option<String>, verbose: bool) -> Self {
+        IpgetSource {
+            ipget_path,
+            ipget_args,
+            verbose,
+        }
+    }
+}
+
+impl Display for IpgetSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ipfs (ipget {})", self.ipget_path.display())
+    }
+}
+
+impl ParamSource for IpgetSource {
+    fn fetch(&self, _file_name: &str, data: &ParameterData, partial_path: &Path) -> Result<()> {
+        let partial_path = partial_path
+            .to_str()
+            .with_context(|| format!("invalid path {:?}", partial_path))?;
+        let mut args = vec![data.cid.as_str(), "-o", partial_path];
+        if let Some(ipget_args) = &self.ipget_args {
+            args.extend(ipget_args.split_whitespace());
+        }
+        trace!(
+            "spawning subprocess: {} {}",
+            self.ipget_path.display(),
+            args.join(" ")
+        );
+        let output = Command::new(self.ipget_path.as_os_str())
+            .args(&args)
+            .output()
+            .with_context(|| "failed to spawn ipget subprocess")?;
+        if self.verbose {
+            stdout()
+                .write_all(&output.stdout)
+                .with_context(|| "failed to write ipget's stdout")?;
+            stderr()
+                .write_all(&output.stderr)
+                .with_context(|| "failed to write ipget's stderr")?;
+        }
+        ensure!(output.status.success(), "ipget returned non-zero exit code");
+        Ok(())
+    }
+}
+
+/// Fetches files from a HTTP(S) mirror, which serves them by their name below a base URL.
+/// Interrupted downloads are resumed with range requests.
+pub struct HttpSource {
+    base_url: Url,
+    client: Client,
+    verbose: bool,
+}
+
+impl HttpSource {
+    pub fn new(mut base_url: Url, verbose: bool) -> Result<Self> {
+        // Without a trailing slash, the last segment of the path would be replaced on joining.
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        let client = Client::builder()
+            .proxy(Proxy::custom(move |url| env_proxy::for_url(url).to_url()))
+            // Downloads of the largest parameters take way longer than the default timeout.
+            .timeout(None)
+            .build()?;
+
+        Ok(HttpSource {
+            base_url,
+            client,
+            verbose,
+        })
+    }
+}
+
+impl Display for HttpSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.base_url)
+    }
+}
+
+impl ParamSource for HttpSource {
+    fn fetch(&self, file_name: &str, _data: &ParameterData, partial_path: &Path) -> Result<()> {
+        let url = self.base_url.join(file_name)?;
+        let offset = fs::metadata(partial_path).map(|m| m.len()).unwrap_or(0);
+
+        let mut request = self.client.get(url.clone());
+        if offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={}-", offset));
+        }
+        trace!("making GET request: {}", url.as_str());
+        let response = request.send()?;
+        trace!("received GET response: {}", response.status());
+
+        let file = match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                let content_range = response
+                    .headers()
+                    .get(header::CONTENT_RANGE)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default();
+                ensure!(
+                    content_range.starts_with(&format!("bytes {}-", offset)),
+                    "unexpected content range {:?} for offset {}",
+                    content_range,
+                    offset
+                );
+                info!("resuming download at byte {}", offset);
+                OpenOptions::new().append(true).open(partial_path)?
+            }
+            // The range starts at the end of the file, which is complete already.
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return Ok(()),
+            status if status.is_success() => File::create(partial_path)?,
+            status => bail!("GET {} returned {}", url, status),
+        };
+
+        let mut writer = io::BufWriter::new(file);
+        match response.content_length() {
+            Some(size) if self.verbose => {
+                copy(&mut FetchProgress::new(response, size), &mut writer)?;
+            }
+            _ => {
+                let mut response = response;
+                copy(&mut response, &mut writer)?;
+            }
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Fetches files from a local directory, e.g. an NFS mount, which holds them by their name.
+pub struct LocalSource {
+    dir: PathBuf,
+}
+
+impl LocalSource {
+    pub fn new(dir: PathBuf) -> Self {
+        LocalSource { dir }
+    }
+}
+
+impl Display for LocalSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.dir.display())
+    }
+}
+
+impl ParamSource for LocalSource {
+    fn fetch(&self, file_name: &str, _data: &ParameterData, partial_path: &Path) -> Result<()> {
+        let path = self.dir.join(file_name);
+        let mut source =
+            File::open(&path).with_context(|| format!("could not open path={:?}", path))?;
+        let len = source.metadata()?.len();
+
+        let offset = fs::metadata(partial_path).map(|m| m.len()).unwrap_or(0);
+        let mut target = if offset > 0 && offset <= len {
+            info!("resuming copy at byte {}", offset);
+            source.seek(SeekFrom::Start(offset))?;
+            OpenOptions::new().append(true).open(partial_path)?
+        } else {
+            File::create(partial_path)?
+        };
+        copy(&mut source, &mut target)?;
+
+        Ok(())
+    }
+}
+
+/// A source as given on the command line or in the settings: `ipfs`, the base URL of a HTTP(S)
+/// mirror or the path of a local directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceSpec {
+    Ipfs,
+    Http(Url),
+    Local(PathBuf),
+}
+
+impl FromStr for SourceSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "ipfs" {
+            Ok(SourceSpec::Ipfs)
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(SourceSpec::Http(Url::parse(s)?))
+        } else if let Some(path) = s.strip_prefix("file://") {
+            Ok(SourceSpec::Local(PathBuf::from(path)))
+        } else {
+            ensure!(!s.is_empty(), "empty parameter source");
+            Ok(SourceSpec::Local(PathBuf::from(s)))
+        }
+    }
+}
+
+/// Parses a comma-separated list of sources, e.g. `https://mirror.example/params,ipfs`.
+pub fn parse_source_specs(sources: &str) -> Result<Vec<SourceSpec>> {
+    sources
+        .split(',')
+        .map(str::trim)
+        .filter(|source| !source.is_empty())
+        .map(SourceSpec::from_str)
+        .collect()
+}
+
+/// The path a parameter file at `path` is fetched to.
+pub fn partial_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(PARTIAL_EXT);
+    path.with_file_name(file_name)
+}
+
+// Produces the truncated BLAKE2b checksum of a file, as found in the manifests.
+fn file_digest(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("could not open path={:?}", path))?;
+    let mut hasher = Blake2b::new();
+
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(hasher.finalize().to_hex()[..32].into())
+}
+
+/// Fetches the parameter file `file_name` to `path`, trying the sources in order until one of
+/// them provides it with the digest of `data`.
+///
+/// The file is fetched next to `path`, see `partial_path`, and only moved there once its digest
+/// matches. A partial file is kept if a source fails, so that the next attempt resumes it, and
+/// removed if its digest does not match.
+pub fn fetch_parameter_file(
+    sources: &[Box<dyn ParamSource>],
+    file_name: &str,
+    data: &ParameterData,
+    path: &Path,
+) -> Result<()> {
+    let partial_path = partial_path(path);
+    for source in sources {
+        info!("fetching {} from {}", file_name, source);
+        if let Err(err) = source.fetch(file_name, data, &partial_path) {
+            warn!("failed to fetch {} from {}: {:?}", file_name, source, err);
+            continue;
+        }
+
+        let digest = file_digest(&partial_path)?;
+        if digest == data.digest {
+            fs::rename(&partial_path, path)?;
+            info!("fetched {} from {}", file_name, source);
+            return Ok(());
+        }
+        warn!(
+            "{} from {} has digest {}, expected {}",
+            file_name, source, digest, data.digest
+        );
+        fs::remove_file(&partial_path)?;
+    }
+
+    bail!("failed to fetch {} from any source", file_name)
+}
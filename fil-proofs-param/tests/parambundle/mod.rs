@@ -0,0 +1,297 @@
+use std::fs::{self, File};
+use std::path::Path;
+
+use anyhow::Result;
+use blake2b_simd::State as Blake2b;
+use fil_proofs_param::bundle::{
+    export_bundle, import_bundle_with_manifests, select_parameters, BundleManifest, ParameterKind,
+    BUNDLE_MANIFEST,
+};
+use filecoin_proofs::constants::{SECTOR_SIZE_2_KIB, SECTOR_SIZE_32_GIB};
+use rand::{thread_rng, Rng};
+use storage_proofs_core::{
+    api_version::ApiVersion,
+    parameter_cache::{
+        has_valid_verified_marker, ParameterData, ParameterMap, PARAMETERS, VERSION,
+    },
+};
+use tar::{Builder, Header};
+use tempfile::tempdir;
+
+/// Writes a file of random bytes to `dir` and returns its manifest entry.
+fn write_param_file(dir: &Path, file_name: &str) -> Result<ParameterData> {
+    let bytes = thread_rng().gen::<[u8; 32]>();
+    fs::write(dir.join(file_name), bytes)?;
+
+    let mut hasher = Blake2b::new();
+    hasher.update(&bytes);
+
+    Ok(ParameterData {
+        cid: "".to_string(),
+        digest: hasher.finalize().to_hex()[..32].into(),
+        sector_size: 2048,
+    })
+}
+
+/// Writes a bundle with the given manifest and entries, without checking any of them, not even
+/// their paths.
+fn write_raw_bundle(
+    path: &Path,
+    manifest: &BundleManifest,
+    entries: &[(&str, &[u8])],
+) -> Result<()> {
+    let mut builder = Builder::new(File::create(path)?);
+    let manifest = serde_json::to_vec(manifest)?;
+    for (name, bytes) in [(BUNDLE_MANIFEST, &manifest[..])]
+        .iter()
+        .chain(entries.iter())
+    {
+        let mut header = Header::new_old();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_size(bytes.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append(&header, *bytes)?;
+    }
+    builder.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn selects_published_parameters() -> Result<()> {
+    let all = select_parameters(
+        &[SECTOR_SIZE_2_KIB, SECTOR_SIZE_32_GIB],
+        &[
+            ParameterKind::Porep,
+            ParameterKind::Post,
+            ParameterKind::Update,
+        ],
+        &[ApiVersion::V1_1_0, ApiVersion::V1_2_0],
+        false,
+    )?;
+    assert!(all
+        .keys()
+        .all(|file_name| PARAMETERS.contains_key(file_name)));
+    assert!(all.values().all(
+        |data| data.sector_size == SECTOR_SIZE_2_KIB || data.sector_size == SECTOR_SIZE_32_GIB
+    ));
+
+    let post = select_parameters(
+        &[SECTOR_SIZE_2_KIB],
+        &[ParameterKind::Post],
+        &[ApiVersion::V1_2_0],
+        false,
+    )?;
+    // Winning and Window PoSt, each with parameters and verifying key.
+    assert_eq!(post.len(), 4);
+    assert!(post.keys().all(|file_name| all.contains_key(file_name)
+        && file_name.contains("proof-of-spacetime-fallback")));
+
+    assert!(select_parameters(
+        &[1234],
+        &[ParameterKind::Post],
+        &[ApiVersion::V1_2_0],
+        false
+    )
+    .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn exports_and_imports_bundle() -> Result<()> {
+    let source_dir = tempdir()?;
+    let target_dir = tempdir()?;
+    let archive_path = source_dir.path().join("bundle.tar");
+
+    let mut manifest = ParameterMap::new();
+    for file_name in ["v28-aaa.params", "v28-aaa.vk"] {
+        manifest.insert(
+            file_name.to_string(),
+            write_param_file(source_dir.path(), file_name)?,
+        );
+    }
+    // Files of the cache which are not selected are not exported.
+    write_param_file(source_dir.path(), "v28-bbb.params")?;
+
+    export_bundle(source_dir.path(), &manifest, &archive_path)?;
+
+    let imported = import_bundle_with_manifests(&archive_path, target_dir.path(), &[&manifest])?;
+    assert_eq!(imported, vec!["v28-aaa.params", "v28-aaa.vk"]);
+    for (file_name, data) in &manifest {
+        let path = target_dir.path().join(file_name);
+        assert_eq!(
+            fs::read(&path)?,
+            fs::read(source_dir.path().join(file_name))?
+        );
+        // Verifying keys are hashed whenever they are loaded, they get no marker.
+        assert_eq!(
+            has_valid_verified_marker(&path, &data.digest),
+            file_name.ends_with(".params")
+        );
+    }
+    assert!(!target_dir.path().join("v28-bbb.params").exists());
+
+    Ok(())
+}
+
+#[test]
+fn imports_metadata_and_replaces_cached_files() -> Result<()> {
+    let source_dir = tempdir()?;
+    let target_dir = tempdir()?;
+    let archive_path = source_dir.path().join("bundle.tar");
+
+    let mut manifest = ParameterMap::new();
+    let params_data = write_param_file(source_dir.path(), "v28-aaa.params")?;
+    manifest.insert("v28-aaa.params".to_string(), params_data.clone());
+    let params = fs::read(source_dir.path().join("v28-aaa.params"))?;
+    let meta = br#"{"sector_size":2048}"#;
+
+    // The metadata is checked against the entry of its parameter file, so the bundle manifest
+    // lists it with that entry.
+    let mut bundle_files = manifest.clone();
+    bundle_files.insert("v28-aaa.meta".to_string(), params_data.clone());
+    write_raw_bundle(
+        &archive_path,
+        &BundleManifest {
+            version: VERSION,
+            files: bundle_files,
+        },
+        &[("v28-aaa.params", &params[..]), ("v28-aaa.meta", &meta[..])],
+    )?;
+
+    // A corrupted file already in the cache is replaced.
+    fs::write(target_dir.path().join("v28-aaa.params"), b"corrupted")?;
+
+    let imported = import_bundle_with_manifests(&archive_path, target_dir.path(), &[&manifest])?;
+    assert_eq!(imported, vec!["v28-aaa.meta", "v28-aaa.params"]);
+    assert_eq!(fs::read(target_dir.path().join("v28-aaa.params"))?, params);
+    assert_eq!(fs::read(target_dir.path().join("v28-aaa.meta"))?, &meta[..]);
+    assert!(has_valid_verified_marker(
+        &target_dir.path().join("v28-aaa.params"),
+        &params_data.digest
+    ));
+    // Nothing but the imported files and the marker is left in the cache.
+    assert_eq!(fs::read_dir(target_dir.path())?.count(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn export_fails_for_invalid_file() -> Result<()> {
+    let source_dir = tempdir()?;
+    let archive_path = source_dir.path().join("bundle.tar");
+
+    let mut manifest = ParameterMap::new();
+    manifest.insert(
+        "v28-aaa.params".to_string(),
+        write_param_file(source_dir.path(), "v28-aaa.params")?,
+    );
+    fs::write(source_dir.path().join("v28-aaa.params"), b"corrupted")?;
+
+    assert!(export_bundle(source_dir.path(), &manifest, &archive_path).is_err());
+
+    // A file which its own manifest does not cover, as its name is not a plain file name, is
+    // rejected instead of exported unverified.
+    fs::create_dir(source_dir.path().join("sub"))?;
+    let mut manifest = ParameterMap::new();
+    manifest.insert(
+        "sub/v28-aaa.params".to_string(),
+        write_param_file(source_dir.path(), "sub/v28-aaa.params")?,
+    );
+    let err = export_bundle(source_dir.path(), &manifest, &archive_path)
+        .expect_err("exported a file which is not in the manifest");
+    assert!(err.to_string().contains("v28-aaa.params"));
+
+    Ok(())
+}
+
+#[test]
+fn import_rejects_invalid_bundles() -> Result<()> {
+    let source_dir = tempdir()?;
+    let target_dir = tempdir()?;
+    let archive_path = source_dir.path().join("bundle.tar");
+
+    let mut manifest = ParameterMap::new();
+    for file_name in ["v28-aaa.params", "v28-aaa.vk"] {
+        manifest.insert(
+            file_name.to_string(),
+            write_param_file(source_dir.path(), file_name)?,
+        );
+    }
+    let params = fs::read(source_dir.path().join("v28-aaa.params"))?;
+    let vk = fs::read(source_dir.path().join("v28-aaa.vk"))?;
+    let bundle_manifest = BundleManifest {
+        version: VERSION,
+        files: manifest.clone(),
+    };
+
+    let invalid_bundles: Vec<(BundleManifest, Vec<(&str, &[u8])>)> = vec![
+        // A corrupted file.
+        (
+            BundleManifest {
+                version: VERSION,
+                files: manifest.clone(),
+            },
+            vec![
+                ("v28-aaa.params", &params[..]),
+                ("v28-aaa.vk", b"corrupted"),
+            ],
+        ),
+        // A missing file.
+        (
+            BundleManifest {
+                version: VERSION,
+                files: manifest.clone(),
+            },
+            vec![("v28-aaa.params", &params[..])],
+        ),
+        // A file which is not in the manifest.
+        (
+            BundleManifest {
+                version: VERSION,
+                files: manifest.clone(),
+            },
+            vec![
+                ("v28-aaa.params", &params[..]),
+                ("v28-aaa.vk", &vk[..]),
+                ("v28-ccc.vk", &vk[..]),
+            ],
+        ),
+        // A file outside of the cache directory.
+        (
+            BundleManifest {
+                version: VERSION,
+                files: manifest.clone(),
+            },
+            vec![("../v28-aaa.params", &params[..]), ("v28-aaa.vk", &vk[..])],
+        ),
+        // A bundle of another version of the parameters.
+        (
+            BundleManifest {
+                version: VERSION + 1,
+                files: manifest.clone(),
+            },
+            vec![("v28-aaa.params", &params[..]), ("v28-aaa.vk", &vk[..])],
+        ),
+    ];
+
+    for (invalid_manifest, entries) in invalid_bundles {
+        write_raw_bundle(&archive_path, &invalid_manifest, &entries)?;
+        assert!(
+            import_bundle_with_manifests(&archive_path, target_dir.path(), &[&manifest]).is_err()
+        );
+        // Nothing is imported, not even the valid files.
+        assert_eq!(fs::read_dir(target_dir.path())?.count(), 0);
+    }
+
+    write_raw_bundle(
+        &archive_path,
+        &bundle_manifest,
+        &[("v28-aaa.params", &params[..]), ("v28-aaa.vk", &vk[..])],
+    )?;
+    import_bundle_with_manifests(&archive_path, target_dir.path(), &[&manifest])?;
+
+    Ok(())
+}
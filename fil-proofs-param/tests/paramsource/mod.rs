@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::Result;
+use blake2b_simd::State as Blake2b;
+use fil_proofs_param::source::{
+    fetch_parameter_file, parse_source_specs, partial_path, HttpSource, LocalSource, ParamSource,
+    SourceSpec,
+};
+use rand::{thread_rng, Rng};
+use reqwest::Url;
+use storage_proofs_core::parameter_cache::ParameterData;
+use tempfile::tempdir;
+
+/// Produces random bytes and their manifest entry.
+fn rand_param_file() -> (Vec<u8>, ParameterData) {
+    let bytes = thread_rng().gen::<[u8; 32]>().to_vec();
+
+    let mut hasher = Blake2b::new();
+    hasher.update(&bytes);
+
+    let data = ParameterData {
+        cid: "".to_string(),
+        digest: hasher.finalize().to_hex()[..32].into(),
+        sector_size: 2048,
+    };
+    (bytes, data)
+}
+
+/// A HTTP server on localhost, serving `files` below `/params/` and supporting range requests.
+struct MirrorServer {
+    url: Url,
+    /// The value of the `Range` header of every request, if any.
+    ranges: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+impl MirrorServer {
+    fn start(files: BTreeMap<String, Vec<u8>>) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = Url::parse(&format!("http://{}/params", listener.local_addr()?))?;
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+
+        let requested_ranges = ranges.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("failed to accept connection");
+                let mut reader = BufReader::new(stream.try_clone().expect("failed to clone"));
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).expect("failed to read");
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("failed to read");
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("range") {
+                            range = Some(value.trim().to_string());
+                        }
+                    }
+                }
+                requested_ranges.lock().unwrap().push(range.clone());
+
+                let file = path
+                    .strip_prefix("/params/")
+                    .and_then(|file_name| files.get(file_name));
+                let (status, headers, body): (_, _, &[u8]) = match (file, range) {
+                    (None, _) => ("404 Not Found", String::new(), &[]),
+                    (Some(bytes), None) => ("200 OK", String::new(), bytes),
+                    (Some(bytes), Some(range)) => {
+                        let start: usize = range
+                            .trim_start_matches("bytes=")
+                            .trim_end_matches('-')
+                            .parse()
+                            .expect("unsupported range");
+                        if start >= bytes.len() {
+                            ("416 Range Not Satisfiable", String::new(), &[])
+                        } else {
+                            let headers = format!(
+                                "Content-Range: bytes {}-{}/{}\r\n",
+                                start,
+                                bytes.len() - 1,
+                                bytes.len()
+                            );
+                            ("206 Partial Content", headers, &bytes[start..])
+                        }
+                    }
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    headers,
+                    body.len()
+                )
+                .and_then(|_| stream.write_all(body))
+                .expect("failed to write response");
+            }
+        });
+
+        Ok(MirrorServer { url, ranges })
+    }
+
+    fn ranges(&self) -> Vec<Option<String>> {
+        self.ranges.lock().unwrap().clone()
+    }
+}
+
+fn http_source(server: &MirrorServer) -> Result<Box<dyn ParamSource>> {
+    Ok(Box::new(HttpSource::new(server.url.clone(), false)?))
+}
+
+#[test]
+fn fetches_from_http_mirror() -> Result<()> {
+    let (bytes, data) = rand_param_file();
+    let server = MirrorServer::start(
+        vec![("v28-aaa.vk".to_string(), bytes.clone())]
+            .into_iter()
+            .collect(),
+    )?;
+    let cache_dir = tempdir()?;
+    let path = cache_dir.path().join("v28-aaa.vk");
+
+    fetch_parameter_file(&[http_source(&server)?], "v28-aaa.vk", &data, &path)?;
+
+    assert_eq!(fs::read(&path)?, bytes);
+    assert!(!partial_path(&path).exists());
+    assert_eq!(server.ranges(), vec![None]);
+
+    Ok(())
+}
+
+#[test]
+fn resumes_partial_http_download() -> Result<()> {
+    let (bytes, data) = rand_param_file();
+    let server = MirrorServer::start(
+        vec![("v28-aaa.vk".to_string(), bytes.clone())]
+            .into_iter()
+            .collect(),
+    )?;
+    let cache_dir = tempdir()?;
+    let path = cache_dir.path().join("v28-aaa.vk");
+    fs::write(partial_path(&path), &bytes[..10])?;
+
+    fetch_parameter_file(&[http_source(&server)?], "v28-aaa.vk", &data, &path)?;
+
+    assert_eq!(fs::read(&path)?, bytes);
+    assert_eq!(server.ranges(), vec![Some("bytes=10-".to_string())]);
+
+    // A complete partial file is only verified and moved into place.
+    fs::remove_file(&path)?;
+    fs::write(partial_path(&path), &bytes)?;
+
+    fetch_parameter_file(&[http_source(&server)?], "v28-aaa.vk", &data, &path)?;
+
+    assert_eq!(fs::read(&path)?, bytes);
+
+    Ok(())
+}
+
+#[test]
+fn rejects_file_with_wrong_digest() -> Result<()> {
+    let (bytes, _) = rand_param_file();
+    let (_, other_data) = rand_param_file();
+    let server = MirrorServer::start(
+        vec![("v28-aaa.vk".to_string(), bytes)]
+            .into_iter()
+            .collect(),
+    )?;
+    let cache_dir = tempdir()?;
+    let path = cache_dir.path().join("v28-aaa.vk");
+
+    assert!(
+        fetch_parameter_file(&[http_source(&server)?], "v28-aaa.vk", &other_data, &path).is_err()
+    );
+
+    assert!(!path.exists());
+    assert!(!partial_path(&path).exists());
+
+    Ok(())
+}
+
+#[test]
+fn falls_back_to_next_source() -> Result<()> {
+    let (bytes, data) = rand_param_file();
+    let server = MirrorServer::start(
+        vec![("v28-aaa.vk".to_string(), bytes.clone())]
+            .into_iter()
+            .collect(),
+    )?;
+    let empty_dir = tempdir()?;
+    let cache_dir = tempdir()?;
+    let path = cache_dir.path().join("v28-aaa.vk");
+
+    let sources: Vec<Box<dyn ParamSource>> = vec![
+        Box::new(LocalSource::new(empty_dir.path().to_path_buf())),
+        http_source(&server)?,
+    ];
+    fetch_parameter_file(&sources, "v28-aaa.vk", &data, &path)?;
+
+    assert_eq!(fs::read(&path)?, bytes);
+
+    // No source has the file.
+    let missing_path = cache_dir.path().join("v28-bbb.vk");
+    assert!(fetch_parameter_file(&sources, "v28-bbb.vk", &data, &missing_path).is_err());
+    assert!(!missing_path.exists());
+    assert_eq!(server.ranges().len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn copies_and_resumes_from_local_dir() -> Result<()> {
+    let (bytes, data) = rand_param_file();
+    let source_dir = tempdir()?;
+    let cache_dir = tempdir()?;
+    fs::write(source_dir.path().join("v28-aaa.vk"), &bytes)?;
+    let sources: Vec<Box<dyn ParamSource>> =
+        vec![Box::new(LocalSource::new(source_dir.path().to_path_buf()))];
+
+    let path = cache_dir.path().join("v28-aaa.vk");
+    fetch_parameter_file(&sources, "v28-aaa.vk", &data, &path)?;
+    assert_eq!(fs::read(&path)?, bytes);
+
+    let resumed_path = cache_dir.path().join("v28-aaa-resumed.vk");
+    fs::copy(
+        source_dir.path().join("v28-aaa.vk"),
+        source_dir.path().join("v28-aaa-resumed.vk"),
+    )?;
+    fs::write(partial_path(&resumed_path), &bytes[..10])?;
+    fetch_parameter_file(&sources, "v28-aaa-resumed.vk", &data, &resumed_path)?;
+    assert_eq!(fs::read(&resumed_path)?, bytes);
+
+    Ok(())
+}
+
+#[test]
+fn parses_source_specs() -> Result<()> {
+    assert_eq!(
+        parse_source_specs("https://mirror.example/params, ipfs,file:///mnt/params,./params")?,
+        vec![
+            SourceSpec::Http(Url::parse("https://mirror.example/params")?),
+            SourceSpec::Ipfs,
+            SourceSpec::Local(Path::new("/mnt/params").to_path_buf()),
+            SourceSpec::Local(Path::new("./params").to_path_buf()),
+        ]
+    );
+    assert!(parse_source_specs("http://[invalid").is_err());
+    assert!(parse_source_specs("")?.is_empty());
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+mod parambundle;
 mod paramfetch;
 mod parampublish;
+mod paramsource;
 mod support;
@@ -0,0 +1,37 @@
+use std::fs::File;
+use std::io::{self, Write};
+
+use anyhow::{Context, Result};
+use clap::{Arg, Command};
+use fil_proofs_tooling::challenge_vectors::{check_challenge_vectors, generate_challenge_vectors};
+
+fn main() -> Result<()> {
+    fil_logger::init();
+
+    let matches = Command::new("gen_challenge_vectors")
+        .version("0.1")
+        .about(
+            "Generates JSON test vectors for the PoSt, PoRep and SnapDeals challenge derivations",
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .help("The file to write the vectors to, defaults to stdout")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let vectors = generate_challenge_vectors()?;
+    check_challenge_vectors(&vectors)?;
+
+    let mut json = serde_json::to_string_pretty(&vectors)?;
+    json.push('\n');
+    match matches.value_of("output") {
+        Some(path) => File::create(path)
+            .and_then(|mut file| file.write_all(json.as_bytes()))
+            .with_context(|| format!("failed to write vectors to {}", path))?,
+        None => io::stdout().write_all(json.as_bytes())?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::fs::read_to_string;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use byte_unit::Byte;
+use clap::{Arg, Command};
+use fil_proofs_tooling::post_planner::{plan_window_post, Calibration};
+use storage_proofs_core::api_version::ApiVersion;
+
+fn main() -> Result<()> {
+    fil_logger::init();
+
+    let matches = Command::new("post_planner")
+        .version("0.1")
+        .about("Predicts the partitions, parameter memory and tree files of a Window PoSt")
+        .arg(
+            Arg::new("sectors")
+                .long("sectors")
+                .required(true)
+                .help("The number of sectors proven")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("size")
+                .long("size")
+                .required(true)
+                .help("The sector size (e.g. 32GiB)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("api_version")
+                .long("api-version")
+                .help("The api_version to use (default: 1.2.0)")
+                .default_value("1.2.0")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("benchy")
+                .long("benchy")
+                .help("The JSON report of `benchy window-post` to estimate timings from")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let sector_count = matches.value_of_t::<usize>("sectors")?;
+    let sector_size = Byte::from_str(matches.value_of_t::<String>("size")?)?.get_bytes() as u64;
+    let api_version = ApiVersion::from_str(&matches.value_of_t::<String>("api_version")?)?;
+    let calibration = match matches.value_of("benchy") {
+        Some(path) => {
+            let report = read_to_string(path)
+                .with_context(|| format!("failed to read benchy report {}", path))?;
+            Some(Calibration::from_benchy_report(&report)?)
+        }
+        None => None,
+    };
+
+    let plan = plan_window_post(sector_count, sector_size, api_version, calibration.as_ref())?;
+
+    let mut json = serde_json::to_string_pretty(&plan)?;
+    json.push('\n');
+    io::stdout().write_all(json.as_bytes())?;
+
+    Ok(())
+}
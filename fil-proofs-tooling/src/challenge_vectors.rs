@@ -0,0 +1,320 @@
+//! Deterministic test vectors for the challenge derivations of PoSt, PoRep and SnapDeals, for
+//! implementations outside of Rust to check themselves against.
+//!
+//! All inputs are part of the vectors. Byte strings, i.e. field elements and seeds, are hex
+//! encoded in their little endian byte order; API versions are encoded as `major.minor.patch`.
+
+use std::convert::TryInto;
+
+use anyhow::{ensure, Context, Result};
+use filecoin_hashers::{poseidon::PoseidonDomain, Domain};
+use serde::{Deserialize, Serialize};
+use storage_proofs_core::api_version::ApiVersion;
+use storage_proofs_porep::stacked::LayerChallenges;
+use storage_proofs_post::fallback::{
+    self, generate_leaf_challenge, generate_sector_challenges, get_challenge_index,
+};
+use storage_proofs_update::Challenges;
+
+const API_VERSIONS: [ApiVersion; 3] = [ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
+
+/// Sector sizes in bytes of the PoSt vectors.
+const POST_SECTOR_SIZES: [u64; 3] = [1 << 11, 1 << 35, 1 << 36];
+/// Sector sizes in nodes of the PoRep and SnapDeals vectors.
+const SECTOR_NODES: [usize; 3] = [1 << 6, 1 << 9, 1 << 30];
+
+const POST_CHALLENGE_COUNT: usize = 10;
+const POREP_CHALLENGE_COUNT: usize = 18;
+
+/// `fallback::generate_sector_challenges`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectorChallengeVector {
+    pub randomness: String,
+    pub prover_id: String,
+    pub sector_set_len: u64,
+    pub challenge_count: usize,
+    pub challenges: Vec<u64>,
+}
+
+/// `fallback::get_challenge_index` and `fallback::generate_leaf_challenge` for every challenge
+/// of the sector at `sector_index`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeafChallengeVector {
+    pub api_version: String,
+    pub sector_size: u64,
+    pub randomness: String,
+    pub sector_id: u64,
+    pub sector_index: usize,
+    pub challenge_count: usize,
+    pub challenge_indexes: Vec<u64>,
+    pub challenges: Vec<u64>,
+}
+
+/// `LayerChallenges::derive` for partition `k`, with `LayerChallenges::new_synthetic` if
+/// `synthetic` is set and `LayerChallenges::new` otherwise.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoRepChallengeVector {
+    pub synthetic: bool,
+    pub sector_nodes: usize,
+    pub replica_id: String,
+    pub comm_r: String,
+    pub seed: String,
+    pub k: u8,
+    pub challenge_count: usize,
+    pub challenges: Vec<usize>,
+}
+
+/// The challenges of a `storage_proofs_update::Challenges` for partition `k`, created with
+/// `Challenges::new`, or with `Challenges::new_poseidon` if `k` is `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateChallengeVector {
+    pub sector_nodes: usize,
+    pub comm_r_new: String,
+    pub k: Option<usize>,
+    pub challenges: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeVectors {
+    pub sector_challenges: Vec<SectorChallengeVector>,
+    pub leaf_challenges: Vec<LeafChallengeVector>,
+    pub porep_challenges: Vec<PoRepChallengeVector>,
+    pub update_challenges: Vec<UpdateChallengeVector>,
+}
+
+/// Generates the vectors, which are the same on every run.
+pub fn generate_challenge_vectors() -> Result<ChallengeVectors> {
+    let mut sector_challenges = Vec::new();
+    for (i, sector_set_len) in [1, 7, 2349].iter().enumerate() {
+        let mut vector = SectorChallengeVector {
+            randomness: to_hex(&input("randomness", i)),
+            prover_id: to_hex(&input("prover_id", i)),
+            sector_set_len: *sector_set_len,
+            challenge_count: POST_CHALLENGE_COUNT,
+            challenges: Vec::new(),
+        };
+        vector.challenges = sector_challenges_of(&vector)?;
+        sector_challenges.push(vector);
+    }
+
+    let mut leaf_challenges = Vec::new();
+    for api_version in &API_VERSIONS {
+        for (i, sector_size) in POST_SECTOR_SIZES.iter().enumerate() {
+            for sector_index in [0, 3] {
+                let mut vector = LeafChallengeVector {
+                    api_version: api_version.to_string(),
+                    sector_size: *sector_size,
+                    randomness: to_hex(&input("randomness", i)),
+                    sector_id: 1000 + i as u64,
+                    sector_index,
+                    challenge_count: POST_CHALLENGE_COUNT,
+                    challenge_indexes: Vec::new(),
+                    challenges: Vec::new(),
+                };
+                let (challenge_indexes, challenges) = leaf_challenges_of(&vector)?;
+                vector.challenge_indexes = challenge_indexes;
+                vector.challenges = challenges;
+                leaf_challenges.push(vector);
+            }
+        }
+    }
+
+    let mut porep_challenges = Vec::new();
+    for synthetic in [false, true] {
+        for (i, sector_nodes) in SECTOR_NODES.iter().enumerate() {
+            for k in [0, 1] {
+                let mut vector = PoRepChallengeVector {
+                    synthetic,
+                    sector_nodes: *sector_nodes,
+                    replica_id: to_hex(&input("replica_id", i)),
+                    comm_r: to_hex(&input("comm_r", i)),
+                    seed: to_hex(&input("seed", i)),
+                    k,
+                    challenge_count: POREP_CHALLENGE_COUNT,
+                    challenges: Vec::new(),
+                };
+                vector.challenges = porep_challenges_of(&vector)?;
+                porep_challenges.push(vector);
+            }
+        }
+    }
+
+    let mut update_challenges = Vec::new();
+    for (i, sector_nodes) in SECTOR_NODES.iter().enumerate() {
+        let partitions = storage_proofs_update::constants::partition_count(*sector_nodes);
+        let mut ks = vec![Some(0), Some(partitions - 1)];
+        ks.dedup();
+        // The poseidon variant derives the challenges of all partitions at once, which are too
+        // many to list for large sectors.
+        if partitions <= 2 {
+            ks.push(None);
+        }
+        for k in ks {
+            let mut vector = UpdateChallengeVector {
+                sector_nodes: *sector_nodes,
+                comm_r_new: to_hex(&input("comm_r_new", i)),
+                k,
+                challenges: Vec::new(),
+            };
+            vector.challenges = update_challenges_of(&vector)?;
+            update_challenges.push(vector);
+        }
+    }
+
+    Ok(ChallengeVectors {
+        sector_challenges,
+        leaf_challenges,
+        porep_challenges,
+        update_challenges,
+    })
+}
+
+/// Re-derives every challenge from the inputs of `vectors` and fails on the first mismatch.
+pub fn check_challenge_vectors(vectors: &ChallengeVectors) -> Result<()> {
+    for (i, vector) in vectors.sector_challenges.iter().enumerate() {
+        ensure!(
+            sector_challenges_of(vector)? == vector.challenges,
+            "sector challenge vector {} does not match",
+            i
+        );
+    }
+    for (i, vector) in vectors.leaf_challenges.iter().enumerate() {
+        ensure!(
+            leaf_challenges_of(vector)?
+                == (vector.challenge_indexes.clone(), vector.challenges.clone()),
+            "leaf challenge vector {} does not match",
+            i
+        );
+    }
+    for (i, vector) in vectors.porep_challenges.iter().enumerate() {
+        ensure!(
+            porep_challenges_of(vector)? == vector.challenges,
+            "porep challenge vector {} does not match",
+            i
+        );
+    }
+    for (i, vector) in vectors.update_challenges.iter().enumerate() {
+        ensure!(
+            update_challenges_of(vector)? == vector.challenges,
+            "update challenge vector {} does not match",
+            i
+        );
+    }
+
+    Ok(())
+}
+
+fn sector_challenges_of(vector: &SectorChallengeVector) -> Result<Vec<u64>> {
+    let randomness = domain_from_hex(&vector.randomness)?;
+    let prover_id = domain_from_hex(&vector.prover_id)?;
+
+    generate_sector_challenges(
+        randomness,
+        vector.challenge_count,
+        vector.sector_set_len,
+        prover_id,
+    )
+}
+
+fn leaf_challenges_of(vector: &LeafChallengeVector) -> Result<(Vec<u64>, Vec<u64>)> {
+    let api_version: ApiVersion = vector.api_version.parse()?;
+    let randomness = domain_from_hex(&vector.randomness)?;
+    let pub_params = fallback::PublicParams {
+        sector_size: vector.sector_size,
+        challenge_count: vector.challenge_count,
+        sector_count: 1,
+        api_version,
+    };
+
+    let challenge_indexes: Vec<u64> = (0..vector.challenge_count)
+        .map(|n| get_challenge_index(api_version, vector.sector_index, vector.challenge_count, n))
+        .collect();
+    let challenges = challenge_indexes
+        .iter()
+        .map(|challenge_index| {
+            generate_leaf_challenge(&pub_params, randomness, vector.sector_id, *challenge_index)
+        })
+        .collect();
+
+    Ok((challenge_indexes, challenges))
+}
+
+fn porep_challenges_of(vector: &PoRepChallengeVector) -> Result<Vec<usize>> {
+    let replica_id = domain_from_hex(&vector.replica_id)?;
+    let comm_r = domain_from_hex(&vector.comm_r)?;
+    let seed = bytes_from_hex(&vector.seed)?;
+    let layer_challenges = if vector.synthetic {
+        LayerChallenges::new_synthetic(1, vector.challenge_count)
+    } else {
+        LayerChallenges::new(1, vector.challenge_count)
+    };
+
+    Ok(layer_challenges.derive(vector.sector_nodes, &replica_id, &comm_r, &seed, vector.k))
+}
+
+fn update_challenges_of(vector: &UpdateChallengeVector) -> Result<Vec<u32>> {
+    let comm_r_new = domain_from_hex(&vector.comm_r_new)?;
+    let challenges = match vector.k {
+        Some(k) => {
+            let partitions = storage_proofs_update::constants::partition_count(vector.sector_nodes);
+            ensure!(k < partitions, "invalid partition {}", k);
+            Challenges::new(vector.sector_nodes, comm_r_new, k)
+        }
+        None => Challenges::new_poseidon(vector.sector_nodes, comm_r_new),
+    };
+
+    Ok(challenges.collect())
+}
+
+/// Returns the `i`-th input named `label`, which is a valid field element.
+fn input(label: &str, i: usize) -> [u8; 32] {
+    let mut bytes: [u8; 32] =
+        *blake2s_simd::blake2s(format!("{}-{}", label, i).as_bytes()).as_array();
+    // Clearing the two most significant bits keeps the value below the field modulus.
+    bytes[31] &= 0x3f;
+    bytes
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn bytes_from_hex(hex: &str) -> Result<[u8; 32]> {
+    ensure!(
+        hex.len() == 64 && hex.is_ascii(),
+        "invalid 32 byte hex string {:?}",
+        hex
+    );
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .with_context(|| format!("invalid hex string {:?}", hex))
+        })
+        .collect::<Result<Vec<u8>>>()?;
+
+    Ok(bytes.try_into().expect("length checked"))
+}
+
+fn domain_from_hex(hex: &str) -> Result<PoseidonDomain> {
+    PoseidonDomain::try_from_bytes(&bytes_from_hex(hex)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge_vectors() {
+        let vectors: ChallengeVectors =
+            serde_json::from_str(include_str!("../vectors/challenges.json"))
+                .expect("failed to parse challenge vectors");
+        check_challenge_vectors(&vectors).expect("challenge vectors do not match");
+
+        let generated = generate_challenge_vectors().expect("failed to generate vectors");
+        assert_eq!(
+            generated, vectors,
+            "vectors/challenges.json is outdated, regenerate it with gen_challenge_vectors"
+        );
+    }
+}
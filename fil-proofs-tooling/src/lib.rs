@@ -2,8 +2,10 @@
 #![warn(clippy::unwrap_used)]
 #![warn(clippy::needless_collect)]
 
+pub mod challenge_vectors;
 pub mod measure;
 pub mod metadata;
+pub mod post_planner;
 pub mod shared;
 pub use measure::{measure, FuncMeasurement};
 pub use metadata::Metadata;
@@ -0,0 +1,459 @@
+//! Capacity planning for Window PoSt.
+//!
+//! A plan states, for a number of sectors of a given size, how many partitions are proven, the
+//! size of their circuits, how large the Groth parameters are and how many files are opened to
+//! generate the vanilla proofs. Timings are only estimated if the plan is calibrated with the
+//! report of a `benchy window-post` run on the same sector size.
+
+use std::fs::metadata;
+
+use anyhow::{ensure, Context, Result};
+use bellperson::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use blstrs::Scalar as Fr;
+use filecoin_proofs::{
+    get_num_partition_for_fallback_post, parameters::window_post_public_params, with_shape,
+    PoStConfig, PoStType, SectorSize, WINDOW_POST_CHALLENGE_COUNT, WINDOW_POST_SECTOR_COUNT,
+};
+use serde::{Deserialize, Serialize};
+use storage_proofs_core::{
+    api_version::ApiVersion,
+    compound_proof::CompoundProof,
+    merkle::{get_base_tree_count, MerkleTreeTrait},
+    parameter_cache::{get_parameter_data, parameter_cache_params_path, CacheableParameters},
+};
+use storage_proofs_post::fallback::{FallbackPoSt, FallbackPoStCircuit, FallbackPoStCompound};
+
+/// Size of an uncompressed G1 point in a parameter file.
+const G1_BYTES: u64 = 96;
+/// Size of an uncompressed G2 point in a parameter file.
+const G2_BYTES: u64 = 192;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WindowPostPlan {
+    pub sector_count: usize,
+    pub sector_size: u64,
+    pub api_version: String,
+    pub sectors_per_partition: usize,
+    pub partitions: usize,
+    pub constraints_per_partition: usize,
+    pub public_inputs_per_partition: usize,
+    /// The cache identifier of the Groth parameters.
+    pub parameter_id: String,
+    /// Whether the parameters are listed in `parameters.json`.
+    pub published: bool,
+    /// Upper bound of the size of the parameter file, derived from the circuit. The parameters
+    /// are memory mapped, so this is also the memory they take up once fully paged in.
+    pub estimated_params_bytes: u64,
+    /// The size of the parameter file, if it is in the local parameter cache.
+    pub cached_params_bytes: Option<u64>,
+    /// The number of tree_r_last files opened over all sectors.
+    pub tree_files_opened: usize,
+    /// The number of replica files opened over all sectors.
+    pub replica_files_opened: usize,
+    pub estimated_prove_time_ms: Option<u64>,
+    pub estimated_verify_time_ms: Option<u64>,
+}
+
+/// Timings of a single partition, taken from the report of `benchy window-post`, which proves
+/// one sector in one partition. A partition is padded to its full number of sectors, so every
+/// partition is assumed to take this long, regardless of how many sectors it proves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    pub sector_size: u64,
+    pub prove_partition_ms: u64,
+    pub verify_partition_ms: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BenchyReport {
+    benchmarks: BenchyWindowPost,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BenchyWindowPost {
+    inputs: BenchyInputs,
+    outputs: BenchyOutputs,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BenchyInputs {
+    sector_size: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BenchyOutputs {
+    gen_window_post_wall_time_ms: u64,
+    verify_window_post_wall_time_ms: u64,
+}
+
+impl Calibration {
+    /// Reads the calibration from the JSON report printed by `benchy window-post`.
+    pub fn from_benchy_report(json: &str) -> Result<Self> {
+        let report: BenchyReport =
+            serde_json::from_str(json).context("invalid benchy window-post report")?;
+        let BenchyWindowPost { inputs, outputs } = report.benchmarks;
+        ensure!(
+            outputs.gen_window_post_wall_time_ms > 0,
+            "benchy report has no window post timing"
+        );
+
+        Ok(Calibration {
+            sector_size: inputs.sector_size,
+            prove_partition_ms: outputs.gen_window_post_wall_time_ms,
+            verify_partition_ms: outputs.verify_window_post_wall_time_ms,
+        })
+    }
+}
+
+/// The dimensions of a circuit which determine the size of its Groth parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitShape {
+    pub constraints: usize,
+    /// Public inputs, including the constant one.
+    pub inputs: usize,
+    pub aux: usize,
+}
+
+impl CircuitShape {
+    pub fn of<C: Circuit<Fr>>(circuit: C) -> Result<Self> {
+        let mut cs = ShapeCS::default();
+        circuit.synthesize(&mut cs)?;
+
+        Ok(cs.0)
+    }
+
+    /// The size of the parameter file generated for the circuit, as written by
+    /// `groth16::Parameters::write`. Query points at infinity are not written, so the actual
+    /// file may be smaller.
+    pub fn groth_params_bytes(&self) -> u64 {
+        let inputs = self.inputs as u64;
+        let aux = self.aux as u64;
+        // The generator adds a constraint per input and evaluates over a power of two domain.
+        let domain = (self.constraints as u64 + inputs).next_power_of_two();
+
+        let vk = 3 * G1_BYTES + 3 * G2_BYTES + 4 + inputs * G1_BYTES;
+        let h = 4 + (domain - 1) * G1_BYTES;
+        let l = 4 + aux * G1_BYTES;
+        let a = 4 + (inputs + aux) * G1_BYTES;
+        let b_g1 = 4 + (inputs + aux) * G1_BYTES;
+        let b_g2 = 4 + (inputs + aux) * G2_BYTES;
+
+        vk + h + l + a + b_g1 + b_g2
+    }
+}
+
+/// Counts constraints and variables without evaluating any of them.
+struct ShapeCS(CircuitShape);
+
+impl Default for ShapeCS {
+    fn default() -> Self {
+        ShapeCS(CircuitShape {
+            constraints: 0,
+            inputs: 1,
+            aux: 0,
+        })
+    }
+}
+
+impl ConstraintSystem<Fr> for ShapeCS {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.0.aux += 1;
+        Ok(Variable::new_unchecked(Index::Aux(self.0.aux - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.0.inputs += 1;
+        Ok(Variable::new_unchecked(Index::Input(self.0.inputs - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, _: LA, _: LB, _: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Fr>) -> LinearCombination<Fr>,
+        LB: FnOnce(LinearCombination<Fr>) -> LinearCombination<Fr>,
+        LC: FnOnce(LinearCombination<Fr>) -> LinearCombination<Fr>,
+    {
+        self.0.constraints += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// Plans a Window PoSt over `sector_count` sectors of `sector_size` bytes.
+///
+/// Fails if there is no Window PoSt for `sector_size`, or if `calibration` was measured on a
+/// different sector size.
+pub fn plan_window_post(
+    sector_count: usize,
+    sector_size: u64,
+    api_version: ApiVersion,
+    calibration: Option<&Calibration>,
+) -> Result<WindowPostPlan> {
+    ensure!(sector_count > 0, "sector count must be positive");
+    let sectors_per_partition = *WINDOW_POST_SECTOR_COUNT
+        .read()
+        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
+        .get(&sector_size)
+        .with_context(|| format!("unsupported sector size: {}", sector_size))?;
+    if let Some(calibration) = calibration {
+        ensure!(
+            calibration.sector_size == sector_size,
+            "calibration is for sector size {}, not {}",
+            calibration.sector_size,
+            sector_size
+        );
+    }
+
+    let post_config = PoStConfig {
+        sector_size: SectorSize(sector_size),
+        challenge_count: WINDOW_POST_CHALLENGE_COUNT,
+        sector_count: sectors_per_partition,
+        typ: PoStType::Window,
+        priority: false,
+        api_version,
+    };
+    let partitions = get_num_partition_for_fallback_post(&post_config, sector_count);
+    let (shape, parameter_id, tree_count) =
+        with_shape!(sector_size, window_post_circuit, &post_config)?;
+
+    let cached_params_bytes = metadata(parameter_cache_params_path(&parameter_id))
+        .ok()
+        .map(|meta| meta.len());
+
+    Ok(WindowPostPlan {
+        sector_count,
+        sector_size,
+        api_version: api_version.to_string(),
+        sectors_per_partition,
+        partitions,
+        constraints_per_partition: shape.constraints,
+        public_inputs_per_partition: shape.inputs,
+        published: get_parameter_data(&parameter_id).is_some(),
+        parameter_id,
+        estimated_params_bytes: shape.groth_params_bytes(),
+        cached_params_bytes,
+        tree_files_opened: sector_count * tree_count,
+        replica_files_opened: sector_count,
+        estimated_prove_time_ms: calibration
+            .map(|calibration| calibration.prove_partition_ms * partitions as u64),
+        estimated_verify_time_ms: calibration
+            .map(|calibration| calibration.verify_partition_ms * partitions as u64),
+    })
+}
+
+fn window_post_circuit<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+) -> Result<(CircuitShape, String, usize)> {
+    let pub_params = window_post_public_params::<Tree>(post_config)?;
+    let parameter_id = <FallbackPoStCompound<Tree> as CacheableParameters<
+        FallbackPoStCircuit<Tree>,
+        _,
+    >>::cache_identifier(&pub_params);
+
+    // Every sector adds the same gadgets to a partition, so a partition of one and one of two
+    // sectors determine the shape of a full one, without synthesizing thousands of sectors.
+    let one = blank_circuit_shape::<Tree>(post_config, 1)?;
+    let shape = match post_config.sector_count {
+        0 | 1 => one,
+        sector_count => {
+            let two = blank_circuit_shape::<Tree>(post_config, 2)?;
+            let extrapolate = |one: usize, two: usize| one + (sector_count - 1) * (two - one);
+            CircuitShape {
+                constraints: extrapolate(one.constraints, two.constraints),
+                inputs: extrapolate(one.inputs, two.inputs),
+                aux: extrapolate(one.aux, two.aux),
+            }
+        }
+    };
+
+    Ok((shape, parameter_id, get_base_tree_count::<Tree>()))
+}
+
+fn blank_circuit_shape<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    sector_count: usize,
+) -> Result<CircuitShape> {
+    let post_config = PoStConfig {
+        sector_count,
+        ..post_config.clone()
+    };
+    let pub_params = window_post_public_params::<Tree>(&post_config)?;
+
+    CircuitShape::of(<FallbackPoStCompound<Tree> as CompoundProof<
+        FallbackPoSt<'_, Tree>,
+        FallbackPoStCircuit<Tree>,
+    >>::blank_circuit(&pub_params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bellperson::groth16::generate_random_parameters;
+    use blstrs::Bls12;
+    use filecoin_proofs::SECTOR_SIZE_2_KIB;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+    use storage_proofs_core::TEST_SEED;
+
+    #[test]
+    fn test_plan_window_post_2kib() {
+        let calibration = Calibration {
+            sector_size: SECTOR_SIZE_2_KIB,
+            prove_partition_ms: 1500,
+            verify_partition_ms: 20,
+        };
+        let plan = plan_window_post(5, SECTOR_SIZE_2_KIB, ApiVersion::V1_2_0, Some(&calibration))
+            .expect("planning failed");
+
+        assert_eq!(plan.sectors_per_partition, 2);
+        assert_eq!(plan.partitions, 3);
+        assert!(plan.published);
+        assert_eq!(plan.tree_files_opened, 5);
+        assert_eq!(plan.replica_files_opened, 5);
+        assert_eq!(plan.estimated_prove_time_ms, Some(4500));
+        assert_eq!(plan.estimated_verify_time_ms, Some(60));
+
+        let post_config = PoStConfig {
+            sector_size: SectorSize(SECTOR_SIZE_2_KIB),
+            challenge_count: WINDOW_POST_CHALLENGE_COUNT,
+            sector_count: 2,
+            typ: PoStType::Window,
+            priority: false,
+            api_version: ApiVersion::V1_2_0,
+        };
+        let shape = with_shape!(SECTOR_SIZE_2_KIB, window_post_circuit, &post_config)
+            .expect("failed to count constraints")
+            .0;
+        assert_eq!(plan.constraints_per_partition, shape.constraints);
+        assert_eq!(plan.estimated_params_bytes, shape.groth_params_bytes());
+
+        // The extrapolated shape of a partition matches the synthesized one.
+        let post_config = PoStConfig {
+            sector_count: 5,
+            ..post_config
+        };
+        assert_eq!(
+            with_shape!(SECTOR_SIZE_2_KIB, window_post_circuit, &post_config)
+                .expect("failed to count constraints")
+                .0,
+            with_shape!(SECTOR_SIZE_2_KIB, blank_circuit_shape, &post_config, 5)
+                .expect("failed to count constraints")
+        );
+
+        let other_calibration = Calibration {
+            sector_size: 2 * SECTOR_SIZE_2_KIB,
+            ..calibration
+        };
+        assert!(plan_window_post(
+            5,
+            SECTOR_SIZE_2_KIB,
+            ApiVersion::V1_2_0,
+            Some(&other_calibration)
+        )
+        .is_err());
+        assert!(plan_window_post(5, 1000, ApiVersion::V1_2_0, None).is_err());
+    }
+
+    #[test]
+    fn test_calibration_from_benchy_report() {
+        let report = r#"{
+            "git": { "hash": "abc", "date": "2023-01-01T00:00:00Z" },
+            "system": {},
+            "benchmarks": {
+                "inputs": { "sector-size": 2048 },
+                "outputs": {
+                    "seal-commit-phase2-wall-time-ms": 300,
+                    "gen-window-post-cpu-time-ms": 2000,
+                    "gen-window-post-wall-time-ms": 1500,
+                    "verify-window-post-cpu-time-ms": 30,
+                    "verify-window-post-wall-time-ms": 20
+                }
+            }
+        }"#;
+        assert_eq!(
+            Calibration::from_benchy_report(report).expect("failed to parse report"),
+            Calibration {
+                sector_size: 2048,
+                prove_partition_ms: 1500,
+                verify_partition_ms: 20,
+            }
+        );
+
+        assert!(Calibration::from_benchy_report(&report.replace("1500", "0")).is_err());
+        assert!(Calibration::from_benchy_report("{}").is_err());
+    }
+
+    #[test]
+    fn test_groth_params_bytes() {
+        // A path of a small tree keeps generating its parameters fast.
+        type Tree = storage_proofs_core::merkle::LCTree<
+            filecoin_hashers::poseidon::PoseidonHasher,
+            typenum::U8,
+            typenum::U0,
+            typenum::U0,
+        >;
+        let post_config = PoStConfig {
+            sector_size: SectorSize(SECTOR_SIZE_2_KIB),
+            challenge_count: 1,
+            sector_count: 1,
+            typ: PoStType::Window,
+            priority: false,
+            api_version: ApiVersion::V1_2_0,
+        };
+        let pub_params =
+            window_post_public_params::<Tree>(&post_config).expect("failed to get public params");
+        let blank_circuit = || {
+            <FallbackPoStCompound<Tree> as CompoundProof<
+                FallbackPoSt<'_, Tree>,
+                FallbackPoStCircuit<Tree>,
+            >>::blank_circuit(&pub_params)
+        };
+        let shape = CircuitShape::of(blank_circuit()).expect("failed to count constraints");
+
+        let rng = &mut XorShiftRng::from_seed(TEST_SEED);
+        let params = generate_random_parameters::<Bls12, _, _>(blank_circuit(), rng)
+            .expect("failed to generate parameters");
+        let mut bytes = Vec::new();
+        params
+            .write(&mut bytes)
+            .expect("failed to write parameters");
+
+        assert!(bytes.len() as u64 <= shape.groth_params_bytes());
+        assert_eq!(
+            params.h.len() as u64,
+            (shape.constraints as u64 + shape.inputs as u64).next_power_of_two() - 1
+        );
+        assert_eq!(params.l.len(), shape.aux);
+        assert_eq!(params.vk.ic.len(), shape.inputs);
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::{Cursor, Read};
+use std::io::{Cursor, Read, Write};
 use std::time::Duration;
 
 use criterion::{criterion_group, criterion_main, Criterion, Throughput};
@@ -6,7 +6,7 @@ use filecoin_proofs::{
     add_piece, get_seal_inputs, PaddedBytesAmount, PoRepConfig, SectorShape2KiB,
     UnpaddedBytesAmount, SECTOR_SIZE_2_KIB,
 };
-use fr32::Fr32Reader;
+use fr32::{Fr32Reader, Fr32Writer};
 use rand::{thread_rng, Rng};
 use storage_proofs_core::{api_version::ApiVersion, is_legacy_porep_id};
 
@@ -65,6 +65,28 @@ fn preprocessing_benchmark(c: &mut Criterion) {
             .sample_size(10)
             .throughput(Throughput::Bytes(size as u64))
             .warm_up_time(Duration::from_secs(1));
+
+        group
+            .bench_function(format!("write_unpadded-{}", size), |b| {
+                let mut padded = Vec::new();
+                Fr32Reader::new(Cursor::new(random_data(size)))
+                    .read_to_end(&mut padded)
+                    .expect("in memory read error");
+                let mut buf = Vec::with_capacity(size);
+
+                start_profile(&format!("write_unpadded_{}", size));
+                b.iter(|| {
+                    let mut writer = Fr32Writer::new(&mut buf);
+                    writer.write_all(&padded).expect("in memory write error");
+                    writer.finish().expect("in memory write error");
+                    assert!(buf.len() >= size);
+                    buf.clear();
+                });
+                stop_profile();
+            })
+            .sample_size(10)
+            .throughput(Throughput::Bytes(size as u64))
+            .warm_up_time(Duration::from_secs(1));
     }
 
     group.finish();
@@ -11,6 +11,7 @@ use storage_proofs_porep::stacked::StackedDrg;
 
 use crate::{
     constants::DefaultPieceHasher,
+    error::Error,
     types::{Commitment, PoRepConfig},
 };
 
@@ -47,11 +48,11 @@ pub fn fauxrep_aux<R: Rng, S: AsRef<Path>, T: AsRef<Path>, Tree: 'static + Merkl
 
     let p_aux_path = cache_path.as_ref().join(CacheKey::PAux.to_string());
     let mut f_p_aux = File::create(&p_aux_path)
-        .with_context(|| format!("could not create file p_aux={:?}", p_aux_path))?;
+        .with_context(|| Error::Io(format!("could not create file p_aux={:?}", p_aux_path)))?;
     let p_aux_bytes = serialize(&p_aux)?;
     f_p_aux
         .write_all(&p_aux_bytes)
-        .with_context(|| format!("could not write to file p_aux={:?}", p_aux_path))?;
+        .with_context(|| Error::Io(format!("could not write to file p_aux={:?}", p_aux_path)))?;
 
     let mut commitment = [0u8; 32];
     commitment[..].copy_from_slice(&comm_r.into_bytes()[..]);
@@ -71,11 +72,11 @@ pub fn fauxrep2<R: AsRef<Path>, S: AsRef<Path>, Tree: 'static + MerkleTreeTrait>
 
     let p_aux_path = cache_path.as_ref().join(CacheKey::PAux.to_string());
     let mut f_p_aux = File::create(&p_aux_path)
-        .with_context(|| format!("could not create file p_aux={:?}", p_aux_path))?;
+        .with_context(|| Error::Io(format!("could not create file p_aux={:?}", p_aux_path)))?;
     let p_aux_bytes = serialize(&p_aux)?;
     f_p_aux
         .write_all(&p_aux_bytes)
-        .with_context(|| format!("could not write to file p_aux={:?}", p_aux_path))?;
+        .with_context(|| Error::Io(format!("could not write to file p_aux={:?}", p_aux_path)))?;
 
     let mut commitment = [0u8; 32];
     commitment[..].copy_from_slice(&comm_r.into_bytes()[..]);
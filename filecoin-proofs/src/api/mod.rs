@@ -1,22 +1,24 @@
 use std::collections::BTreeMap;
 use std::fs::{self, File, OpenOptions};
-use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 
 use anyhow::{ensure, Context, Result};
 use bincode::deserialize;
 use filecoin_hashers::{sha256::Sha256Hasher, Hasher};
-use fr32::{write_unpadded, Fr32Reader};
+use fr32::{bytes_into_fr, fr_into_bytes, Fr32Reader, Fr32Writer};
+use iowrap::{Pos, ReadMany};
 use log::{info, trace};
 use memmap2::MmapOptions;
 use merkletree::store::{DiskStore, LevelCacheStore, StoreConfig};
 use storage_proofs_core::{
     cache_key::CacheKey,
+    crypto::sloth,
     measurements::{measure_op, Operation},
     merkle::get_base_tree_count,
     pieces::generate_piece_commitment_bytes_from_source,
     sector::SectorId,
-    util::default_rows_to_discard,
+    util::{default_rows_to_discard, NODE_SIZE},
 };
 use storage_proofs_porep::{
     stacked::{generate_replica_id, PersistentAux, StackedDrg, TemporaryAux},
@@ -31,17 +33,20 @@ use crate::{
         DefaultBinaryTree, DefaultOctTree, DefaultPieceDomain, DefaultPieceHasher,
         MINIMUM_RESERVED_BYTES_FOR_PIECE_IN_FULLY_ALIGNED_SECTOR as MINIMUM_PIECE_SIZE,
     },
+    error::Error,
     parameters::public_params,
-    pieces::{get_piece_alignment, sum_piece_bytes_with_alignment},
+    pieces::{get_piece_alignment, join_and_pad_piece_infos, sum_piece_bytes_with_alignment},
     types::{
-        Commitment, MerkleTreeTrait, PaddedBytesAmount, PieceInfo, PoRepConfig, PrivateReplicaInfo,
-        ProverId, SealPreCommitPhase1Output, Ticket, UnpaddedByteIndex, UnpaddedBytesAmount,
+        unseal_key_path, CacheManifest, Commitment, MerkleTreeTrait, PaddedBytesAmount, PieceInfo,
+        PoRepConfig, PrivateReplicaInfo, ProverId, SealPreCommitPhase1Output, SectorCacheReport,
+        Ticket, UnpaddedByteIndex, UnpaddedBytesAmount, UnsealKeyReport, UnsealKeyRetention,
     },
 };
 
 mod fake_seal;
 mod post_util;
 mod seal;
+mod sealing_job;
 mod update;
 mod util;
 mod window_post;
@@ -50,6 +55,7 @@ mod winning_post;
 pub use fake_seal::*;
 pub use post_util::*;
 pub use seal::*;
+pub use sealing_job::*;
 pub use update::*;
 pub use util::*;
 pub use window_post::*;
@@ -63,14 +69,16 @@ pub fn clear_cache<Tree: MerkleTreeTrait>(cache_dir: &Path) -> Result<()> {
 
     let mut t_aux: TemporaryAux<Tree, Sha256Hasher> = {
         let f_aux_path = cache_dir.to_path_buf().join(CacheKey::TAux.to_string());
-        let aux_bytes = fs::read(&f_aux_path)
-            .with_context(|| format!("could not read from path={:?}", f_aux_path))?;
+        let aux_bytes = fs::read(&f_aux_path).with_context(|| {
+            Error::CacheCorrupted(format!("could not read from path={:?}", f_aux_path))
+        })?;
 
         deserialize(&aux_bytes)
     }?;
 
     t_aux.set_cache_path(cache_dir);
-    let result = TemporaryAux::<Tree, DefaultPieceHasher>::clear_temp(t_aux);
+    let result = TemporaryAux::<Tree, DefaultPieceHasher>::clear_temp(t_aux)
+        .and_then(|_| CacheManifest::prune(cache_dir));
 
     info!("clear_cache:finish");
 
@@ -98,14 +106,16 @@ pub fn clear_layer_data<Tree: MerkleTreeTrait>(cache_dir: &Path) -> Result<()> {
 
     let mut t_aux: TemporaryAux<Tree, Sha256Hasher> = {
         let f_aux_path = cache_dir.to_path_buf().join(CacheKey::TAux.to_string());
-        let aux_bytes = fs::read(&f_aux_path)
-            .with_context(|| format!("could not read from path={:?}", f_aux_path))?;
+        let aux_bytes = fs::read(&f_aux_path).with_context(|| {
+            Error::CacheCorrupted(format!("could not read from path={:?}", f_aux_path))
+        })?;
 
         deserialize(&aux_bytes)
     }?;
 
     t_aux.set_cache_path(cache_dir);
-    let result = TemporaryAux::<Tree, DefaultPieceHasher>::clear_layer_data(&t_aux);
+    let result = TemporaryAux::<Tree, DefaultPieceHasher>::clear_layer_data(&t_aux)
+        .and_then(|_| CacheManifest::prune(cache_dir));
 
     info!("clear_layer_data:finish");
 
@@ -118,14 +128,16 @@ pub fn clear_synthetic_proofs<Tree: MerkleTreeTrait>(cache_dir: &Path) -> Result
 
     let mut t_aux: TemporaryAux<Tree, Sha256Hasher> = {
         let f_aux_path = cache_dir.to_path_buf().join(CacheKey::TAux.to_string());
-        let aux_bytes = fs::read(&f_aux_path)
-            .with_context(|| format!("could not read from path={:?}", f_aux_path))?;
+        let aux_bytes = fs::read(&f_aux_path).with_context(|| {
+            Error::CacheCorrupted(format!("could not read from path={:?}", f_aux_path))
+        })?;
 
         deserialize(&aux_bytes)
     }?;
 
     t_aux.set_cache_path(cache_dir);
-    let result = TemporaryAux::<Tree, DefaultPieceHasher>::clear_synthetic_proofs(&t_aux);
+    let result = TemporaryAux::<Tree, DefaultPieceHasher>::clear_synthetic_proofs(&t_aux)
+        .and_then(|_| CacheManifest::prune(cache_dir));
 
     info!("clear_synthetic_proofs:finish");
 
@@ -164,8 +176,12 @@ pub fn get_unsealed_range<T: Into<PathBuf> + AsRef<Path>, Tree: 'static + Merkle
 ) -> Result<UnpaddedBytesAmount> {
     info!("get_unsealed_range:start");
 
-    let f_out = File::create(&output_path)
-        .with_context(|| format!("could not create output_path={:?}", output_path.as_ref()))?;
+    let f_out = File::create(&output_path).with_context(|| {
+        Error::Io(format!(
+            "could not create output_path={:?}",
+            output_path.as_ref()
+        ))
+    })?;
 
     let buf_f_out = BufWriter::new(f_out);
 
@@ -223,7 +239,10 @@ where
     Tree: 'static + MerkleTreeTrait,
 {
     info!("unseal_range:start");
-    ensure!(comm_d != [0; 32], "Invalid all zero commitment (comm_d)");
+    ensure!(
+        comm_d != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_d)".into())
+    );
 
     let comm_d =
         as_safe_commitment::<<DefaultPieceHasher as Hasher>::Domain, _>(&comm_d, "comm_d")?;
@@ -290,7 +309,10 @@ where
     Tree: 'static + MerkleTreeTrait,
 {
     info!("unseal_range_mapped:start");
-    ensure!(comm_d != [0; 32], "Invalid all zero commitment (comm_d)");
+    ensure!(
+        comm_d != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_d)".into())
+    );
 
     let comm_d =
         as_safe_commitment::<<DefaultPieceHasher as Hasher>::Domain, _>(&comm_d, "comm_d")?;
@@ -380,13 +402,185 @@ where
     // If the call to `extract_range` was successful, the `unsealed` vector must
     // have a length which equals `num_bytes_padded`. The byte at its 0-index
     // byte will be the the byte at index `offset_padded` in the sealed sector.
-    let written = write_unpadded(unsealed, &mut unsealed_output, 0, num_bytes.into())
-        .context("write_unpadded failed")?;
-
-    let amount = UnpaddedBytesAmount(written as u64);
+    let mut fr32_writer = Fr32Writer::new(&mut unsealed_output);
+    fr32_writer
+        .write_all(unsealed)
+        .and_then(|_| fr32_writer.finish())
+        .context("unpadding failed")?;
 
     trace!("unseal_range_inner:finish");
-    Ok(amount)
+    Ok(num_bytes)
+}
+
+/// Retains the key of the sector in `cache_path`, i.e. the labels of its last layer, so that
+/// `unseal_range_with_key` can unseal it without relabeling. Retaining is opt-in and has to be
+/// done while the layer data still exists, i.e. after `seal_pre_commit_phase2` and before
+/// `clear_layer_data`. The retained key is not removed by any of the `clear_*` functions.
+///
+/// # Arguments
+///
+/// * `porep_config` - porep configuration containing the sector size.
+/// * `cache_path` - path to the directory in which the sector data's Merkle Tree is written.
+/// * `retention` - whether to link or copy the last layer labels.
+pub fn retain_unseal_key<Tree: 'static + MerkleTreeTrait>(
+    porep_config: &PoRepConfig,
+    cache_path: &Path,
+    retention: UnsealKeyRetention,
+) -> Result<UnsealKeyReport> {
+    info!("retain_unseal_key:start");
+
+    let mut t_aux: TemporaryAux<Tree, DefaultPieceHasher> = {
+        let f_aux_path = cache_path.to_path_buf().join(CacheKey::TAux.to_string());
+        let aux_bytes = fs::read(&f_aux_path).with_context(|| {
+            Error::CacheCorrupted(format!("could not read from path={:?}", f_aux_path))
+        })?;
+
+        deserialize(&aux_bytes)
+    }?;
+    t_aux.set_cache_path(cache_path);
+
+    let last_layer = t_aux
+        .labels
+        .labels
+        .last()
+        .context("t_aux contains no layers")?;
+    let last_layer_path = StoreConfig::data_path(&last_layer.path, &last_layer.id);
+
+    let key_bytes = u64::from(porep_config.padded_bytes_amount());
+    let layer_bytes = fs::metadata(&last_layer_path)
+        .with_context(|| {
+            Error::CacheCorrupted(format!(
+                "could not read last layer labels at path={:?}",
+                last_layer_path
+            ))
+        })?
+        .len();
+    ensure!(
+        layer_bytes == key_bytes,
+        Error::CacheCorrupted(format!(
+            "last layer labels at path={:?} have {} bytes, expected {}",
+            last_layer_path, layer_bytes, key_bytes
+        ))
+    );
+
+    // Link or copy to a tmp file first, so that an existing key is replaced atomically.
+    let key_path = unseal_key_path(cache_path);
+    let tmp_path = key_path.with_extension("tmp");
+    if tmp_path.exists() {
+        fs::remove_file(&tmp_path)?;
+    }
+    let additional_bytes = match retention {
+        UnsealKeyRetention::LastLayer => {
+            fs::hard_link(&last_layer_path, &tmp_path).with_context(|| {
+                Error::Io(format!(
+                    "could not link {:?} to {:?}",
+                    last_layer_path, tmp_path
+                ))
+            })?;
+            0
+        }
+        UnsealKeyRetention::KeyFile => {
+            fs::copy(&last_layer_path, &tmp_path).with_context(|| {
+                Error::Io(format!(
+                    "could not copy {:?} to {:?}",
+                    last_layer_path, tmp_path
+                ))
+            })?;
+            key_bytes
+        }
+    };
+    fs::rename(&tmp_path, &key_path)
+        .with_context(|| Error::Io(format!("could not rename {:?}", tmp_path)))?;
+
+    info!("retain_unseal_key:finish");
+
+    Ok(UnsealKeyReport {
+        path: key_path,
+        retention,
+        key_bytes,
+        additional_bytes,
+    })
+}
+
+/// Unseals the bytes of a piece like `unseal_range`, but decodes them with the key retained
+/// by `retain_unseal_key` instead of regenerating it. Only the nodes covering the requested
+/// range are read from `sealed_sector` and the key, each is decoded with a single field
+/// subtraction.
+///
+/// # Arguments
+///
+/// * `porep_config` - porep configuration containing the sector size.
+/// * `cache_path` - path to the directory containing the retained key.
+/// * `sealed_sector` - a byte source from which we read sealed sector data.
+/// * `unsealed_output` - a byte sink to which we write unsealed, un-bit-padded sector bytes.
+/// * `offset` - the byte index in the unsealed sector of the first byte that we want to read.
+/// * `num_bytes` - the number of bytes that we want to read.
+pub fn unseal_range_with_key<P, R, W>(
+    porep_config: &PoRepConfig,
+    cache_path: P,
+    mut sealed_sector: R,
+    mut unsealed_output: W,
+    offset: UnpaddedByteIndex,
+    num_bytes: UnpaddedBytesAmount,
+) -> Result<UnpaddedBytesAmount>
+where
+    P: AsRef<Path>,
+    R: Read + Seek,
+    W: Write,
+{
+    info!("unseal_range_with_key:start");
+
+    let key_path = unseal_key_path(&cache_path);
+    let mut key = File::open(&key_path).with_context(|| {
+        Error::CacheCorrupted(format!("could not open unseal key={:?}", key_path))
+    })?;
+    let sector_bytes = u64::from(porep_config.padded_bytes_amount());
+    ensure!(
+        key.metadata()?.len() == sector_bytes,
+        Error::CacheCorrupted(format!(
+            "unseal key={:?} does not match the sector size",
+            key_path
+        ))
+    );
+
+    let offset_padded: PaddedBytesAmount = UnpaddedBytesAmount::from(offset).into();
+    let num_bytes_padded: PaddedBytesAmount = num_bytes.into();
+    let start = u64::from(offset_padded);
+    let end = start + u64::from(num_bytes_padded);
+    ensure!(end <= sector_bytes, "range exceeds the sector size");
+
+    // Decode all nodes which overlap with the requested range.
+    let node_size = NODE_SIZE as u64;
+    let nodes_start = start / node_size * node_size;
+    let nodes_end = end + (node_size - end % node_size) % node_size;
+    let len = (nodes_end - nodes_start) as usize;
+
+    let mut data = vec![0u8; len];
+    sealed_sector.seek(SeekFrom::Start(nodes_start))?;
+    sealed_sector
+        .read_exact(&mut data)
+        .context("could not read sealed sector")?;
+
+    let mut key_data = vec![0u8; len];
+    key.seek(SeekFrom::Start(nodes_start))?;
+    key.read_exact(&mut key_data)
+        .with_context(|| Error::Io(format!("could not read unseal key={:?}", key_path)))?;
+
+    for (node, key_node) in data.chunks_mut(NODE_SIZE).zip(key_data.chunks(NODE_SIZE)) {
+        let key_fr = bytes_into_fr(key_node)?;
+        let encoded_fr = bytes_into_fr(node)?;
+        node.copy_from_slice(&fr_into_bytes(&sloth::decode(&key_fr, &encoded_fr)));
+    }
+
+    let unsealed = &data[(start - nodes_start) as usize..(end - nodes_start) as usize];
+    let mut fr32_writer = Fr32Writer::new(&mut unsealed_output);
+    fr32_writer
+        .write_all(unsealed)
+        .and_then(|_| fr32_writer.finish())
+        .context("unpadding failed")?;
+
+    info!("unseal_range_with_key:finish");
+    Ok(num_bytes)
 }
 
 /// Generates a piece commitment for the provided byte source. Returns an error
@@ -496,6 +690,102 @@ where
     result
 }
 
+/// The padded size of the chunks in which `add_piece_unsized` reads and commits a piece.
+const UNSIZED_PIECE_CHUNK_SIZE: usize = 1 << 20;
+
+/// Writes bytes from `source` to `target`, adding bit-padding ("preprocessing"), for a piece
+/// whose size is not known up front. Once `source` reaches EOF, the smallest power of two piece
+/// size containing the data is chosen and the piece is padded with zeros up to it. Returns a
+/// tuple containing the `PieceInfo` of the padded piece and the number of bytes read from
+/// `source`.
+///
+/// WARNING: As the piece size is only known at EOF, this function neither prepends nor appends
+/// alignment bytes to `target`; it is the caller's responsibility to ensure that the piece is
+/// written at an offset which is a multiple of its padded size, e.g. as the first piece of a
+/// sector.
+///
+/// # Arguments
+///
+/// * `source` - a readable source of unprocessed piece bytes.
+/// * `target` - a writer where we will write the processed piece bytes.
+pub fn add_piece_unsized<R, W>(source: R, target: W) -> Result<(PieceInfo, UnpaddedBytesAmount)>
+where
+    R: Read,
+    W: Write,
+{
+    trace!("add_piece_unsized:start");
+
+    let result = measure_op(Operation::AddPiece, || {
+        let mut source = Pos::new(BufReader::new(source));
+        let mut target = BufWriter::new(target);
+
+        // The data is committed in power of two sized pieces, which are joined and padded with
+        // zeros once the size is known.
+        let mut piece_infos = Vec::new();
+        let mut data_bytes = 0u64;
+        {
+            let mut fr32_reader = Fr32Reader::new(&mut source);
+            let mut buffer = vec![0u8; UNSIZED_PIECE_CHUNK_SIZE];
+            loop {
+                let read = fr32_reader
+                    .read_many(&mut buffer)
+                    .context("failed to read and preprocess bytes")?;
+                if read == 0 {
+                    break;
+                }
+
+                // Round the last chunk up to a whole number of minimum sized pieces.
+                let min_piece_bytes = u64::from(PaddedBytesAmount::from(UnpaddedBytesAmount(
+                    MINIMUM_PIECE_SIZE,
+                ))) as usize;
+                let chunk_len = read + (min_piece_bytes - read % min_piece_bytes) % min_piece_bytes;
+                buffer[read..chunk_len].fill(0);
+                target.write_all(&buffer[..chunk_len])?;
+
+                let mut offset = 0;
+                while offset < chunk_len {
+                    let len = prev_power_of_two(chunk_len - offset);
+                    let commitment = generate_piece_commitment_bytes_from_source::<
+                        DefaultPieceHasher,
+                    >(&mut &buffer[offset..offset + len], len)?;
+                    piece_infos.push(PieceInfo::new(
+                        commitment,
+                        PaddedBytesAmount(len as u64).into(),
+                    )?);
+                    offset += len;
+                }
+                data_bytes += chunk_len as u64;
+
+                if read < buffer.len() {
+                    break;
+                }
+            }
+        }
+
+        let bytes_read = source.position();
+        ensure!(
+            bytes_read != 0,
+            "add_piece_unsized: read 0 bytes before EOF from source"
+        );
+
+        let piece_info = join_and_pad_piece_infos(&piece_infos)?;
+        let padding_bytes = u64::from(PaddedBytesAmount::from(piece_info.size)) - data_bytes;
+        io::copy(&mut io::repeat(0).take(padding_bytes), &mut target)
+            .context("failed to write zero padding")?;
+        target.flush()?;
+
+        Ok((piece_info, UnpaddedBytesAmount(bytes_read)))
+    });
+
+    trace!("add_piece_unsized:finish");
+    result
+}
+
+/// The largest power of two which is smaller or equal to `x`, `x` must be > 0.
+fn prev_power_of_two(x: usize) -> usize {
+    1 << (usize::BITS - 1 - x.leading_zeros())
+}
+
 fn ensure_piece_size(piece_size: UnpaddedBytesAmount) -> Result<()> {
     ensure!(
         piece_size >= UnpaddedBytesAmount(MINIMUM_PIECE_SIZE),
@@ -574,8 +864,10 @@ fn verify_store(config: &StoreConfig, arity: usize, required_configs: usize) ->
 
         ensure!(
             configs.len() == required_configs,
-            "Missing store file (or associated split paths): {}",
-            store_path.display()
+            Error::CacheCorrupted(format!(
+                "Missing store file (or associated split paths): {}",
+                store_path.display()
+            ))
         );
 
         let store_len = config.size.expect("disk store size not configured");
@@ -588,8 +880,7 @@ fn verify_store(config: &StoreConfig, arity: usize, required_configs: usize) ->
             );
             ensure!(
                 DiskStore::<DefaultPieceDomain>::is_consistent(store_len, arity, config,)?,
-                "Store is inconsistent: {:?}",
-                &data_path
+                Error::CacheCorrupted(format!("Store is inconsistent: {:?}", &data_path))
             );
         }
     } else {
@@ -604,8 +895,7 @@ fn verify_store(config: &StoreConfig, arity: usize, required_configs: usize) ->
                 arity,
                 config,
             )?,
-            "Store is inconsistent: {:?}",
-            store_path
+            Error::CacheCorrupted(format!("Store is inconsistent: {:?}", store_path))
         );
     }
 
@@ -649,8 +939,10 @@ fn verify_level_cache_store<Tree: MerkleTreeTrait>(config: &StoreConfig) -> Resu
 
         ensure!(
             configs.len() == required_configs,
-            "Missing store file (or associated split paths): {}",
-            store_path.display()
+            Error::CacheCorrupted(format!(
+                "Missing store file (or associated split paths): {}",
+                store_path.display()
+            ))
         );
 
         let store_len = config.size.expect("disk store size not configured");
@@ -667,8 +959,7 @@ fn verify_level_cache_store<Tree: MerkleTreeTrait>(config: &StoreConfig) -> Resu
                     Tree::Arity::to_usize(),
                     config,
                 )?,
-                "Store is inconsistent: {:?}",
-                &data_path
+                Error::CacheCorrupted(format!("Store is inconsistent: {:?}", &data_path))
             );
         }
     } else {
@@ -683,8 +974,7 @@ fn verify_level_cache_store<Tree: MerkleTreeTrait>(config: &StoreConfig) -> Resu
                 Tree::Arity::to_usize(),
                 config,
             )?,
-            "Store is inconsistent: {:?}",
-            store_path
+            Error::CacheCorrupted(format!("Store is inconsistent: {:?}", store_path))
         );
     }
 
@@ -765,19 +1055,27 @@ where
 
     // Make sure p_aux exists and is valid.
     let p_aux_path = cache.join(CacheKey::PAux.to_string());
-    let p_aux_bytes = fs::read(&p_aux_path)
-        .with_context(|| format!("could not read file p_aux={:?}", p_aux_path))?;
-
-    let _: PersistentAux<<Tree::Hasher as Hasher>::Domain> = deserialize(&p_aux_bytes)?;
+    let p_aux_bytes = fs::read(&p_aux_path).with_context(|| {
+        Error::CacheCorrupted(format!("could not read file p_aux={:?}", p_aux_path))
+    })?;
+
+    let _: PersistentAux<<Tree::Hasher as Hasher>::Domain> = deserialize(&p_aux_bytes)
+        .with_context(|| {
+            Error::CacheCorrupted(format!("could not deserialize p_aux={:?}", p_aux_path))
+        })?;
     drop(p_aux_bytes);
 
     // Make sure t_aux exists and is valid.
     let t_aux = {
         let t_aux_path = cache.join(CacheKey::TAux.to_string());
-        let t_aux_bytes = fs::read(&t_aux_path)
-            .with_context(|| format!("could not read file t_aux={:?}", t_aux_path))?;
+        let t_aux_bytes = fs::read(&t_aux_path).with_context(|| {
+            Error::CacheCorrupted(format!("could not read file t_aux={:?}", t_aux_path))
+        })?;
 
-        let mut res: TemporaryAux<Tree, DefaultPieceHasher> = deserialize(&t_aux_bytes)?;
+        let mut res: TemporaryAux<Tree, DefaultPieceHasher> = deserialize(&t_aux_bytes)
+            .with_context(|| {
+                Error::CacheCorrupted(format!("could not deserialize t_aux={:?}", t_aux_path))
+            })?;
 
         // Switch t_aux to the passed in cache_path
         res.set_cache_path(&cache_path);
@@ -805,3 +1103,22 @@ where
 
     Ok(())
 }
+
+/// Re-hashes the files recorded in the manifest of `cache_path` by the sealing phases run so
+/// far, and reports which of them are missing or corrupt.
+///
+/// Unlike `validate_cache_for_precommit_phase2` and `validate_cache_for_commit`, this reads
+/// every file in full, which is expensive, but catches damaged layers, tree_c or tree_r_last
+/// files before a proof is generated from them.
+pub fn verify_sector_cache<R: AsRef<Path>>(cache_path: R) -> Result<SectorCacheReport> {
+    info!("verify_sector_cache:start");
+
+    let cache_path = cache_path.as_ref();
+    let manifest = CacheManifest::read(cache_path)?.with_context(|| {
+        Error::CacheCorrupted(format!("missing cache manifest in {:?}", cache_path))
+    })?;
+    let report = manifest.verify(cache_path)?;
+
+    info!("verify_sector_cache:finish");
+    Ok(report)
+}
@@ -1,7 +1,8 @@
 use std::collections::BTreeMap;
 
 use anyhow::{anyhow, ensure, Context, Result};
-use filecoin_hashers::Hasher;
+use bincode::deserialize;
+use filecoin_hashers::{HashFunction, Hasher};
 use log::{debug, info};
 use storage_proofs_core::{merkle::MerkleTreeTrait, proof::ProofScheme, sector::SectorId};
 use storage_proofs_post::fallback::{
@@ -9,10 +10,12 @@ use storage_proofs_post::fallback::{
 };
 
 use crate::{
-    api::as_safe_commitment,
+    api::{as_safe_commitment, get_base_tree_size},
+    error::Error,
+    replica_store::{tree_r_last_proofs, LocalReplicaStore, ReplicaStore},
     types::{
-        ChallengeSeed, FallbackPoStSectorProof, PoStConfig, PrivateReplicaInfo, ProverId,
-        VanillaProof,
+        ChallengeSeed, Commitment, FallbackPoStSectorProof, PersistentAux, PoStConfig,
+        PrivateReplicaInfo, ProverId, VanillaProof,
     },
     PartitionSnarkProof, PoStType, SnarkProof, SINGLE_PARTITION_PROOF_LEN,
 };
@@ -28,7 +31,7 @@ pub fn generate_fallback_sector_challenges<Tree: 'static + MerkleTreeTrait>(
     info!("generate_sector_challenges:start");
     ensure!(
         post_config.typ == PoStType::Window || post_config.typ == PoStType::Winning,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     let randomness_safe: <Tree::Hasher as Hasher>::Domain =
@@ -99,47 +102,99 @@ pub fn generate_single_vanilla_proof<Tree: 'static + MerkleTreeTrait>(
 ) -> Result<FallbackPoStSectorProof<Tree>> {
     info!("generate_single_vanilla_proof:start: {:?}", sector_id);
 
-    let tree = &replica
-        .merkle_tree(post_config.sector_size)
+    // The tree shape is checked first, so that a config error is not taken for a faulty sector.
+    get_base_tree_size::<Tree>(post_config.sector_size)?;
+    let store = LocalReplicaStore::new::<Tree>(replica.replica_path(), replica.cache_dir_path())
         .with_context(|| {
             format!(
-                "generate_single_vanilla_proof: merkle_tree failed: {:?}",
+                "generate_single_vanilla_proof: could not open replica: {:?}",
                 sector_id
             )
-        })?;
-    let comm_r = replica.safe_comm_r().with_context(|| {
+        })
+        .context(Error::FaultySectors(vec![sector_id]))?;
+    let vanilla_proof = generate_single_vanilla_proof_with_store::<Tree, _>(
+        post_config,
+        sector_id,
+        replica.comm_r(),
+        &store,
+        challenges,
+    )?;
+
+    info!("generate_single_vanilla_proof:finish: {:?}", sector_id);
+
+    Ok(vanilla_proof)
+}
+
+/// Generates a single vanilla proof like `generate_single_vanilla_proof`, reading the replica,
+/// tree_r_last and p_aux of the sector through `store` instead of from local paths.
+///
+/// Failures of the sector itself, i.e. a failed read, a comm_r which does not match comm_c and
+/// comm_r_last of p_aux or an invalid inclusion proof, are classified as `Error::FaultySectors`.
+pub fn generate_single_vanilla_proof_with_store<Tree, S>(
+    post_config: &PoStConfig,
+    sector_id: SectorId,
+    comm_r: Commitment,
+    store: &S,
+    challenges: &[u64],
+) -> Result<FallbackPoStSectorProof<Tree>>
+where
+    Tree: 'static + MerkleTreeTrait,
+    S: ReplicaStore + ?Sized,
+{
+    info!(
+        "generate_single_vanilla_proof_with_store:start: {:?}",
+        sector_id
+    );
+
+    // The tree shape is checked first, so that a config error is not taken for a faulty sector.
+    get_base_tree_size::<Tree>(post_config.sector_size)?;
+    let comm_r = as_safe_commitment(&comm_r, "comm_r")?;
+    let p_aux_bytes = store
+        .read_p_aux()
+        .context(Error::FaultySectors(vec![sector_id]))?;
+    let p_aux: PersistentAux<<Tree::Hasher as Hasher>::Domain> = deserialize(&p_aux_bytes)
+        .with_context(|| {
+            Error::CacheCorrupted(format!("could not deserialize p_aux of {:?}", sector_id))
+        })
+        .context(Error::FaultySectors(vec![sector_id]))?;
+    if <Tree::Hasher as Hasher>::Function::hash2(&p_aux.comm_c, &p_aux.comm_r_last) != comm_r {
+        return Err(Error::InvalidCommitment(format!(
+            "comm_r of {:?} does not match comm_c and comm_r_last of p_aux",
+            sector_id
+        )))
+        .context(Error::FaultySectors(vec![sector_id]));
+    }
+
+    let inclusion_proofs = tree_r_last_proofs::<Tree, S>(
+        post_config.sector_size,
+        store,
+        sector_id,
+        p_aux.comm_r_last,
+        challenges,
+    )
+    .with_context(|| {
         format!(
-            "generate_single_vanilla_poof: safe_comm_r failed: {:?}",
+            "generate_single_vanilla_proof_with_store: vanilla_proof failed: {:?}",
             sector_id
         )
-    })?;
-    let comm_c = replica.safe_comm_c();
-    let comm_r_last = replica.safe_comm_r_last();
-
-    let priv_sectors = vec![fallback::PrivateSector {
-        tree,
-        comm_c,
-        comm_r_last,
-    }];
-
-    let priv_inputs = fallback::PrivateInputs::<Tree> {
-        sectors: &priv_sectors,
-    };
-
-    let vanilla_proof =
-        fallback::vanilla_proof(sector_id, &priv_inputs, challenges).with_context(|| {
-            format!(
-                "generate_single_vanilla_proof: vanilla_proof failed: {:?}",
-                sector_id
-            )
-        })?;
+    })
+    .context(Error::FaultySectors(vec![sector_id]))?;
 
-    info!("generate_single_vanilla_proof:finish: {:?}", sector_id);
+    info!(
+        "generate_single_vanilla_proof_with_store:finish: {:?}",
+        sector_id
+    );
 
     Ok(FallbackPoStSectorProof {
         sector_id,
         comm_r,
-        vanilla_proof,
+        vanilla_proof: fallback::Proof {
+            sectors: vec![SectorProof {
+                inclusion_proofs,
+                comm_c: p_aux.comm_c,
+                comm_r_last: p_aux.comm_r_last,
+            }],
+        },
     })
 }
 
@@ -156,7 +211,7 @@ pub fn partition_vanilla_proofs<Tree: MerkleTreeTrait>(
     info!("partition_vanilla_proofs:start");
     ensure!(
         post_config.typ == PoStType::Window || post_config.typ == PoStType::Winning,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     let num_sectors_per_chunk = pub_params.sector_count;
@@ -240,7 +295,7 @@ pub fn single_partition_vanilla_proofs<Tree: MerkleTreeTrait>(
     debug!("processing partition: {}", partition_index);
     ensure!(
         post_config.typ == PoStType::Window || post_config.typ == PoStType::Winning,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     let num_sectors_per_chunk = pub_params.sector_count;
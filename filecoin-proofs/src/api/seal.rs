@@ -30,8 +30,8 @@ use storage_proofs_core::{
     Data,
 };
 use storage_proofs_porep::stacked::{
-    self, generate_replica_id, ChallengeRequirements, Labels, LabelsCache, StackedCompound,
-    StackedDrg, Tau, TemporaryAux, TemporaryAuxCache,
+    self, generate_replica_id, ChallengeRequirements, LabelCheckpoint, Labels, LabelsCache,
+    PersistentAux, StackedCompound, StackedDrg, Tau, TemporaryAux, TemporaryAuxCache,
 };
 use storage_proofs_update::vanilla::prepare_tree_r_data;
 use typenum::{Unsigned, U11, U2};
@@ -46,12 +46,14 @@ use crate::{
     constants::{
         DefaultBinaryTree, DefaultPieceDomain, DefaultPieceHasher, SINGLE_PARTITION_PROOF_LEN,
     },
+    error::Error,
     parameters::setup_params,
     pieces::{self, verify_pieces},
     types::{
-        AggregateSnarkProof, Commitment, PieceInfo, PoRepConfig, ProverId, SealCommitOutput,
-        SealCommitPhase1Output, SealPreCommitOutput, SealPreCommitPhase1Output, SectorSize, Ticket,
-        BINARY_ARITY,
+        AggregateSnarkProof, CacheManifest, Commitment, PieceInfo, PoRepConfig, ProverId,
+        SealCommitOutput, SealCommitPartitionOutput, SealCommitPhase1Output, SealPhase,
+        SealPreCommitOutput, SealPreCommitPhase1Input, SealPreCommitPhase1Output, SectorSize,
+        Ticket, BINARY_ARITY,
     },
 };
 
@@ -73,6 +75,222 @@ where
 {
     info!("seal_pre_commit_phase1:start: {:?}", sector_id);
 
+    let out = seal_pre_commit_phase1_inner::<_, _, _, Tree>(
+        porep_config,
+        cache_path,
+        in_path,
+        out_path,
+        prover_id,
+        sector_id,
+        ticket,
+        piece_infos,
+        false,
+    )?;
+
+    info!("seal_pre_commit_phase1:finish: {:?}", sector_id);
+    Ok(out)
+}
+
+/// Runs `seal_pre_commit_phase1` with a label checkpoint in `cache_path`, so that an interrupted
+/// run can be resumed by calling this again with the same arguments.
+///
+/// The layers completed by a previous run are validated against the digests recorded in its
+/// checkpoint, and labeling continues with the layer following the last valid one. Layers that
+/// are missing or corrupt are regenerated. `seal_pre_commit_phase1` itself does not write a
+/// checkpoint.
+#[allow(clippy::too_many_arguments)]
+pub fn resume_seal_pre_commit_phase1<R, S, T, Tree: 'static + MerkleTreeTrait>(
+    porep_config: &PoRepConfig,
+    cache_path: R,
+    in_path: S,
+    out_path: T,
+    prover_id: ProverId,
+    sector_id: SectorId,
+    ticket: Ticket,
+    piece_infos: &[PieceInfo],
+) -> Result<SealPreCommitPhase1Output<Tree>>
+where
+    R: AsRef<Path>,
+    S: AsRef<Path>,
+    T: AsRef<Path>,
+{
+    info!("resume_seal_pre_commit_phase1:start: {:?}", sector_id);
+
+    let out = seal_pre_commit_phase1_inner::<_, _, _, Tree>(
+        porep_config,
+        cache_path,
+        in_path,
+        out_path,
+        prover_id,
+        sector_id,
+        ticket,
+        piece_infos,
+        true,
+    )?;
+
+    info!("resume_seal_pre_commit_phase1:finish: {:?}", sector_id);
+    Ok(out)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn seal_pre_commit_phase1_inner<R, S, T, Tree: 'static + MerkleTreeTrait>(
+    porep_config: &PoRepConfig,
+    cache_path: R,
+    in_path: S,
+    out_path: T,
+    prover_id: ProverId,
+    sector_id: SectorId,
+    ticket: Ticket,
+    piece_infos: &[PieceInfo],
+    resume: bool,
+) -> Result<SealPreCommitPhase1Output<Tree>>
+where
+    R: AsRef<Path>,
+    S: AsRef<Path>,
+    T: AsRef<Path>,
+{
+    let compound_public_params = pre_commit_public_params::<Tree>(porep_config)?;
+
+    let (config, comm_d, replica_id) = prepare_pre_commit_phase1::<_, _, _, Tree>(
+        porep_config,
+        &compound_public_params.vanilla_params,
+        cache_path,
+        in_path,
+        out_path,
+        prover_id,
+        sector_id,
+        ticket,
+        piece_infos,
+    )?;
+
+    let labels = if resume {
+        StackedDrg::<Tree, DefaultPieceHasher>::resume_phase1(
+            &compound_public_params.vanilla_params,
+            &replica_id,
+            &config.path,
+        )?
+    } else {
+        StackedDrg::<Tree, DefaultPieceHasher>::replicate_phase1(
+            &compound_public_params.vanilla_params,
+            &replica_id,
+            &config.path,
+        )?
+    };
+
+    let layer_digests = if resume {
+        LabelCheckpoint::read(&config.path)?
+            .map(|checkpoint| checkpoint.digests)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    CacheManifest::record_phase(&config.path, SealPhase::PreCommit1, &layer_digests)?;
+
+    Ok(SealPreCommitPhase1Output {
+        labels,
+        config,
+        comm_d,
+    })
+}
+
+/// Runs `seal_pre_commit_phase1` for several sectors of the same `porep_config` at once.
+///
+/// The data of each sector is copied and its tree_d built as usual, after which all sectors
+/// are labeled together: with multicore SDR enabled every parent list is read once from the
+/// parents cache and used for all sectors of the batch. The outputs are returned in the order
+/// of `sectors`.
+pub fn seal_pre_commit_phase1_batch<Tree: 'static + MerkleTreeTrait>(
+    porep_config: &PoRepConfig,
+    sectors: &[SealPreCommitPhase1Input],
+) -> Result<Vec<SealPreCommitPhase1Output<Tree>>> {
+    info!(
+        "seal_pre_commit_phase1_batch:start: {} sectors",
+        sectors.len()
+    );
+    ensure!(!sectors.is_empty(), "no sectors to seal");
+
+    let compound_public_params = pre_commit_public_params::<Tree>(porep_config)?;
+
+    let mut configs = Vec::with_capacity(sectors.len());
+    let mut comm_ds = Vec::with_capacity(sectors.len());
+    let mut replica_ids = Vec::with_capacity(sectors.len());
+    for sector in sectors {
+        let (config, comm_d, replica_id) = prepare_pre_commit_phase1::<_, _, _, Tree>(
+            porep_config,
+            &compound_public_params.vanilla_params,
+            &sector.cache_path,
+            &sector.in_path,
+            &sector.out_path,
+            sector.prover_id,
+            sector.sector_id,
+            sector.ticket,
+            &sector.piece_infos,
+        )?;
+        configs.push(config);
+        comm_ds.push(comm_d);
+        replica_ids.push(replica_id);
+    }
+
+    let cache_paths: Vec<_> = configs.iter().map(|config| &config.path).collect();
+    let labels = StackedDrg::<Tree, DefaultPieceHasher>::replicate_phase1_batch(
+        &compound_public_params.vanilla_params,
+        &replica_ids,
+        &cache_paths,
+    )?;
+
+    let out = labels
+        .into_iter()
+        .zip(configs)
+        .zip(comm_ds)
+        .map(|((labels, config), comm_d)| {
+            CacheManifest::record_phase(&config.path, SealPhase::PreCommit1, &[])?;
+
+            Ok(SealPreCommitPhase1Output {
+                labels,
+                config,
+                comm_d,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    info!("seal_pre_commit_phase1_batch:finish");
+    Ok(out)
+}
+
+fn pre_commit_public_params<Tree: 'static + MerkleTreeTrait>(
+    porep_config: &PoRepConfig,
+) -> Result<compound_proof::PublicParams<'static, StackedDrg<'static, Tree, DefaultPieceHasher>>> {
+    let compound_setup_params = compound_proof::SetupParams {
+        vanilla_params: setup_params(porep_config)?,
+        partitions: Some(usize::from(porep_config.partitions)),
+        priority: false,
+    };
+
+    <StackedCompound<Tree, DefaultPieceHasher> as CompoundProof<
+        StackedDrg<'_, Tree, DefaultPieceHasher>,
+        _,
+    >>::setup(&compound_setup_params)
+}
+
+/// Copies the sector data to `out_path`, builds tree_d in `cache_path` and derives the replica
+/// id, i.e. everything phase1 does before labeling.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn prepare_pre_commit_phase1<R, S, T, Tree: 'static + MerkleTreeTrait>(
+    porep_config: &PoRepConfig,
+    vanilla_params: &stacked::PublicParams<Tree>,
+    cache_path: R,
+    in_path: S,
+    out_path: T,
+    prover_id: ProverId,
+    sector_id: SectorId,
+    ticket: Ticket,
+    piece_infos: &[PieceInfo],
+) -> Result<(StoreConfig, Commitment, <Tree::Hasher as Hasher>::Domain)>
+where
+    R: AsRef<Path>,
+    S: AsRef<Path>,
+    T: AsRef<Path>,
+{
     let in_path_is_dev_zero = in_path.as_ref() == Path::new("/dev/zero");
     if in_path_is_dev_zero {
         trace!("using unreplicated data file /dev/zero");
@@ -96,11 +314,19 @@ where
     );
 
     let sector_bytes = usize::from(porep_config.padded_bytes_amount());
-    fs::metadata(&in_path)
-        .with_context(|| format!("could not read in_path={:?})", in_path.as_ref().display()))?;
+    fs::metadata(&in_path).with_context(|| {
+        Error::Io(format!(
+            "could not read in_path={:?})",
+            in_path.as_ref().display()
+        ))
+    })?;
 
-    fs::metadata(&out_path)
-        .with_context(|| format!("could not read out_path={:?}", out_path.as_ref().display()))?;
+    fs::metadata(&out_path).with_context(|| {
+        Error::Io(format!(
+            "could not read out_path={:?}",
+            out_path.as_ref().display()
+        ))
+    })?;
 
     // Copy unsealed data to output location, where it will be sealed in place.
     //
@@ -120,34 +346,31 @@ where
         .read(true)
         .write(true)
         .open(&out_path)
-        .with_context(|| format!("could not open out_path={:?}", out_path.as_ref().display()))?;
+        .with_context(|| {
+            Error::Io(format!(
+                "could not open out_path={:?}",
+                out_path.as_ref().display()
+            ))
+        })?;
 
     // Extend the underlying file with `0` bytes until it's length is the requested sector size.
     f_data.set_len(sector_bytes as u64)?;
 
     let data = unsafe {
-        MmapOptions::new()
-            .map_mut(&f_data)
-            .with_context(|| format!("could not mmap out_path={:?}", out_path.as_ref().display()))?
-    };
-
-    let compound_setup_params = compound_proof::SetupParams {
-        vanilla_params: setup_params(porep_config)?,
-        partitions: Some(usize::from(porep_config.partitions)),
-        priority: false,
+        MmapOptions::new().map_mut(&f_data).with_context(|| {
+            Error::Io(format!(
+                "could not mmap out_path={:?}",
+                out_path.as_ref().display()
+            ))
+        })?
     };
 
-    let compound_public_params = <StackedCompound<Tree, DefaultPieceHasher> as CompoundProof<
-        StackedDrg<'_, Tree, DefaultPieceHasher>,
-        _,
-    >>::setup(&compound_setup_params)?;
-
     trace!("building merkle tree for the original data");
     let (config, comm_d) = measure_op(Operation::CommD, || -> Result<_> {
         let base_tree_size = get_base_tree_size::<DefaultBinaryTree>(porep_config.sector_size)?;
         let base_tree_leafs = get_base_tree_leafs::<DefaultBinaryTree>(base_tree_size)?;
         ensure!(
-            compound_public_params.vanilla_params.graph.size() == base_tree_leafs,
+            vanilla_params.graph.size() == base_tree_leafs,
             "graph size and leaf size don't match"
         );
 
@@ -184,7 +407,7 @@ where
 
     ensure!(
         verify_pieces(&comm_d, piece_infos, porep_config.sector_size)?,
-        "pieces and comm_d do not match"
+        Error::InvalidCommitment("pieces and comm_d do not match".into())
     );
 
     let replica_id = generate_replica_id::<Tree::Hasher, _>(
@@ -195,20 +418,7 @@ where
         &porep_config.porep_id,
     );
 
-    let labels = StackedDrg::<Tree, DefaultPieceHasher>::replicate_phase1(
-        &compound_public_params.vanilla_params,
-        &replica_id,
-        &config.path,
-    )?;
-
-    let out = SealPreCommitPhase1Output {
-        labels,
-        config,
-        comm_d,
-    };
-
-    info!("seal_pre_commit_phase1:finish: {:?}", sector_id);
-    Ok(out)
+    Ok((config, comm_d, replica_id))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -233,6 +443,7 @@ where
         metadata(replica_path.as_ref())?.is_file(),
         "replica_path must be a file"
     );
+    CacheManifest::ensure_intact(cache_path.as_ref(), SealPhase::PreCommit2)?;
 
     let SealPreCommitPhase1Output {
         mut labels,
@@ -277,7 +488,7 @@ where
         );
         ensure!(
             config.rows_to_discard == default_rows_to_discard(base_tree_leafs, BINARY_ARITY),
-            "Invalid cache size specified"
+            Error::InvalidConfig("Invalid cache size specified".into())
         );
 
         let store: DiskStore<DefaultPieceDomain> =
@@ -310,19 +521,21 @@ where
     // Persist p_aux and t_aux here
     let p_aux_path = cache_path.as_ref().join(CacheKey::PAux.to_string());
     let mut f_p_aux = File::create(&p_aux_path)
-        .with_context(|| format!("could not create file p_aux={:?}", p_aux_path))?;
+        .with_context(|| Error::Io(format!("could not create file p_aux={:?}", p_aux_path)))?;
     let p_aux_bytes = serialize(&p_aux)?;
     f_p_aux
         .write_all(&p_aux_bytes)
-        .with_context(|| format!("could not write to file p_aux={:?}", p_aux_path))?;
+        .with_context(|| Error::Io(format!("could not write to file p_aux={:?}", p_aux_path)))?;
 
     let t_aux_path = cache_path.as_ref().join(CacheKey::TAux.to_string());
     let mut f_t_aux = File::create(&t_aux_path)
-        .with_context(|| format!("could not create file t_aux={:?}", t_aux_path))?;
+        .with_context(|| Error::Io(format!("could not create file t_aux={:?}", t_aux_path)))?;
     let t_aux_bytes = serialize(&t_aux)?;
     f_t_aux
         .write_all(&t_aux_bytes)
-        .with_context(|| format!("could not write to file t_aux={:?}", t_aux_path))?;
+        .with_context(|| Error::Io(format!("could not write to file t_aux={:?}", t_aux_path)))?;
+
+    CacheManifest::record_phase(cache_path.as_ref(), SealPhase::PreCommit2, &[])?;
 
     let out = SealPreCommitOutput { comm_r, comm_d };
 
@@ -421,6 +634,7 @@ pub fn seal_commit_phase1_inner<T: AsRef<Path>, Tree: 'static + MerkleTreeTrait>
         metadata(replica_path.as_ref())?.is_file(),
         "replica_path must be a file"
     );
+    CacheManifest::ensure_intact(cache_path.as_ref(), SealPhase::Commit1)?;
 
     ensure!(
         seed.is_some() || porep_config.feature_enabled(ApiFeature::SyntheticPoRep),
@@ -429,30 +643,42 @@ pub fn seal_commit_phase1_inner<T: AsRef<Path>, Tree: 'static + MerkleTreeTrait>
 
     let SealPreCommitOutput { comm_d, comm_r } = pre_commit;
 
-    ensure!(comm_d != [0; 32], "Invalid all zero commitment (comm_d)");
-    ensure!(comm_r != [0; 32], "Invalid all zero commitment (comm_r)");
+    ensure!(
+        comm_d != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_d)".into())
+    );
+    ensure!(
+        comm_r != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_r)".into())
+    );
     ensure!(
         verify_pieces(&comm_d, piece_infos, porep_config.sector_size)?,
-        "pieces and comm_d do not match"
+        Error::InvalidCommitment("pieces and comm_d do not match".into())
     );
 
     let p_aux = {
         let p_aux_path = cache_path.as_ref().join(CacheKey::PAux.to_string());
-        let p_aux_bytes = fs::read(&p_aux_path)
-            .with_context(|| format!("could not read file p_aux={:?}", p_aux_path))?;
+        let p_aux_bytes = fs::read(&p_aux_path).with_context(|| {
+            Error::CacheCorrupted(format!("could not read file p_aux={:?}", p_aux_path))
+        })?;
 
-        deserialize(&p_aux_bytes)
+        deserialize(&p_aux_bytes).with_context(|| {
+            Error::CacheCorrupted(format!("could not deserialize p_aux={:?}", p_aux_path))
+        })
     }?;
 
     let t_aux = {
         let t_aux_path = cache_path.as_ref().join(CacheKey::TAux.to_string());
-        let t_aux_bytes = fs::read(&t_aux_path)
-            .with_context(|| format!("could not read file t_aux={:?}", t_aux_path))?;
+        let t_aux_bytes = fs::read(&t_aux_path).with_context(|| {
+            Error::CacheCorrupted(format!("could not read file t_aux={:?}", t_aux_path))
+        })?;
 
-        let mut res: TemporaryAux<_, _> = deserialize(&t_aux_bytes)?;
+        let mut res: TemporaryAux<_, _> = deserialize(&t_aux_bytes).with_context(|| {
+            Error::CacheCorrupted(format!("could not deserialize t_aux={:?}", t_aux_path))
+        })?;
 
         // Switch t_aux to the passed in cache_path
-        res.set_cache_path(cache_path);
+        res.set_cache_path(&cache_path);
         res
     };
 
@@ -513,6 +739,8 @@ pub fn seal_commit_phase1_inner<T: AsRef<Path>, Tree: 'static + MerkleTreeTrait>
     )?;
     ensure!(sanity_check, "Invalid vanilla proof generated");
 
+    CacheManifest::record_phase(cache_path.as_ref(), SealPhase::Commit1, &[])?;
+
     let out = SealCommitPhase1Output {
         vanilla_proofs,
         comm_r,
@@ -537,17 +765,159 @@ pub fn seal_commit_phase2<Tree: 'static + MerkleTreeTrait>(
 ) -> Result<SealCommitOutput> {
     info!("seal_commit_phase2:start: {:?}", sector_id);
 
+    let SealCommitPhase1Output {
+        comm_r,
+        comm_d,
+        seed,
+        ticket,
+        ..
+    } = phase1_output;
+    let partitions: Vec<usize> = (0..phase1_output.vanilla_proofs.len()).collect();
+    let groth_proofs = seal_commit_phase2_circuit_proofs(porep_config, phase1_output, &partitions)?;
+
+    let verifying_key = get_stacked_verifying_key::<Tree>(porep_config)?;
+    let proof = MultiProof::new(groth_proofs, &verifying_key);
+    let mut buf =
+        Vec::with_capacity(SINGLE_PARTITION_PROOF_LEN * usize::from(porep_config.partitions));
+
+    proof.write(&mut buf)?;
+
+    // Verification is cheap when parameters are cached,
+    // and it is never correct to return a proof which does not verify.
+    verify_seal::<Tree>(
+        porep_config,
+        comm_r,
+        comm_d,
+        prover_id,
+        sector_id,
+        ticket,
+        seed,
+        &buf,
+    )
+    .context("post-seal verification sanity check failed")?;
+
+    let out = SealCommitOutput { proof: buf };
+
+    info!("seal_commit_phase2:finish: {:?}", sector_id);
+    Ok(out)
+}
+
+/// Proves the given PoRep `partitions` of a sector, so that the proofs of a single sector can be
+/// generated on several machines. The returned partial proof contains one proof per partition, in
+/// the order of `partitions`, and is turned into the seal proof by
+/// `merge_seal_commit_partition_proofs` once every partition is proven.
+pub fn seal_commit_phase2_partitions<Tree: 'static + MerkleTreeTrait>(
+    porep_config: &PoRepConfig,
+    phase1_output: SealCommitPhase1Output<Tree>,
+    sector_id: SectorId,
+    partitions: &[usize],
+) -> Result<SealCommitPartitionOutput> {
+    info!("seal_commit_phase2_partitions:start: {:?}", sector_id);
+
+    let groth_proofs = seal_commit_phase2_circuit_proofs(porep_config, phase1_output, partitions)?;
+    let mut proof = Vec::with_capacity(SINGLE_PARTITION_PROOF_LEN * groth_proofs.len());
+    for groth_proof in &groth_proofs {
+        groth_proof.write(&mut proof)?;
+    }
+
+    info!("seal_commit_phase2_partitions:finish: {:?}", sector_id);
+    Ok(SealCommitPartitionOutput {
+        partitions: partitions.to_vec(),
+        proof,
+    })
+}
+
+/// Merges the partial proofs of `seal_commit_phase2_partitions` into the seal proof of a sector.
+/// Parts may be passed in any order, but every partition has to be proven exactly once. The
+/// merged proof is checked with `verify_seal` before it is returned.
+#[allow(clippy::too_many_arguments)]
+pub fn merge_seal_commit_partition_proofs<Tree: 'static + MerkleTreeTrait>(
+    porep_config: &PoRepConfig,
+    parts: Vec<SealCommitPartitionOutput>,
+    comm_r: Commitment,
+    comm_d: Commitment,
+    prover_id: ProverId,
+    sector_id: SectorId,
+    ticket: Ticket,
+    seed: Ticket,
+) -> Result<SealCommitOutput> {
+    info!("merge_seal_commit_partition_proofs:start: {:?}", sector_id);
+
+    let num_partitions = usize::from(porep_config.partitions);
+    let mut partition_proofs: Vec<Option<&[u8]>> = vec![None; num_partitions];
+    for part in &parts {
+        ensure!(
+            part.proof.len() == part.partitions.len() * SINGLE_PARTITION_PROOF_LEN,
+            "partial seal proof of {} partitions has invalid length {}",
+            part.partitions.len(),
+            part.proof.len()
+        );
+        for (&k, partition_proof) in part
+            .partitions
+            .iter()
+            .zip(part.proof.chunks(SINGLE_PARTITION_PROOF_LEN))
+        {
+            ensure!(
+                k < num_partitions,
+                "invalid partition {}, sector has {} partitions",
+                k,
+                num_partitions
+            );
+            ensure!(
+                partition_proofs[k].replace(partition_proof).is_none(),
+                "partition {} proven more than once",
+                k
+            );
+        }
+    }
+
+    let mut proof = Vec::with_capacity(SINGLE_PARTITION_PROOF_LEN * num_partitions);
+    for (k, partition_proof) in partition_proofs.into_iter().enumerate() {
+        let partition_proof =
+            partition_proof.with_context(|| format!("partition {} was not proven", k))?;
+        proof.extend_from_slice(partition_proof);
+    }
+
+    let is_valid = verify_seal::<Tree>(
+        porep_config,
+        comm_r,
+        comm_d,
+        prover_id,
+        sector_id,
+        ticket,
+        seed,
+        &proof,
+    )?;
+    ensure!(is_valid, "merged seal proof failed to verify");
+
+    info!("merge_seal_commit_partition_proofs:finish: {:?}", sector_id);
+    Ok(SealCommitOutput { proof })
+}
+
+/// Generates the circuit proofs of the given partitions from the vanilla proofs of commit
+/// phase1.
+fn seal_commit_phase2_circuit_proofs<Tree: 'static + MerkleTreeTrait>(
+    porep_config: &PoRepConfig,
+    phase1_output: SealCommitPhase1Output<Tree>,
+    partitions: &[usize],
+) -> Result<Vec<groth16::Proof<Bls12>>> {
     let SealCommitPhase1Output {
         vanilla_proofs,
         comm_d,
         comm_r,
         replica_id,
         seed,
-        ticket,
+        ..
     } = phase1_output;
 
-    ensure!(comm_d != [0; 32], "Invalid all zero commitment (comm_d)");
-    ensure!(comm_r != [0; 32], "Invalid all zero commitment (comm_r)");
+    ensure!(
+        comm_d != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_d)".into())
+    );
+    ensure!(
+        comm_r != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_r)".into())
+    );
     ensure!(seed != [0; 32], "Invalid porep challenge seed");
     ensure!(
         !vanilla_proofs.is_empty()
@@ -556,6 +926,20 @@ pub fn seal_commit_phase2<Tree: 'static + MerkleTreeTrait>(
                 .all(|partition_proofs| !partition_proofs.is_empty()),
         "C1 output contains no vanilla proofs",
     );
+    ensure!(!partitions.is_empty(), "no partitions to prove");
+
+    let mut vanilla_proofs: Vec<_> = vanilla_proofs.into_iter().map(Some).collect();
+    let vanilla_proofs = partitions
+        .iter()
+        .map(|&k| {
+            let partition_proofs = vanilla_proofs
+                .get_mut(k)
+                .with_context(|| format!("C1 output contains no partition {}", k))?
+                .take()
+                .with_context(|| format!("partition {} requested more than once", k))?;
+            Ok((k, partition_proofs))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
     let comm_r_safe = as_safe_commitment(&comm_r, "comm_r")?;
     let comm_d_safe = DefaultPieceDomain::try_from_bytes(&comm_d)?;
@@ -589,7 +973,7 @@ pub fn seal_commit_phase2<Tree: 'static + MerkleTreeTrait>(
     >>::setup(&compound_setup_params)?;
 
     trace!("snark_proof:start");
-    let groth_proofs = StackedCompound::<Tree, DefaultPieceHasher>::circuit_proofs(
+    let groth_proofs = StackedCompound::<Tree, DefaultPieceHasher>::circuit_proofs_for_partitions(
         &public_inputs,
         vanilla_proofs,
         &compound_public_params.vanilla_params,
@@ -598,31 +982,7 @@ pub fn seal_commit_phase2<Tree: 'static + MerkleTreeTrait>(
     )?;
     trace!("snark_proof:finish");
 
-    let verifying_key = get_stacked_verifying_key::<Tree>(porep_config)?;
-    let proof = MultiProof::new(groth_proofs, &verifying_key);
-    let mut buf =
-        Vec::with_capacity(SINGLE_PARTITION_PROOF_LEN * usize::from(porep_config.partitions));
-
-    proof.write(&mut buf)?;
-
-    // Verification is cheap when parameters are cached,
-    // and it is never correct to return a proof which does not verify.
-    verify_seal::<Tree>(
-        porep_config,
-        comm_r,
-        comm_d,
-        prover_id,
-        sector_id,
-        ticket,
-        seed,
-        &buf,
-    )
-    .context("post-seal verification sanity check failed")?;
-
-    let out = SealCommitOutput { proof: buf };
-
-    info!("seal_commit_phase2:finish: {:?}", sector_id);
-    Ok(out)
+    Ok(groth_proofs)
 }
 
 /// Given the specified arguments, this method returns the inputs that were used to
@@ -652,8 +1012,14 @@ pub fn get_seal_inputs<Tree: 'static + MerkleTreeTrait>(
 ) -> Result<Vec<Vec<Fr>>> {
     trace!("get_seal_inputs:start");
 
-    ensure!(comm_d != [0; 32], "Invalid all zero commitment (comm_d)");
-    ensure!(comm_r != [0; 32], "Invalid all zero commitment (comm_r)");
+    ensure!(
+        comm_d != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_d)".into())
+    );
+    ensure!(
+        comm_r != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_r)".into())
+    );
 
     let replica_id = generate_replica_id::<Tree::Hasher, _>(
         &prover_id,
@@ -1006,8 +1372,14 @@ pub fn verify_seal<Tree: 'static + MerkleTreeTrait>(
 ) -> Result<bool> {
     info!("verify_seal:start: {:?}", sector_id);
 
-    ensure!(comm_d_in != [0; 32], "Invalid all zero commitment (comm_d)");
-    ensure!(comm_r_in != [0; 32], "Invalid all zero commitment (comm_r)");
+    ensure!(
+        comm_d_in != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_d)".into())
+    );
+    ensure!(
+        comm_r_in != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_r)".into())
+    );
     ensure!(!proof_vec.is_empty(), "Invalid proof bytes (empty vector)");
 
     let comm_r: <Tree::Hasher as Hasher>::Domain = as_safe_commitment(&comm_r_in, "comm_r")?;
@@ -1107,13 +1479,13 @@ pub fn verify_batch_seal<Tree: 'static + MerkleTreeTrait>(
     for comm_d_in in comm_d_ins {
         ensure!(
             comm_d_in != &[0; 32],
-            "Invalid all zero commitment (comm_d)"
+            Error::InvalidCommitment("Invalid all zero commitment (comm_d)".into())
         );
     }
     for comm_r_in in comm_r_ins {
         ensure!(
             comm_r_in != &[0; 32],
-            "Invalid all zero commitment (comm_r)"
+            Error::InvalidCommitment("Invalid all zero commitment (comm_r)".into())
         );
     }
     for proofs in proof_vecs {
@@ -1198,24 +1570,8 @@ where
     O: AsRef<Path>,
     R: AsRef<Path>,
 {
-    let leaf_count = sector_size as usize / NODE_SIZE;
-    let base_tree_count = get_base_tree_count::<TreeR>();
-    let base_tree_leafs = leaf_count / base_tree_count;
-
-    let rows_to_discard = default_rows_to_discard(base_tree_leafs, TreeR::Arity::to_usize());
-    let size = get_base_tree_size::<TreeR>(SectorSize(sector_size))?;
-    let tree_r_last_config = StoreConfig {
-        path: PathBuf::from(output_dir.as_ref()),
-        id: CacheKey::CommRLastTree.to_string(),
-        size: Some(size),
-        // A default 'rows_to_discard' value will be chosen for tree_r_last, unless the user
-        // overrides this value via the environment setting (FIL_PROOFS_ROWS_TO_DISCARD). If
-        // this value is specified, no checking is done on it and it may result in a broken
-        // configuration. *Use with caution*. It must be noted that if/when this unchecked
-        // value is passed through merkle_light, merkle_light now does a check that does not
-        // allow us to discard more rows than is possible to discard.
-        rows_to_discard,
-    };
+    let (base_tree_leafs, base_tree_count, tree_r_last_config) =
+        tree_r_last_config::<TreeR>(sector_size, output_dir.as_ref())?;
 
     let replica_base_tree_size = get_base_tree_size::<DefaultBinaryTree>(sector_size.into())?;
     let replica_base_tree_leafs = get_base_tree_leafs::<DefaultBinaryTree>(replica_base_tree_size)?;
@@ -1238,6 +1594,53 @@ where
     Ok(tree_r_last.root())
 }
 
+/// Rebuilds only the sub-trees of TreeRLast with the given `indices`, i.e. the
+/// `sc-02-data-tree-r-last-<index>.dat` files in `cache_path`, from the replica.
+///
+/// The rebuilt sub-trees are only moved into place if their roots, combined with the roots of
+/// the other sub-trees, match `comm_r_last` from the `p_aux` stored in `cache_path`. Otherwise
+/// the existing files are left as they are. The `sector_size` is in bytes.
+pub fn rebuild_tree_r_last<C, R, TreeR: 'static + MerkleTreeTrait>(
+    sector_size: u64,
+    replica_path: R,
+    cache_path: C,
+    indices: &[usize],
+) -> Result<()>
+where
+    C: AsRef<Path>,
+    R: AsRef<Path>,
+{
+    info!("rebuild_tree_r_last:start: {:?}", indices);
+
+    let p_aux = read_p_aux::<TreeR>(cache_path.as_ref())?;
+    let (base_tree_leafs, base_tree_count, tree_r_last_config) =
+        tree_r_last_config::<TreeR>(sector_size, cache_path.as_ref())?;
+
+    let replica_base_tree_size = get_base_tree_size::<DefaultBinaryTree>(sector_size.into())?;
+    let replica_base_tree_leafs = get_base_tree_leafs::<DefaultBinaryTree>(replica_base_tree_size)?;
+    let replica = DiskStore::new_from_disk_with_path(replica_base_tree_leafs, &replica_path)?;
+
+    let matches = StackedDrg::<TreeR, DefaultPieceHasher>::rebuild_tree_r_last_sub_trees(
+        base_tree_leafs,
+        base_tree_count,
+        tree_r_last_config,
+        PathBuf::from(replica_path.as_ref()),
+        &replica,
+        indices,
+        &p_aux.comm_r_last,
+    )?;
+    ensure!(
+        matches,
+        Error::CacheCorrupted(format!(
+            "rebuilt tree_r_last sub-trees {:?} do not match comm_r_last",
+            indices
+        ))
+    );
+
+    info!("rebuild_tree_r_last:finish");
+    Ok(())
+}
+
 /// Generate the merkle tree on top of the labels (TreeC).
 ///
 /// The generated trees are stored in `output_dir`, usually the cache directory. The `input_dir`
@@ -1253,34 +1656,10 @@ where
     I: AsRef<Path>,
     O: AsRef<Path>,
 {
-    let leaf_count = sector_size as usize / NODE_SIZE;
-    let base_tree_count = get_base_tree_count::<Tree>();
-    let base_tree_leafs = leaf_count / base_tree_count;
-
-    let rows_to_discard = default_rows_to_discard(base_tree_leafs, Tree::Arity::to_usize());
-    let size = get_base_tree_size::<Tree>(SectorSize(sector_size))?;
-    let tree_c_config = StoreConfig {
-        path: PathBuf::from(output_dir.as_ref()),
-        id: CacheKey::CommCTree.to_string(),
-        size: Some(size),
-        rows_to_discard,
-    };
+    let (base_tree_leafs, base_tree_count, tree_c_config) =
+        tree_c_config::<Tree>(sector_size, output_dir.as_ref())?;
     let configs = split_config(tree_c_config, base_tree_count)?;
-
-    let labels_cache = {
-        let label_base_tree_size = get_base_tree_size::<DefaultBinaryTree>(sector_size.into())?;
-        let label_base_tree_leafs = get_base_tree_leafs::<DefaultBinaryTree>(label_base_tree_size)?;
-        let label_configs = (1..=num_layers)
-            .map(|layer| StoreConfig {
-                path: PathBuf::from(input_dir.as_ref()),
-                id: CacheKey::label_layer(layer),
-                size: Some(label_base_tree_leafs),
-                rows_to_discard: default_rows_to_discard(label_base_tree_leafs, BINARY_ARITY),
-            })
-            .collect();
-        let labels = Labels::new(label_configs);
-        LabelsCache::<Tree>::new(&labels).context("failed to create labels cache")?
-    };
+    let labels_cache = open_labels_cache::<Tree>(sector_size, input_dir.as_ref(), num_layers)?;
 
     let tree_c = match num_layers {
         2 => StackedDrg::<Tree, DefaultPieceHasher>::generate_tree_c::<U2, Tree::Arity>(
@@ -1301,6 +1680,137 @@ where
     Ok(tree_c.root())
 }
 
+/// Rebuilds only the sub-trees of TreeC with the given `indices`, i.e. the
+/// `sc-02-data-tree-c-<index>.dat` files in `cache_path`, from the labels stored in `input_dir`.
+///
+/// The rebuilt sub-trees are only moved into place if their roots, combined with the roots of
+/// the other sub-trees, match `comm_c` from the `p_aux` stored in `cache_path`. Otherwise the
+/// existing files are left as they are. The `sector_size` is in bytes.
+pub fn rebuild_tree_c<I, C, Tree: 'static + MerkleTreeTrait>(
+    sector_size: u64,
+    input_dir: I,
+    cache_path: C,
+    num_layers: usize,
+    indices: &[usize],
+) -> Result<()>
+where
+    I: AsRef<Path>,
+    C: AsRef<Path>,
+{
+    info!("rebuild_tree_c:start: {:?}", indices);
+
+    ensure!(
+        num_layers == 2 || num_layers == 11,
+        Error::InvalidConfig("Unsupported column arity".into())
+    );
+    let p_aux = read_p_aux::<Tree>(cache_path.as_ref())?;
+    let (base_tree_leafs, base_tree_count, tree_c_config) =
+        tree_c_config::<Tree>(sector_size, cache_path.as_ref())?;
+    let configs = split_config(tree_c_config, base_tree_count)?;
+    let labels_cache = open_labels_cache::<Tree>(sector_size, input_dir.as_ref(), num_layers)?;
+
+    let matches = StackedDrg::<Tree, DefaultPieceHasher>::rebuild_tree_c_sub_trees(
+        base_tree_leafs,
+        &configs,
+        &labels_cache,
+        indices,
+        &p_aux.comm_c,
+    )?;
+    ensure!(
+        matches,
+        Error::CacheCorrupted(format!(
+            "rebuilt tree_c sub-trees {:?} do not match comm_c",
+            indices
+        ))
+    );
+
+    info!("rebuild_tree_c:finish");
+    Ok(())
+}
+
+/// Returns the number of leafs per base tree, the number of base trees and the store config of
+/// TreeRLast within `output_dir`.
+fn tree_r_last_config<TreeR: MerkleTreeTrait>(
+    sector_size: u64,
+    output_dir: &Path,
+) -> Result<(usize, usize, StoreConfig)> {
+    let leaf_count = sector_size as usize / NODE_SIZE;
+    let base_tree_count = get_base_tree_count::<TreeR>();
+    let base_tree_leafs = leaf_count / base_tree_count;
+
+    let rows_to_discard = default_rows_to_discard(base_tree_leafs, TreeR::Arity::to_usize());
+    let size = get_base_tree_size::<TreeR>(SectorSize(sector_size))?;
+    let tree_r_last_config = StoreConfig {
+        path: PathBuf::from(output_dir),
+        id: CacheKey::CommRLastTree.to_string(),
+        size: Some(size),
+        // A default 'rows_to_discard' value will be chosen for tree_r_last, unless the user
+        // overrides this value via the environment setting (FIL_PROOFS_ROWS_TO_DISCARD). If
+        // this value is specified, no checking is done on it and it may result in a broken
+        // configuration. *Use with caution*. It must be noted that if/when this unchecked
+        // value is passed through merkle_light, merkle_light now does a check that does not
+        // allow us to discard more rows than is possible to discard.
+        rows_to_discard,
+    };
+
+    Ok((base_tree_leafs, base_tree_count, tree_r_last_config))
+}
+
+/// Returns the number of leafs per base tree, the number of base trees and the store config of
+/// TreeC within `output_dir`.
+fn tree_c_config<Tree: MerkleTreeTrait>(
+    sector_size: u64,
+    output_dir: &Path,
+) -> Result<(usize, usize, StoreConfig)> {
+    let leaf_count = sector_size as usize / NODE_SIZE;
+    let base_tree_count = get_base_tree_count::<Tree>();
+    let base_tree_leafs = leaf_count / base_tree_count;
+
+    let rows_to_discard = default_rows_to_discard(base_tree_leafs, Tree::Arity::to_usize());
+    let size = get_base_tree_size::<Tree>(SectorSize(sector_size))?;
+    let tree_c_config = StoreConfig {
+        path: PathBuf::from(output_dir),
+        id: CacheKey::CommCTree.to_string(),
+        size: Some(size),
+        rows_to_discard,
+    };
+
+    Ok((base_tree_leafs, base_tree_count, tree_c_config))
+}
+
+fn open_labels_cache<Tree: 'static + MerkleTreeTrait>(
+    sector_size: u64,
+    input_dir: &Path,
+    num_layers: usize,
+) -> Result<LabelsCache<Tree>> {
+    let label_base_tree_size = get_base_tree_size::<DefaultBinaryTree>(sector_size.into())?;
+    let label_base_tree_leafs = get_base_tree_leafs::<DefaultBinaryTree>(label_base_tree_size)?;
+    let label_configs = (1..=num_layers)
+        .map(|layer| StoreConfig {
+            path: PathBuf::from(input_dir),
+            id: CacheKey::label_layer(layer),
+            size: Some(label_base_tree_leafs),
+            rows_to_discard: default_rows_to_discard(label_base_tree_leafs, BINARY_ARITY),
+        })
+        .collect();
+    let labels = Labels::new(label_configs);
+
+    LabelsCache::<Tree>::new(&labels).context("failed to create labels cache")
+}
+
+fn read_p_aux<Tree: MerkleTreeTrait>(
+    cache_path: &Path,
+) -> Result<PersistentAux<<Tree::Hasher as Hasher>::Domain>> {
+    let p_aux_path = cache_path.join(CacheKey::PAux.to_string());
+    let p_aux_bytes = fs::read(&p_aux_path).with_context(|| {
+        Error::CacheCorrupted(format!("could not read file p_aux={:?}", p_aux_path))
+    })?;
+
+    deserialize(&p_aux_bytes).with_context(|| {
+        Error::CacheCorrupted(format!("could not deserialize p_aux={:?}", p_aux_path))
+    })
+}
+
 pub fn sdr<P, Tree: 'static + MerkleTreeTrait>(
     porep_config: &PoRepConfig,
     cache_path: P,
@@ -0,0 +1,294 @@
+use std::fs::{self, rename};
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+use storage_proofs_core::{
+    api_version::ApiFeature, cache_key::CacheKey, merkle::MerkleTreeTrait, sector::SectorId,
+};
+
+use crate::{
+    api::{
+        generate_synth_proofs, resume_seal_pre_commit_phase1, seal_commit_phase1,
+        seal_commit_phase2, seal_pre_commit_phase2,
+    },
+    error::Error,
+    types::{
+        PieceInfo, PoRepConfig, ProverId, SealCommitPhase1Output, SealPreCommitOutput,
+        SealPreCommitPhase1Input, SealPreCommitPhase1Output, Ticket,
+    },
+};
+
+/// The phase a `SealingJob` runs on its next call to `advance`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SealingPhase {
+    PreCommit1,
+    PreCommit2,
+    /// Pre-commit is done, `SealingJob::set_seed` has to be called before commit can start.
+    WaitSeed,
+    Commit1,
+    Commit2,
+    /// The proof is available through `SealingJob::proof`.
+    Done,
+}
+
+/// The outputs of the last completed phase, which are the inputs of the next one.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "SealPreCommitPhase1Output<Tree>: Serialize, SealCommitPhase1Output<Tree>: Serialize",
+    deserialize = "SealPreCommitPhase1Output<Tree>: Deserialize<'de>, SealCommitPhase1Output<Tree>: Deserialize<'de>"
+))]
+enum SealingState<Tree: MerkleTreeTrait> {
+    PreCommit1,
+    PreCommit2(SealPreCommitPhase1Output<Tree>),
+    Commit1,
+    Commit2(SealCommitPhase1Output<Tree>),
+    Done(Vec<u8>),
+}
+
+/// Everything a `SealingJob` persists in its cache dir.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "SealingState<Tree>: Serialize",
+    deserialize = "SealingState<Tree>: Deserialize<'de>"
+))]
+struct SealingJobRecord<Tree: MerkleTreeTrait> {
+    /// Sector size and porep id of the `PoRepConfig` the job was created with.
+    sector_size: u64,
+    porep_id: [u8; 32],
+    cache_path: PathBuf,
+    in_path: PathBuf,
+    out_path: PathBuf,
+    prover_id: ProverId,
+    sector_id: SectorId,
+    ticket: Ticket,
+    piece_infos: Vec<PieceInfo>,
+    seed: Option<Ticket>,
+    pre_commit: Option<SealPreCommitOutput>,
+    state: SealingState<Tree>,
+}
+
+/// Seals a sector by running the four sealing phases one after another.
+///
+/// The job stores its inputs, its current phase and the output of the last completed phase as
+/// JSON in the cache dir of the sector, after each phase. A job interrupted by a restart is
+/// picked up again with `SealingJob::load`, calling `advance` then reruns the phase which did
+/// not complete, where pre-commit phase1 resumes from its label checkpoint.
+#[derive(Debug)]
+pub struct SealingJob<Tree: 'static + MerkleTreeTrait> {
+    porep_config: PoRepConfig,
+    record: SealingJobRecord<Tree>,
+}
+
+impl<Tree: 'static + MerkleTreeTrait> SealingJob<Tree> {
+    /// Returns the path of the job file in `cache_path`.
+    pub fn path<P: AsRef<Path>>(cache_path: P) -> PathBuf {
+        cache_path.as_ref().join(CacheKey::SealingJob.to_string())
+    }
+
+    /// Creates a new job for `sector` and persists it in its cache dir. Fails if the cache dir
+    /// already contains a job, which has to be resumed with `load` instead.
+    pub fn create(porep_config: &PoRepConfig, sector: SealPreCommitPhase1Input) -> Result<Self> {
+        let path = Self::path(&sector.cache_path);
+        ensure!(
+            !path.exists(),
+            "sealing job {:?} already exists, load it instead",
+            path
+        );
+
+        let job = SealingJob {
+            porep_config: porep_config.clone(),
+            record: SealingJobRecord {
+                sector_size: u64::from(porep_config.sector_size),
+                porep_id: porep_config.porep_id,
+                cache_path: sector.cache_path,
+                in_path: sector.in_path,
+                out_path: sector.out_path,
+                prover_id: sector.prover_id,
+                sector_id: sector.sector_id,
+                ticket: sector.ticket,
+                piece_infos: sector.piece_infos,
+                seed: None,
+                pre_commit: None,
+                state: SealingState::PreCommit1,
+            },
+        };
+        job.write()?;
+
+        Ok(job)
+    }
+
+    /// Loads the job persisted in `cache_path`, which must have been created with the same
+    /// `porep_config`. The job continues in `cache_path`, even if it was created in a cache dir
+    /// at another path, e.g. before the cache dir was moved or remounted.
+    pub fn load<P: AsRef<Path>>(porep_config: &PoRepConfig, cache_path: P) -> Result<Self> {
+        let path = Self::path(&cache_path);
+        let bytes =
+            fs::read(&path).with_context(|| format!("failed to read sealing job {:?}", path))?;
+        let mut record: SealingJobRecord<Tree> =
+            serde_json::from_slice(&bytes).with_context(|| {
+                Error::CacheCorrupted(format!("failed to parse sealing job {:?}", path))
+            })?;
+        ensure!(
+            record.sector_size == u64::from(porep_config.sector_size)
+                && record.porep_id == porep_config.porep_id,
+            Error::InvalidConfig(format!(
+                "sealing job {:?} was created with a different porep config",
+                path
+            ))
+        );
+        record.cache_path = cache_path.as_ref().to_path_buf();
+
+        Ok(SealingJob {
+            porep_config: porep_config.clone(),
+            record,
+        })
+    }
+
+    pub fn phase(&self) -> SealingPhase {
+        match self.record.state {
+            SealingState::PreCommit1 => SealingPhase::PreCommit1,
+            SealingState::PreCommit2(_) => SealingPhase::PreCommit2,
+            SealingState::Commit1 if self.record.seed.is_none() => SealingPhase::WaitSeed,
+            SealingState::Commit1 => SealingPhase::Commit1,
+            SealingState::Commit2(_) => SealingPhase::Commit2,
+            SealingState::Done(_) => SealingPhase::Done,
+        }
+    }
+
+    pub fn sector_id(&self) -> SectorId {
+        self.record.sector_id
+    }
+
+    /// The output of pre-commit, available once pre-commit phase2 completed.
+    pub fn pre_commit_output(&self) -> Option<&SealPreCommitOutput> {
+        self.record.pre_commit.as_ref()
+    }
+
+    /// The seal proof, available once the job is done.
+    pub fn proof(&self) -> Option<&[u8]> {
+        match &self.record.state {
+            SealingState::Done(proof) => Some(proof),
+            _ => None,
+        }
+    }
+
+    /// Sets the interactive seed commit is run with. Setting the same seed again is a no-op,
+    /// a different one is rejected once commit phase1 has run with the first one.
+    pub fn set_seed(&mut self, seed: Ticket) -> Result<()> {
+        if self.record.seed == Some(seed) {
+            return Ok(());
+        }
+        ensure!(
+            matches!(
+                self.record.state,
+                SealingState::PreCommit1 | SealingState::PreCommit2(_) | SealingState::Commit1
+            ),
+            "seed cannot be changed after commit phase1 ran"
+        );
+
+        self.record.seed = Some(seed);
+        self.write()
+    }
+
+    /// Runs the next phase and persists its output, returns the phase to run next.
+    ///
+    /// Does nothing if the job is done or waiting for its seed. A phase which fails leaves the
+    /// job unchanged, so that calling `advance` again retries it.
+    pub fn advance(&mut self) -> Result<SealingPhase> {
+        let record = &self.record;
+        let porep_config = &self.porep_config;
+        info!(
+            "sealing job {:?}: running {:?}",
+            record.sector_id,
+            self.phase()
+        );
+
+        let (pre_commit, state) = match &record.state {
+            SealingState::PreCommit1 => {
+                let phase1_output = resume_seal_pre_commit_phase1::<_, _, _, Tree>(
+                    porep_config,
+                    &record.cache_path,
+                    &record.in_path,
+                    &record.out_path,
+                    record.prover_id,
+                    record.sector_id,
+                    record.ticket,
+                    &record.piece_infos,
+                )?;
+                (None, SealingState::PreCommit2(phase1_output))
+            }
+            SealingState::PreCommit2(phase1_output) => {
+                let pre_commit = seal_pre_commit_phase2(
+                    porep_config,
+                    phase1_output.clone(),
+                    &record.cache_path,
+                    &record.out_path,
+                )?;
+                if porep_config.feature_enabled(ApiFeature::SyntheticPoRep) {
+                    generate_synth_proofs::<_, Tree>(
+                        porep_config,
+                        &record.cache_path,
+                        &record.out_path,
+                        record.prover_id,
+                        record.sector_id,
+                        record.ticket,
+                        pre_commit.clone(),
+                        &record.piece_infos,
+                    )?;
+                }
+                (Some(pre_commit), SealingState::Commit1)
+            }
+            SealingState::Commit1 => {
+                let (seed, pre_commit) = match (record.seed, &record.pre_commit) {
+                    (Some(seed), Some(pre_commit)) => (seed, pre_commit.clone()),
+                    _ => return Ok(self.phase()),
+                };
+                let phase1_output = seal_commit_phase1::<_, Tree>(
+                    porep_config,
+                    &record.cache_path,
+                    &record.out_path,
+                    record.prover_id,
+                    record.sector_id,
+                    record.ticket,
+                    seed,
+                    pre_commit,
+                    &record.piece_infos,
+                )?;
+                (None, SealingState::Commit2(phase1_output))
+            }
+            SealingState::Commit2(phase1_output) => {
+                let output = seal_commit_phase2(
+                    porep_config,
+                    phase1_output.clone(),
+                    record.prover_id,
+                    record.sector_id,
+                )?;
+                (None, SealingState::Done(output.proof))
+            }
+            SealingState::Done(_) => return Ok(SealingPhase::Done),
+        };
+
+        if pre_commit.is_some() {
+            self.record.pre_commit = pre_commit;
+        }
+        self.record.state = state;
+        self.write()?;
+
+        Ok(self.phase())
+    }
+
+    /// Stores the job atomically, by writing first to `.tmp` and then renaming.
+    fn write(&self) -> Result<()> {
+        let path = Self::path(&self.record.cache_path);
+        let tmp_path = path.with_extension("tmp");
+        let bytes = serde_json::to_vec(&self.record).context("failed to serialize sealing job")?;
+        fs::write(&tmp_path, bytes)
+            .with_context(|| Error::Io(format!("failed to write sealing job {:?}", tmp_path)))?;
+        rename(&tmp_path, &path)
+            .with_context(|| Error::Io(format!("failed to rename sealing job {:?}", tmp_path)))?;
+
+        Ok(())
+    }
+}
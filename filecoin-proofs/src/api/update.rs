@@ -34,6 +34,7 @@ use crate::{
     caches::{get_empty_sector_update_params, get_empty_sector_update_verifying_key},
     chunk_iter::ChunkIterator,
     constants::{DefaultPieceDomain, DefaultPieceHasher},
+    error::Error,
     pieces::verify_pieces,
     types::{
         Commitment, EmptySectorUpdateEncoded, EmptySectorUpdateProof, PieceInfo, PoRepConfig,
@@ -46,10 +47,13 @@ fn get_p_aux<Tree: 'static + MerkleTreeTrait<Hasher = TreeRHasher>>(
     cache_path: &Path,
 ) -> Result<PersistentAux<<Tree::Hasher as Hasher>::Domain>> {
     let p_aux_path = cache_path.join(CacheKey::PAux.to_string());
-    let p_aux_bytes = fs::read(&p_aux_path)
-        .with_context(|| format!("could not read file p_aux={:?}", p_aux_path))?;
+    let p_aux_bytes = fs::read(&p_aux_path).with_context(|| {
+        Error::CacheCorrupted(format!("could not read file p_aux={:?}", p_aux_path))
+    })?;
 
-    let p_aux = deserialize(&p_aux_bytes)?;
+    let p_aux = deserialize(&p_aux_bytes).with_context(|| {
+        Error::CacheCorrupted(format!("could not deserialize p_aux={:?}", p_aux_path))
+    })?;
 
     Ok(p_aux)
 }
@@ -60,11 +64,11 @@ fn persist_p_aux<Tree: 'static + MerkleTreeTrait<Hasher = TreeRHasher>>(
 ) -> Result<()> {
     let p_aux_path = cache_path.join(CacheKey::PAux.to_string());
     let mut f_p_aux = File::create(&p_aux_path)
-        .with_context(|| format!("could not create file p_aux={:?}", p_aux_path))?;
+        .with_context(|| Error::Io(format!("could not create file p_aux={:?}", p_aux_path)))?;
     let p_aux_bytes = serialize(&p_aux)?;
     f_p_aux
         .write_all(&p_aux_bytes)
-        .with_context(|| format!("could not write to file p_aux={:?}", p_aux_path))?;
+        .with_context(|| Error::Io(format!("could not write to file p_aux={:?}", p_aux_path)))?;
 
     Ok(())
 }
@@ -76,10 +80,14 @@ fn get_t_aux<Tree: 'static + MerkleTreeTrait<Hasher = TreeRHasher>>(
 ) -> Result<TemporaryAux<Tree, DefaultPieceHasher>> {
     let t_aux_path = cache_path.join(CacheKey::TAux.to_string());
     trace!("Instantiating TemporaryAux from {:?}", cache_path);
-    let t_aux_bytes = fs::read(&t_aux_path)
-        .with_context(|| format!("could not read file t_aux={:?}", t_aux_path))?;
-
-    let mut res: TemporaryAux<Tree, DefaultPieceHasher> = deserialize(&t_aux_bytes)?;
+    let t_aux_bytes = fs::read(&t_aux_path).with_context(|| {
+        Error::CacheCorrupted(format!("could not read file t_aux={:?}", t_aux_path))
+    })?;
+
+    let mut res: TemporaryAux<Tree, DefaultPieceHasher> =
+        deserialize(&t_aux_bytes).with_context(|| {
+            Error::CacheCorrupted(format!("could not deserialize t_aux={:?}", t_aux_path))
+        })?;
     res.set_cache_path(cache_path);
     trace!("Set TemporaryAux cache_path to {:?}", cache_path);
 
@@ -92,11 +100,11 @@ fn persist_t_aux<Tree: 'static + MerkleTreeTrait<Hasher = TreeRHasher>>(
 ) -> Result<()> {
     let t_aux_path = cache_path.join(CacheKey::TAux.to_string());
     let mut f_t_aux = File::create(&t_aux_path)
-        .with_context(|| format!("could not create file t_aux={:?}", t_aux_path))?;
+        .with_context(|| Error::Io(format!("could not create file t_aux={:?}", t_aux_path)))?;
     let t_aux_bytes = serialize(&t_aux)?;
     f_t_aux
         .write_all(&t_aux_bytes)
-        .with_context(|| format!("could not write to file t_aux={:?}", t_aux_path))?;
+        .with_context(|| Error::Io(format!("could not write to file t_aux={:?}", t_aux_path)))?;
 
     Ok(())
 }
@@ -186,15 +194,21 @@ pub fn encode_into<Tree: 'static + MerkleTreeTrait<Hasher = TreeRHasher>>(
     comm_r_domain.write_bytes(&mut comm_r)?;
     comm_r_last_domain.write_bytes(&mut comm_r_last)?;
 
-    ensure!(comm_d != [0; 32], "Invalid all zero commitment (comm_d)");
-    ensure!(comm_r != [0; 32], "Invalid all zero commitment (comm_r)");
+    ensure!(
+        comm_d != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_d)".into())
+    );
+    ensure!(
+        comm_r != [0; 32],
+        Error::InvalidCommitment("Invalid all zero commitment (comm_r)".into())
+    );
     ensure!(
         comm_r_last != [0; 32],
-        "Invalid all zero commitment (comm_r)"
+        Error::InvalidCommitment("Invalid all zero commitment (comm_r)".into())
     );
     ensure!(
         verify_pieces(&comm_d, piece_infos, porep_config.sector_size)?,
-        "pieces and comm_d do not match"
+        Error::InvalidCommitment("pieces and comm_d do not match".into())
     );
 
     // Persist p_aux and t_aux into the new_cache_path here
@@ -221,6 +235,9 @@ pub fn encode_into<Tree: 'static + MerkleTreeTrait<Hasher = TreeRHasher>>(
 /// needed in order to do the decoding correctly. The `nodes_count` is the total number of nodes
 /// within the file. The `num_nodes` defines how many nodes will be decoded, starting from the
 /// current position.
+///
+/// The decoded nodes are written as they are stored in the sector, i.e. Fr32 padded. Wrapping
+/// `output_data` in an [`fr32::Fr32Writer`] writes the unpadded data instead.
 #[allow(clippy::too_many_arguments)]
 pub fn decode_from_range<R: Read, S: Read, W: Write>(
     nodes_count: usize,
@@ -9,15 +9,18 @@ use merkletree::merkle::{get_merkle_tree_leafs, get_merkle_tree_len};
 use storage_proofs_core::merkle::{get_base_tree_count, MerkleTreeTrait};
 use typenum::Unsigned;
 
-use crate::types::{Commitment, SectorSize};
+use crate::{
+    error::Error,
+    types::{Commitment, SectorSize},
+};
 
 pub fn as_safe_commitment<H: Domain, T: AsRef<str>>(
     comm: &[u8; 32],
     commitment_name: T,
 ) -> Result<H> {
-    bytes_into_fr(comm)
-        .map(Into::into)
-        .with_context(|| format!("Invalid commitment ({})", commitment_name.as_ref(),))
+    bytes_into_fr(comm).map(Into::into).with_context(|| {
+        Error::InvalidCommitment(format!("Invalid commitment ({})", commitment_name.as_ref()))
+    })
 }
 
 pub fn commitment_from_fr(fr: Fr) -> Commitment {
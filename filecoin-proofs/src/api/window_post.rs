@@ -1,33 +1,67 @@
 use std::collections::BTreeMap;
+use std::io;
+use std::panic;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, RecvTimeoutError},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{ensure, Context, Result};
-use filecoin_hashers::Hasher;
-use log::info;
-use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use bellperson::groth16::verify_proofs_batch;
+use filecoin_hashers::{HashFunction, Hasher};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use rand::rngs::OsRng;
+use rayon::{
+    prelude::{IntoParallelRefIterator, ParallelIterator},
+    ThreadPool, ThreadPoolBuilder,
+};
 use storage_proofs_core::{
     compound_proof::{self, CompoundProof},
-    merkle::MerkleTreeTrait,
+    error::Error as CoreError,
+    merkle::{MerkleProofTrait, MerkleTreeTrait},
     multi_proof::MultiProof,
+    proof::ProofScheme,
     sector::SectorId,
+    settings::SETTINGS,
+    util::NODE_SIZE,
 };
 use storage_proofs_post::fallback::{
-    self, FallbackPoSt, FallbackPoStCompound, PrivateSector, PublicSector,
+    self, generate_leaf_challenge, get_challenge_index, FallbackPoSt, FallbackPoStCompound,
+    PrivateSector, PublicSector,
 };
 
 use crate::{
     api::{
-        as_safe_commitment, get_partitions_for_window_post, partition_vanilla_proofs,
-        single_partition_vanilla_proofs, util,
+        as_safe_commitment, generate_fallback_sector_challenges, generate_single_vanilla_proof,
+        get_partitions_for_window_post, merge_window_post_partition_proofs,
+        partition_vanilla_proofs, single_partition_vanilla_proofs, util,
     },
     caches::{get_post_params, get_post_verifying_key},
+    error::{classify, Error},
     parameters::window_post_setup_params,
     types::{
         ChallengeSeed, FallbackPoStSectorProof, PoStConfig, PrivateReplicaInfo, ProverId,
-        PublicReplicaInfo, SnarkProof,
+        PublicReplicaInfo, SectorHealth, SectorHealthReport, SnarkProof, WindowPoStFailure,
+        WindowPoStOutput, WindowPoStReport,
     },
     PartitionSnarkProof, PoStType,
 };
 
+lazy_static! {
+    /// The threads `check_sectors_for_post` checks sectors on. A check blocked on a hung read keeps
+    /// its thread, so repeated scans cannot pile up more blocked threads than the pool has. Its
+    /// size is set by `sector_check_num_threads`.
+    static ref SECTOR_CHECK_POOL: ThreadPool = ThreadPoolBuilder::new()
+        .num_threads(SETTINGS.sector_check_num_threads as usize)
+        .thread_name(|index| format!("sector-check-{}", index))
+        .build()
+        .expect("failed to build sector check pool");
+}
+
 /// Generates a Window proof-of-spacetime with provided vanilla proofs.
 pub fn generate_window_post_with_vanilla<Tree: 'static + MerkleTreeTrait>(
     post_config: &PoStConfig,
@@ -38,7 +72,7 @@ pub fn generate_window_post_with_vanilla<Tree: 'static + MerkleTreeTrait>(
     info!("generate_window_post_with_vanilla:start");
     ensure!(
         post_config.typ == PoStType::Window,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     let randomness_safe: <Tree::Hasher as Hasher>::Domain =
@@ -89,7 +123,8 @@ pub fn generate_window_post_with_vanilla<Tree: 'static + MerkleTreeTrait>(
         &pub_inputs,
         partitioned_proofs,
         &groth_params,
-    )?;
+    )
+    .map_err(classify)?;
 
     info!("generate_window_post_with_vanilla:finish");
 
@@ -106,7 +141,7 @@ pub fn generate_window_post<Tree: 'static + MerkleTreeTrait>(
     info!("generate_window_post:start");
     ensure!(
         post_config.typ == PoStType::Window,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     let randomness_safe = as_safe_commitment(randomness, "randomness")?;
@@ -169,14 +204,213 @@ pub fn generate_window_post<Tree: 'static + MerkleTreeTrait>(
         sectors: &priv_sectors,
     };
 
-    let proofs =
-        FallbackPoStCompound::prove(&pub_params, &pub_inputs, &priv_inputs, &groth_params)?;
+    let proofs = FallbackPoStCompound::prove(&pub_params, &pub_inputs, &priv_inputs, &groth_params)
+        .map_err(classify)?;
 
     info!("generate_window_post:finish");
 
     util::proofs_to_bytes(&proofs)
 }
 
+/// Generates a Window proof-of-spacetime like `generate_window_post`, one partition at a time.
+///
+/// The trees and vanilla proofs of a partition are only held while it is proven, at most
+/// `max_concurrent_partitions` partitions are proven at once. Peak memory is therefore bounded
+/// by that number instead of the number of sectors. Fails with the error of the first partition
+/// which fails, which is `Error::FaultySectors` listing its faulty sectors if the failure is due to
+/// the files of its sectors. The partitions after the ones proven concurrently with it are not
+/// proven.
+///
+/// Every partition is proven on a thread of its own instead of a rayon task, as the prover blocks
+/// its thread while it waits on work it hands to the rayon pool.
+pub fn generate_window_post_streaming<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    randomness: &ChallengeSeed,
+    replicas: &BTreeMap<SectorId, PrivateReplicaInfo<Tree>>,
+    prover_id: ProverId,
+    max_concurrent_partitions: usize,
+) -> Result<SnarkProof> {
+    info!("generate_window_post_streaming:start");
+    ensure!(
+        post_config.typ == PoStType::Window,
+        Error::InvalidConfig("invalid post config type".into())
+    );
+    ensure!(
+        max_concurrent_partitions > 0,
+        Error::InvalidConfig("max_concurrent_partitions must be at least 1".into())
+    );
+
+    let sector_ids: Vec<SectorId> = replicas.keys().copied().collect();
+    let challenges = generate_fallback_sector_challenges::<Tree>(
+        post_config,
+        randomness,
+        &sector_ids,
+        prover_id,
+    )?;
+
+    let partitions: Vec<&[SectorId]> = sector_ids.chunks(post_config.sector_count).collect();
+    let mut proofs = Vec::with_capacity(partitions.len());
+    for (group_index, group) in partitions.chunks(max_concurrent_partitions).enumerate() {
+        let group_proofs = thread::scope(|s| {
+            let handles = group
+                .iter()
+                .enumerate()
+                .map(|(i, partition_sectors)| {
+                    let partition_index = group_index * max_concurrent_partitions + i;
+                    let challenges = &challenges;
+                    s.spawn(move || {
+                        let vanilla_proofs = partition_vanilla_proofs_streaming(
+                            post_config,
+                            replicas,
+                            challenges,
+                            partition_sectors,
+                        )?;
+
+                        generate_single_window_post_with_vanilla::<Tree>(
+                            post_config,
+                            randomness,
+                            prover_id,
+                            vanilla_proofs,
+                            partition_index,
+                        )
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|err| panic::resume_unwind(err))
+                })
+                .collect::<Vec<_>>()
+        });
+        // Collected in partition order, so that the error of the first failing partition is kept.
+        proofs.extend(group_proofs.into_iter().collect::<Result<Vec<_>>>()?);
+    }
+
+    info!("generate_window_post_streaming:finish");
+
+    merge_window_post_partition_proofs(proofs)
+}
+
+/// Generates the vanilla proofs of the sectors of a single partition, opening their trees only
+/// for the time it takes to prove them.
+fn partition_vanilla_proofs_streaming<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    replicas: &BTreeMap<SectorId, PrivateReplicaInfo<Tree>>,
+    challenges: &BTreeMap<SectorId, Vec<u64>>,
+    partition_sectors: &[SectorId],
+) -> Result<Vec<FallbackPoStSectorProof<Tree>>> {
+    let (vanilla_proofs, faulty) = vanilla_proofs_or_faulty(
+        post_config,
+        replicas,
+        challenges,
+        partition_sectors,
+        "generate_window_post_streaming",
+    )?;
+    ensure!(faulty.is_empty(), Error::FaultySectors(faulty));
+
+    Ok(vanilla_proofs)
+}
+
+/// Generates a Window proof-of-spacetime over the healthy sectors in `replicas` only.
+///
+/// A sector whose vanilla proof fails with `Error::FaultySectors`, i.e. because of its own files,
+/// is skipped, any other error is returned. As challenges depend on the position of a sector, the
+/// vanilla proofs of the remaining sectors are regenerated until no further sector fails, the
+/// proof is then generated from them with `partition_vanilla_proofs`.
+/// The skipped sectors are returned with the proof, which verifies against the public replica
+/// infos of the proven sectors. Fails with `Error::FaultySectors` if every sector is faulty.
+pub fn generate_window_post_skip_faulty<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    randomness: &ChallengeSeed,
+    replicas: &BTreeMap<SectorId, PrivateReplicaInfo<Tree>>,
+    prover_id: ProverId,
+) -> Result<WindowPoStOutput> {
+    info!("generate_window_post_skip_faulty:start");
+    ensure!(
+        post_config.typ == PoStType::Window,
+        Error::InvalidConfig("invalid post config type".into())
+    );
+
+    let mut sector_ids: Vec<SectorId> = replicas.keys().copied().collect();
+    let mut skipped = Vec::new();
+    let vanilla_proofs = loop {
+        ensure!(!sector_ids.is_empty(), Error::FaultySectors(skipped));
+
+        let challenges = generate_fallback_sector_challenges::<Tree>(
+            post_config,
+            randomness,
+            &sector_ids,
+            prover_id,
+        )?;
+        let (vanilla_proofs, faulty) = vanilla_proofs_or_faulty(
+            post_config,
+            replicas,
+            &challenges,
+            &sector_ids,
+            "generate_window_post_skip_faulty",
+        )?;
+        if faulty.is_empty() {
+            break vanilla_proofs;
+        }
+
+        sector_ids.retain(|sector_id| !faulty.contains(sector_id));
+        skipped.extend(faulty);
+    };
+
+    let proof = generate_window_post_with_vanilla::<Tree>(
+        post_config,
+        randomness,
+        prover_id,
+        vanilla_proofs,
+    )?;
+
+    info!("generate_window_post_skip_faulty:finish");
+
+    Ok(WindowPoStOutput { proof, skipped })
+}
+
+/// Generates the vanilla proofs of `sector_ids`, returned with the sectors whose proof failed
+/// with `Error::FaultySectors`. Any other error is returned, the one of the first such sector in
+/// `sector_ids` if there are several.
+fn vanilla_proofs_or_faulty<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    replicas: &BTreeMap<SectorId, PrivateReplicaInfo<Tree>>,
+    challenges: &BTreeMap<SectorId, Vec<u64>>,
+    sector_ids: &[SectorId],
+    caller: &str,
+) -> Result<(Vec<FallbackPoStSectorProof<Tree>>, Vec<SectorId>)> {
+    let results: Vec<_> = sector_ids
+        .par_iter()
+        .map(|sector_id| {
+            generate_single_vanilla_proof::<Tree>(
+                post_config,
+                *sector_id,
+                &replicas[sector_id],
+                &challenges[sector_id],
+            )
+        })
+        .collect();
+
+    let mut vanilla_proofs = Vec::with_capacity(results.len());
+    let mut faulty = Vec::new();
+    for (sector_id, result) in sector_ids.iter().zip(results) {
+        match result {
+            Ok(vanilla_proof) => vanilla_proofs.push(vanilla_proof),
+            Err(err) if matches!(err.downcast_ref::<Error>(), Some(Error::FaultySectors(_))) => {
+                warn!("{}: faulty sector {:?}: {:#}", caller, sector_id, err);
+                faulty.push(*sector_id);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok((vanilla_proofs, faulty))
+}
+
 /// Verifies a window proof-of-spacetime.
 pub fn verify_window_post<Tree: 'static + MerkleTreeTrait>(
     post_config: &PoStConfig,
@@ -189,7 +423,7 @@ pub fn verify_window_post<Tree: 'static + MerkleTreeTrait>(
 
     ensure!(
         post_config.typ == PoStType::Window,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     let randomness_safe = as_safe_commitment(randomness, "randomness")?;
@@ -248,6 +482,224 @@ pub fn verify_window_post<Tree: 'static + MerkleTreeTrait>(
     Ok(true)
 }
 
+/// Verifies a Window proof-of-spacetime like `verify_window_post`, but checks the Groth16 proof
+/// of every partition on its own and reports which ones fail instead of a single `bool`.
+///
+/// If the `vanilla_proofs` the proof was generated from are given, the vanilla proof of every
+/// sector is checked as well: its comm_r and each inclusion proof against the challenge
+/// re-derived for it. Only malformed input, e.g. a proof of the wrong length, returns an error.
+pub fn verify_window_post_with_diagnostics<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    randomness: &ChallengeSeed,
+    replicas: &BTreeMap<SectorId, PublicReplicaInfo>,
+    prover_id: ProverId,
+    proof: &[u8],
+    vanilla_proofs: Option<&[FallbackPoStSectorProof<Tree>]>,
+) -> Result<WindowPoStReport> {
+    info!("verify_window_post_with_diagnostics:start");
+
+    ensure!(
+        post_config.typ == PoStType::Window,
+        Error::InvalidConfig("invalid post config type".into())
+    );
+
+    let randomness_safe = as_safe_commitment(randomness, "randomness")?;
+    let prover_id_safe = as_safe_commitment(&prover_id, "prover_id")?;
+
+    let vanilla_params = window_post_setup_params(post_config);
+    let partitions = get_partitions_for_window_post(replicas.len(), post_config);
+
+    let setup_params = compound_proof::SetupParams {
+        vanilla_params,
+        partitions,
+        priority: false,
+    };
+    let pub_params: compound_proof::PublicParams<'_, FallbackPoSt<'_, Tree>> =
+        FallbackPoStCompound::setup(&setup_params)?;
+
+    let pub_sectors: Vec<_> = replicas
+        .iter()
+        .map(|(sector_id, replica)| {
+            let comm_r = replica.safe_comm_r().with_context(|| {
+                format!(
+                    "verify_window_post_with_diagnostics: safe_comm_r failed: {:?}",
+                    sector_id
+                )
+            })?;
+            Ok(PublicSector {
+                id: *sector_id,
+                comm_r,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    let pub_inputs = fallback::PublicInputs {
+        randomness: randomness_safe,
+        prover_id: prover_id_safe,
+        sectors: pub_sectors,
+        k: None,
+    };
+
+    let verifying_key = get_post_verifying_key::<Tree>(post_config)?;
+    let multi_proof = MultiProof::new_from_bytes(partitions, proof, &verifying_key)?;
+
+    let mut report = WindowPoStReport {
+        partitions: multi_proof.circuit_proofs.len(),
+        failures: Vec::new(),
+    };
+    for (partition, circuit_proof) in multi_proof.circuit_proofs.iter().enumerate() {
+        let inputs = FallbackPoStCompound::<Tree>::generate_public_inputs(
+            &pub_inputs,
+            &pub_params.vanilla_params,
+            Some(partition),
+        )?;
+        if !verify_proofs_batch(&verifying_key, &mut OsRng, &[circuit_proof], &[inputs])? {
+            report
+                .failures
+                .push(WindowPoStFailure::SnarkProof { partition });
+        }
+    }
+
+    if let Some(vanilla_proofs) = vanilla_proofs {
+        let mut vanilla_proofs_by_id = BTreeMap::new();
+        for vanilla_proof in vanilla_proofs {
+            vanilla_proofs_by_id
+                .entry(vanilla_proof.sector_id)
+                .or_insert(vanilla_proof);
+        }
+
+        let num_sectors_per_chunk = pub_params.vanilla_params.sector_count;
+        for (partition, sectors_chunk) in
+            pub_inputs.sectors.chunks(num_sectors_per_chunk).enumerate()
+        {
+            for (i, pub_sector) in sectors_chunk.iter().enumerate() {
+                let sector_index = partition * num_sectors_per_chunk + i;
+                match vanilla_proofs_by_id.get(&pub_sector.id) {
+                    Some(vanilla_proof) => report.failures.extend(check_vanilla_proof(
+                        &pub_params.vanilla_params,
+                        &pub_inputs,
+                        partition,
+                        sector_index,
+                        pub_sector,
+                        vanilla_proof,
+                    )),
+                    None => report
+                        .failures
+                        .push(WindowPoStFailure::MissingVanillaProof {
+                            partition,
+                            sector_id: pub_sector.id,
+                        }),
+                }
+            }
+        }
+    }
+
+    info!("verify_window_post_with_diagnostics:finish");
+
+    Ok(report)
+}
+
+/// Checks the vanilla proof of the sector at `sector_index` of the whole proof with
+/// `FallbackPoSt::verify`, and returns every failure found if it is rejected.
+fn check_vanilla_proof<Tree: 'static + MerkleTreeTrait>(
+    pub_params: &fallback::PublicParams,
+    pub_inputs: &fallback::PublicInputs<<Tree::Hasher as Hasher>::Domain>,
+    partition: usize,
+    sector_index: usize,
+    pub_sector: &PublicSector<<Tree::Hasher as Hasher>::Domain>,
+    vanilla_proof: &FallbackPoStSectorProof<Tree>,
+) -> Vec<WindowPoStFailure> {
+    let sector_id = pub_sector.id;
+    let sector_proofs = &vanilla_proof.vanilla_proof.sectors;
+    // `FallbackPoSt::verify` takes comm_r_last from the first inclusion proof before it checks
+    // their count.
+    let inclusion_proofs = sector_proofs
+        .first()
+        .map(|sector_proof| sector_proof.inclusion_proofs().as_slice())
+        .unwrap_or(&[]);
+    if inclusion_proofs.is_empty() || inclusion_proofs.len() != pub_params.challenge_count {
+        return vec![WindowPoStFailure::ChallengeCount {
+            partition,
+            sector_id,
+            expected: pub_params.challenge_count,
+            actual: inclusion_proofs.len(),
+        }];
+    }
+
+    // With a single sector per partition, partition `sector_index` derives the challenges of the
+    // sector at `sector_index` of the whole proof.
+    let sector_params = fallback::PublicParams {
+        sector_count: 1,
+        ..pub_params.clone()
+    };
+    let sector_inputs = fallback::PublicInputs {
+        randomness: pub_inputs.randomness,
+        prover_id: pub_inputs.prover_id,
+        sectors: vec![pub_sector.clone()],
+        k: Some(sector_index),
+    };
+    if let Ok(true) =
+        FallbackPoSt::<Tree>::verify(&sector_params, &sector_inputs, &vanilla_proof.vanilla_proof)
+    {
+        return Vec::new();
+    }
+
+    // The proof is rejected, find out by which of the checks of `FallbackPoSt::verify`.
+    let sector_proof = match sector_proofs.as_slice() {
+        [sector_proof] => sector_proof,
+        _ => {
+            return vec![WindowPoStFailure::InvalidVanillaProof {
+                partition,
+                sector_id,
+            }]
+        }
+    };
+    let mut failures = Vec::new();
+    let comm_r_last = inclusion_proofs[0].root();
+    let comm_r = <Tree::Hasher as Hasher>::Function::hash2(&sector_proof.comm_c, &comm_r_last);
+    if comm_r != pub_sector.comm_r {
+        failures.push(WindowPoStFailure::CommRMismatch {
+            partition,
+            sector_id,
+        });
+    }
+
+    let leaves = pub_params.sector_size as usize / NODE_SIZE;
+    for (n, inclusion_proof) in inclusion_proofs.iter().enumerate() {
+        let challenge_index = get_challenge_index(
+            pub_params.api_version,
+            sector_index,
+            pub_params.challenge_count,
+            n,
+        );
+        let challenge = generate_leaf_challenge(
+            pub_params,
+            pub_inputs.randomness,
+            u64::from(sector_id),
+            challenge_index,
+        );
+        if inclusion_proof.root() != comm_r_last
+            || inclusion_proof.expected_len(leaves) != inclusion_proof.path().len()
+            || !inclusion_proof.validate(challenge as usize)
+        {
+            failures.push(WindowPoStFailure::InclusionProof {
+                partition,
+                sector_id,
+                challenge_index: n,
+                challenge,
+            });
+        }
+    }
+    if failures.is_empty() {
+        failures.push(WindowPoStFailure::InvalidVanillaProof {
+            partition,
+            sector_id,
+        });
+    }
+
+    failures
+}
+
 /// Generates a Window proof-of-spacetime with provided vanilla proofs of a single partition.
 pub fn generate_single_window_post_with_vanilla<Tree: 'static + MerkleTreeTrait>(
     post_config: &PoStConfig,
@@ -259,7 +711,7 @@ pub fn generate_single_window_post_with_vanilla<Tree: 'static + MerkleTreeTrait>
     info!("generate_single_window_post_with_vanilla:start");
     ensure!(
         post_config.typ == PoStType::Window,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     let randomness_safe: <Tree::Hasher as Hasher>::Domain =
@@ -307,10 +759,175 @@ pub fn generate_single_window_post_with_vanilla<Tree: 'static + MerkleTreeTrait>
         &pub_inputs,
         vec![partitioned_proofs],
         &groth_params,
-    )?;
+    )
+    .map_err(classify)?;
 
     info!("generate_single_window_post_with_vanilla:finish");
 
     let proofs_bytes = util::proofs_to_bytes(&proofs)?;
     Ok(PartitionSnarkProof(proofs_bytes))
 }
+
+/// Checks ahead of a Window PoSt deadline that every sector in `replicas` can be proven.
+///
+/// Derives the challenges for `randomness` and generates the vanilla proof of every sector with
+/// `generate_single_vanilla_proof`, the way `generate_window_post` would, but without the SNARK.
+/// The sectors are checked on a pool of threads shared by all scans. A sector whose check is not
+/// done within `timeout` is reported as `SectorHealth::SlowRead`. If no check starts or finishes
+/// within `timeout`, every thread of the pool is blocked and the sectors still queued are reported
+/// as `SectorHealth::NotStarted`. A timed out check is not cancelled, its read may still be in
+/// flight after the report is returned and keeps its thread until it completes.
+pub fn check_sectors_for_post<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    randomness: &ChallengeSeed,
+    replicas: &BTreeMap<SectorId, PrivateReplicaInfo<Tree>>,
+    prover_id: ProverId,
+    timeout: Duration,
+) -> Result<SectorHealthReport> {
+    info!("check_sectors_for_post:start");
+    ensure!(
+        post_config.typ == PoStType::Window,
+        Error::InvalidConfig("invalid post config type".into())
+    );
+
+    let sector_ids: Vec<SectorId> = replicas.keys().copied().collect();
+    let mut challenges = generate_fallback_sector_challenges::<Tree>(
+        post_config,
+        randomness,
+        &sector_ids,
+        prover_id,
+    )?;
+
+    let (sender, receiver) = mpsc::channel();
+    // Set once the report is returned, so that checks still queued are dropped instead of run.
+    let finished = Arc::new(AtomicBool::new(false));
+    for (sector_id, replica) in replicas {
+        let sector_id = *sector_id;
+        let post_config = post_config.clone();
+        let replica = replica.clone();
+        let sector_challenges = challenges.remove(&sector_id);
+        let sender = sender.clone();
+        let finished = finished.clone();
+        SECTOR_CHECK_POOL.spawn(move || {
+            if finished.load(Ordering::SeqCst) {
+                return;
+            }
+            // The receiver is gone if the scan is finished.
+            let _ = sender.send(CheckEvent::Started(sector_id, Instant::now()));
+            let health = check_sector(&post_config, sector_id, &replica, sector_challenges);
+            let _ = sender.send(CheckEvent::Done(sector_id, health));
+        });
+    }
+    drop(sender);
+
+    let mut sectors = BTreeMap::new();
+    let mut started = BTreeMap::new();
+    let mut last_event = Instant::now();
+    while sectors.len() < replicas.len() {
+        // Every thread of the pool is blocked if no check started or finished for `timeout`.
+        let deadline = started
+            .values()
+            .map(|start| *start + timeout)
+            .fold(last_event + timeout, Instant::min);
+        match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(CheckEvent::Started(sector_id, start)) => {
+                if !sectors.contains_key(&sector_id) {
+                    started.insert(sector_id, start);
+                }
+                last_event = Instant::now();
+            }
+            Ok(CheckEvent::Done(sector_id, health)) => {
+                if started.remove(&sector_id).is_some() {
+                    sectors.insert(sector_id, health);
+                }
+                last_event = Instant::now();
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let now = Instant::now();
+                let blocked = now >= last_event + timeout;
+                for sector_id in replicas.keys() {
+                    match started.get(sector_id) {
+                        Some(start) if now >= *start + timeout => {
+                            started.remove(sector_id);
+                            sectors.insert(*sector_id, SectorHealth::SlowRead(timeout));
+                        }
+                        None if blocked && !sectors.contains_key(sector_id) => {
+                            sectors.insert(*sector_id, SectorHealth::NotStarted);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    finished.store(true, Ordering::SeqCst);
+
+    for sector_id in replicas.keys() {
+        let health = sectors
+            .entry(*sector_id)
+            .or_insert_with(|| SectorHealth::Error("check did not finish".into()));
+        if !health.is_ok() {
+            warn!("check_sectors_for_post: {:?} {:?}", sector_id, health);
+        }
+    }
+
+    info!("check_sectors_for_post:finish");
+
+    Ok(SectorHealthReport { sectors })
+}
+
+enum CheckEvent {
+    Started(SectorId, Instant),
+    Done(SectorId, SectorHealth),
+}
+
+fn check_sector<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    sector_id: SectorId,
+    replica: &PrivateReplicaInfo<Tree>,
+    challenges: Option<Vec<u64>>,
+) -> SectorHealth {
+    let start = Instant::now();
+
+    let challenges = match challenges {
+        Some(challenges) if !challenges.is_empty() => challenges,
+        _ => return SectorHealth::Error("no challenges derived for sector".into()),
+    };
+
+    // comm_r is checked against comm_c and comm_r_last of p_aux while generating the proof.
+    let err =
+        match generate_single_vanilla_proof::<Tree>(post_config, sector_id, replica, &challenges) {
+            Ok(_) => return SectorHealth::Ok(start.elapsed()),
+            Err(err) => err,
+        };
+
+    let is_missing = !replica.replica_path().exists()
+        || err.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<io::Error>(),
+                Some(io_err) if io_err.kind() == io::ErrorKind::NotFound
+            )
+        });
+    let is_comm_r_mismatch = err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<Error>(),
+            Some(Error::InvalidCommitment(_))
+        )
+    });
+    let is_invalid = err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<CoreError>(),
+            Some(CoreError::InvalidVanillaProof(_))
+        )
+    });
+    if is_missing {
+        SectorHealth::MissingFile(format!("{:#}", err))
+    } else if is_comm_r_mismatch {
+        SectorHealth::CommRMismatch
+    } else if is_invalid {
+        SectorHealth::BadMerklePath
+    } else {
+        SectorHealth::Error(format!("{:#}", err))
+    }
+}
@@ -1,8 +1,11 @@
+use std::time::{Duration, Instant};
+
 use anyhow::{ensure, Context, Result};
 use filecoin_hashers::Hasher;
 use log::info;
 use storage_proofs_core::{
-    compound_proof::{self, CompoundProof},
+    compound_proof::{self, CompoundProof, SnarkTimings},
+    measurements::{measure_op, Operation},
     merkle::MerkleTreeTrait,
     multi_proof::MultiProof,
     sector::SectorId,
@@ -13,12 +16,15 @@ use storage_proofs_post::fallback::{
 };
 
 use crate::{
-    api::{as_safe_commitment, partition_vanilla_proofs, util},
+    api::{
+        as_safe_commitment, generate_fallback_sector_challenges, partition_vanilla_proofs, util,
+    },
     caches::{get_post_params, get_post_verifying_key},
+    error::{classify, Error},
     parameters::winning_post_setup_params,
     types::{
         ChallengeSeed, Commitment, FallbackPoStSectorProof, PoStConfig, PrivateReplicaInfo,
-        ProverId, PublicReplicaInfo, SnarkProof,
+        ProverId, PublicReplicaInfo, SnarkProof, WinningPoStSectorTimings, WinningPoStTimings,
     },
     PoStType,
 };
@@ -31,9 +37,26 @@ pub fn generate_winning_post_with_vanilla<Tree: 'static + MerkleTreeTrait>(
     vanilla_proofs: Vec<FallbackPoStSectorProof<Tree>>,
 ) -> Result<SnarkProof> {
     info!("generate_winning_post_with_vanilla:start");
+
+    let (proof, _) =
+        winning_post_with_vanilla_timed(post_config, randomness, prover_id, vanilla_proofs)?;
+
+    info!("generate_winning_post_with_vanilla:finish");
+
+    Ok(proof)
+}
+
+/// Generates a Winning proof-of-spacetime with provided vanilla proofs, and returns the time
+/// spent on synthesizing its circuit and proving it.
+fn winning_post_with_vanilla_timed<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    randomness: &ChallengeSeed,
+    prover_id: ProverId,
+    vanilla_proofs: Vec<FallbackPoStSectorProof<Tree>>,
+) -> Result<(SnarkProof, SnarkTimings)> {
     ensure!(
         post_config.typ == PoStType::Winning,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     ensure!(
@@ -81,16 +104,15 @@ pub fn generate_winning_post_with_vanilla<Tree: 'static + MerkleTreeTrait>(
         &vanilla_proofs,
     )?;
 
-    let proofs = FallbackPoStCompound::prove_with_vanilla(
+    let (proofs, timings) = FallbackPoStCompound::prove_with_vanilla_timed(
         &pub_params,
         &pub_inputs,
         partitioned_proofs,
         &groth_params,
-    )?;
+    )
+    .map_err(classify)?;
 
-    info!("generate_winning_post_with_vanilla:finish");
-
-    util::proofs_to_bytes(&proofs)
+    Ok((util::proofs_to_bytes(&proofs)?, timings))
 }
 
 /// Generates a Winning proof-of-spacetime.
@@ -101,9 +123,33 @@ pub fn generate_winning_post<Tree: 'static + MerkleTreeTrait>(
     prover_id: ProverId,
 ) -> Result<SnarkProof> {
     info!("generate_winning_post:start");
+
+    let (proof, timings) =
+        generate_winning_post_with_timings(post_config, randomness, replicas, prover_id, None)?;
+
+    info!("generate_winning_post:finish: {:?}", timings.total);
+
+    Ok(proof)
+}
+
+/// Generates a Winning proof-of-spacetime like `generate_winning_post`, and reports the wall time
+/// of every step of it.
+///
+/// If a `deadline` is given, it is checked before every step and generation is aborted with
+/// `Error::DeadlineExceeded` once it has passed. The SNARK itself can not be interrupted, so
+/// that a proof which started in time is always returned.
+pub fn generate_winning_post_with_timings<Tree: 'static + MerkleTreeTrait>(
+    post_config: &PoStConfig,
+    randomness: &ChallengeSeed,
+    replicas: &[(SectorId, PrivateReplicaInfo<Tree>)],
+    prover_id: ProverId,
+    deadline: Option<Instant>,
+) -> Result<(SnarkProof, WinningPoStTimings)> {
+    info!("generate_winning_post_with_timings:start");
+    let start = Instant::now();
     ensure!(
         post_config.typ == PoStType::Winning,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     ensure!(
@@ -111,74 +157,97 @@ pub fn generate_winning_post<Tree: 'static + MerkleTreeTrait>(
         "invalid amount of replicas"
     );
 
-    let randomness_safe: <Tree::Hasher as Hasher>::Domain =
-        as_safe_commitment(randomness, "randomness")?;
-    let prover_id_safe: <Tree::Hasher as Hasher>::Domain =
-        as_safe_commitment(&prover_id, "prover_id")?;
-
-    let vanilla_params = winning_post_setup_params(post_config)?;
-    let param_sector_count = vanilla_params.sector_count;
+    let mut timings = WinningPoStTimings::default();
+
+    check_deadline(deadline, "challenge derivation")?;
+    let sector_ids: Vec<SectorId> = replicas.iter().map(|(sector_id, _)| *sector_id).collect();
+    let (challenges, elapsed) = timed(Operation::PostChallengeDerivation, || {
+        generate_fallback_sector_challenges::<Tree>(post_config, randomness, &sector_ids, prover_id)
+    });
+    let challenges = challenges?;
+    timings.challenge_derivation = elapsed;
+
+    let mut vanilla_proofs = Vec::with_capacity(replicas.len());
+    for (sector_id, replica) in replicas {
+        check_deadline(deadline, &format!("opening sector {:?}", sector_id))?;
+        let (tree, tree_open) = timed(Operation::PostTreeOpen, || {
+            replica.merkle_tree(post_config.sector_size)
+        });
+        let tree = tree.with_context(|| {
+            format!(
+                "generate_winning_post_with_timings: merkle_tree failed: {:?}",
+                sector_id
+            )
+        })?;
+
+        check_deadline(deadline, &format!("reading sector {:?}", sector_id))?;
+        let priv_sectors = vec![PrivateSector {
+            tree: &tree,
+            comm_c: replica.safe_comm_c(),
+            comm_r_last: replica.safe_comm_r_last(),
+        }];
+        let priv_inputs = fallback::PrivateInputs::<Tree> {
+            sectors: &priv_sectors,
+        };
+        let (vanilla_proof, proof_read) = timed(Operation::PostInclusionProofs, || {
+            fallback::vanilla_proof(*sector_id, &priv_inputs, &challenges[sector_id])
+        });
+        let vanilla_proof = vanilla_proof.with_context(|| {
+            format!(
+                "generate_winning_post_with_timings: vanilla_proof failed: {:?}",
+                sector_id
+            )
+        })?;
+
+        vanilla_proofs.push(FallbackPoStSectorProof {
+            sector_id: *sector_id,
+            comm_r: replica.safe_comm_r().with_context(|| {
+                format!(
+                    "generate_winning_post_with_timings: safe_comm_r failed: {:?}",
+                    sector_id
+                )
+            })?,
+            vanilla_proof,
+        });
+        timings.sectors.push(WinningPoStSectorTimings {
+            sector_id: *sector_id,
+            tree_open,
+            proof_read,
+        });
+    }
 
-    let setup_params = compound_proof::SetupParams {
-        vanilla_params,
-        partitions: None,
-        priority: post_config.priority,
-    };
-    let pub_params: compound_proof::PublicParams<'_, FallbackPoSt<'_, Tree>> =
-        FallbackPoStCompound::setup(&setup_params)?;
-    let groth_params = get_post_params::<Tree>(post_config)?;
+    check_deadline(deadline, "snark")?;
+    let (proof, snark_timings) = measure_op(Operation::PostSnark, || {
+        winning_post_with_vanilla_timed::<Tree>(post_config, randomness, prover_id, vanilla_proofs)
+    })?;
+    timings.synthesis = snark_timings.synthesis;
+    timings.proving = snark_timings.proving;
+    timings.total = start.elapsed();
+
+    info!(
+        "generate_winning_post_with_timings:finish: {:?}",
+        timings.total
+    );
 
-    let trees = replicas
-        .iter()
-        .map(|(sector_id, replica)| {
-            replica
-                .merkle_tree(post_config.sector_size)
-                .with_context(|| {
-                    format!("generate_winning_post: merkle_tree failed: {:?}", sector_id)
-                })
-        })
-        .collect::<Result<Vec<_>>>()?;
+    Ok((proof, timings))
+}
 
-    let mut pub_sectors = Vec::with_capacity(param_sector_count);
-    let mut priv_sectors = Vec::with_capacity(param_sector_count);
+fn timed<T, F: FnOnce() -> T>(op: Operation, f: F) -> (T, Duration) {
+    let start = Instant::now();
+    let result = measure_op(op, f);
 
-    for _ in 0..param_sector_count {
-        for ((sector_id, replica), tree) in replicas.iter().zip(trees.iter()) {
-            let comm_r = replica.safe_comm_r().with_context(|| {
-                format!("generate_winning_post: safe_comm_r failed: {:?}", sector_id)
-            })?;
-            let comm_c = replica.safe_comm_c();
-            let comm_r_last = replica.safe_comm_r_last();
+    (result, start.elapsed())
+}
 
-            pub_sectors.push(PublicSector::<<Tree::Hasher as Hasher>::Domain> {
-                id: *sector_id,
-                comm_r,
-            });
-            priv_sectors.push(PrivateSector {
-                tree,
-                comm_c,
-                comm_r_last,
-            });
-        }
+fn check_deadline(deadline: Option<Instant>, phase: &str) -> Result<()> {
+    if let Some(deadline) = deadline {
+        ensure!(
+            Instant::now() < deadline,
+            Error::DeadlineExceeded(phase.to_string())
+        );
     }
 
-    let pub_inputs = fallback::PublicInputs::<<Tree::Hasher as Hasher>::Domain> {
-        randomness: randomness_safe,
-        prover_id: prover_id_safe,
-        sectors: pub_sectors,
-        k: None,
-    };
-
-    let priv_inputs = fallback::PrivateInputs::<Tree> {
-        sectors: &priv_sectors,
-    };
-
-    let proofs =
-        FallbackPoStCompound::<Tree>::prove(&pub_params, &pub_inputs, &priv_inputs, &groth_params)?;
-
-    info!("generate_winning_post:finish");
-
-    util::proofs_to_bytes(&proofs)
+    Ok(())
 }
 
 /// Given some randomness and the length of available sectors, generates the challenged sector.
@@ -195,7 +264,7 @@ pub fn generate_winning_post_sector_challenge<Tree: MerkleTreeTrait>(
     ensure!(sector_set_size != 0, "empty sector set is invalid");
     ensure!(
         post_config.typ == PoStType::Winning,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
 
     let prover_id_safe: <Tree::Hasher as Hasher>::Domain =
@@ -231,7 +300,7 @@ pub fn verify_winning_post<Tree: 'static + MerkleTreeTrait>(
 
     ensure!(
         post_config.typ == PoStType::Winning,
-        "invalid post config type"
+        Error::InvalidConfig("invalid post config type".into())
     );
     ensure!(
         post_config.sector_count == replicas.len(),
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use bellperson::groth16::{self, prepare_verifying_key};
 use blstrs::Bls12;
 use lazy_static::lazy_static;
@@ -20,6 +20,7 @@ use storage_proofs_update::{
 
 use crate::{
     constants::{DefaultPieceHasher, PUBLISHED_SECTOR_SIZES},
+    error::Error,
     parameters::{public_params, window_post_public_params, winning_post_public_params},
     types::{PoRepConfig, PoStConfig, PoStType},
 };
@@ -124,7 +125,8 @@ where
 
     info!("no params in memory cache for {}", &identifier);
 
-    let new_entry = Arc::new(generator()?);
+    let new_entry =
+        Arc::new(generator().with_context(|| Error::MissingParameters(identifier.clone()))?);
     let res = new_entry.clone();
     {
         let cache = &mut (*cache_ref).lock().expect("poisoned cache");
@@ -144,7 +146,10 @@ where
     G: Send + Sync,
 {
     trace!("srs_cache_lookup looking up {}", identifier);
-    if let Some(entry) = cache_ref.get_or_init(&identifier, generator)? {
+    if let Some(entry) = cache_ref
+        .get_or_init(&identifier, generator)
+        .with_context(|| Error::MissingParameters(identifier.clone()))?
+    {
         return Ok(entry.clone());
     }
 
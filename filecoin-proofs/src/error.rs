@@ -0,0 +1,57 @@
+use std::io;
+
+use anyhow::Error as AnyhowError;
+use storage_proofs_core::{error::Error as CoreError, sector::SectorId};
+
+/// Classified failures of the public API.
+///
+/// API functions keep returning `anyhow::Result`; where a failure can be
+/// classified, one of these variants is attached to the error as context, so
+/// callers can inspect it with `err.downcast_ref::<filecoin_proofs::Error>()`
+/// while the underlying cause (e.g. `storage_proofs_core::error::Error`) stays
+/// downcastable as well. Variants wrapping a message display it unchanged.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    CacheCorrupted(String),
+    #[error("missing or invalid parameters for {0}")]
+    MissingParameters(String),
+    #[error("{0}")]
+    InvalidCommitment(String),
+    #[error("faulty sectors {0:?}")]
+    FaultySectors(Vec<SectorId>),
+    #[error("{0}")]
+    Io(String),
+    #[error("{0}")]
+    InvalidConfig(String),
+    #[error("deadline exceeded before {0}")]
+    DeadlineExceeded(String),
+}
+
+/// Attaches a matching `Error` variant to `err` if it is not classified yet
+/// and its cause chain contains a failure we know how to classify.
+pub(crate) fn classify(err: AnyhowError) -> AnyhowError {
+    if err.downcast_ref::<Error>().is_some() {
+        return err;
+    }
+
+    let classified = err.chain().find_map(|cause| {
+        if let Some(core_err) = cause.downcast_ref::<CoreError>() {
+            match core_err {
+                CoreError::FaultySectors(sectors) => Some(Error::FaultySectors(sectors.clone())),
+                CoreError::InvalidParameters(path) => Some(Error::MissingParameters(path.clone())),
+                CoreError::Io(io_err) => Some(Error::Io(io_err.to_string())),
+                _ => None,
+            }
+        } else {
+            cause
+                .downcast_ref::<io::Error>()
+                .map(|io_err| Error::Io(io_err.to_string()))
+        }
+    });
+
+    match classified {
+        Some(classified) => err.context(classified),
+        None => err,
+    }
+}
@@ -11,9 +11,11 @@ compile_error!(
 pub mod caches;
 pub mod chunk_iter;
 pub mod constants;
+pub mod error;
 pub mod param;
 pub mod parameters;
 pub mod pieces;
+pub mod replica_store;
 pub mod types;
 
 mod api;
@@ -23,4 +25,6 @@ pub use api::*;
 pub use chunk_iter::ChunkIterator;
 pub use commitment_reader::*;
 pub use constants::*;
+pub use error::Error;
+pub use replica_store::{LocalReplicaStore, ReplicaStore};
 pub use types::*;
@@ -106,12 +106,17 @@ pub fn compute_comm_d(sector_size: SectorSize, piece_infos: &[PieceInfo]) -> Res
         "Piece is larger than sector."
     );
 
+    let piece_info = join_and_pad_piece_infos(piece_infos)?;
+
+    Ok(piece_info.commitment)
+}
+
+/// Joins the given pieces, in order, into a single piece. Pieces are aligned by inserting zero
+/// padding in between, and the result is padded with zeros at the end up to the next power of two.
+pub(crate) fn join_and_pad_piece_infos(piece_infos: &[PieceInfo]) -> Result<PieceInfo> {
     let mut stack = Stack::new();
 
-    let first = piece_infos
-        .first()
-        .expect("unreachable: !is_empty()")
-        .clone();
+    let first = piece_infos.first().context("no pieces to join")?.clone();
     ensure!(
         u64::from(PaddedBytesAmount::from(first.size)).is_power_of_two(),
         "Piece size ({:?}) must be a power of 2.",
@@ -139,9 +144,7 @@ pub fn compute_comm_d(sector_size: SectorSize, piece_infos: &[PieceInfo]) -> Res
 
     ensure!(stack.len() == 1, "Stack size ({}) must be 1.", stack.len());
 
-    let comm_d_calculated = stack.pop()?.commitment;
-
-    Ok(comm_d_calculated)
+    stack.pop()
 }
 
 /// Stack used for piece reduction.
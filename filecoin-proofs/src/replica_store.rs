@@ -0,0 +1,309 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context, Result};
+use filecoin_hashers::{Domain, Hasher};
+use memmap2::{Mmap, MmapOptions};
+use merkletree::{hash::Algorithm, store::StoreConfig};
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use storage_proofs_core::{
+    cache_key::CacheKey,
+    error::Error as CoreError,
+    merkle::{get_base_tree_count, MerkleProof, MerkleProofTrait, MerkleTreeTrait},
+    sector::SectorId,
+    util::{default_rows_to_discard, NODE_SIZE},
+};
+use typenum::Unsigned;
+
+use crate::{error::Error, types::SectorSize};
+
+/// Read access to the files of a sealed sector which PoSt reads from: the replica, whose nodes
+/// are the leafs of tree_r_last, the cached rows of the tree_r_last base trees and p_aux.
+///
+/// A vanilla proof only reads the challenged leafs with their neighbours and a few cached tree
+/// rows, so that a caller-provided implementation can serve these ranges from remote storage,
+/// see `generate_single_vanilla_proof_with_store`.
+pub trait ReplicaStore: Send + Sync {
+    /// Fills `buf` with the bytes of the replica starting at `offset`.
+    fn read_replica(&self, offset: u64, buf: &mut [u8]) -> Result<()>;
+
+    /// Fills `buf` with the bytes starting at `offset` of the file of base tree `tree_index`
+    /// of tree_r_last, see `tree_r_last_path`.
+    fn read_tree_r_last(&self, tree_index: usize, offset: u64, buf: &mut [u8]) -> Result<()>;
+
+    /// Returns the content of the p_aux file.
+    fn read_p_aux(&self) -> Result<Vec<u8>>;
+}
+
+/// Returns the path of the file of base tree `tree_index` of tree_r_last in `cache_dir`.
+pub fn tree_r_last_path<Tree: MerkleTreeTrait>(cache_dir: &Path, tree_index: usize) -> PathBuf {
+    let id = if get_base_tree_count::<Tree>() == 1 {
+        CacheKey::CommRLastTree.to_string()
+    } else {
+        format!("{}-{}", CacheKey::CommRLastTree, tree_index)
+    };
+    StoreConfig::data_path(cache_dir, &id)
+}
+
+/// A `ReplicaStore` over a replica and cache dir on local disk, which are memory mapped.
+#[derive(Debug)]
+pub struct LocalReplicaStore {
+    replica: Mmap,
+    trees: Vec<Mmap>,
+    p_aux_path: PathBuf,
+}
+
+impl LocalReplicaStore {
+    pub fn new<Tree: MerkleTreeTrait>(replica_path: &Path, cache_dir: &Path) -> Result<Self> {
+        let replica = map_file(replica_path)?;
+        let trees = (0..get_base_tree_count::<Tree>())
+            .map(|tree_index| map_file(&tree_r_last_path::<Tree>(cache_dir, tree_index)))
+            .collect::<Result<_>>()?;
+
+        Ok(LocalReplicaStore {
+            replica,
+            trees,
+            p_aux_path: cache_dir.join(CacheKey::PAux.to_string()),
+        })
+    }
+}
+
+impl ReplicaStore for LocalReplicaStore {
+    fn read_replica(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        read_mapped(&self.replica, offset, buf)
+    }
+
+    fn read_tree_r_last(&self, tree_index: usize, offset: u64, buf: &mut [u8]) -> Result<()> {
+        let tree = self
+            .trees
+            .get(tree_index)
+            .with_context(|| format!("invalid tree_r_last index {}", tree_index))?;
+        read_mapped(tree, offset, buf)
+    }
+
+    fn read_p_aux(&self) -> Result<Vec<u8>> {
+        fs::read(&self.p_aux_path).with_context(|| {
+            Error::CacheCorrupted(format!("could not read from path={:?}", self.p_aux_path))
+        })
+    }
+}
+
+fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path).with_context(|| format!("could not open path={:?}", path))?;
+    // Safety: the sealed files are not modified while they are proven.
+    unsafe { MmapOptions::new().map(&file) }
+        .with_context(|| format!("could not mmap path={:?}", path))
+}
+
+fn read_mapped(data: &[u8], offset: u64, buf: &mut [u8]) -> Result<()> {
+    let start = offset as usize;
+    ensure!(
+        start <= data.len() && buf.len() <= data.len() - start,
+        Error::Io(format!(
+            "read of {} bytes at {} out of bounds of {} bytes",
+            buf.len(),
+            offset,
+            data.len()
+        ))
+    );
+    buf.copy_from_slice(&data[start..start + buf.len()]);
+
+    Ok(())
+}
+
+/// The shape of tree_r_last, used to locate its nodes in a `ReplicaStore`.
+struct TreeRLastLayout {
+    arity: usize,
+    tree_count: usize,
+    base_tree_leafs: usize,
+    rows_to_discard: usize,
+    /// Index of the first node of every row of a base tree, from the leafs up to the root, as if
+    /// no rows were discarded.
+    row_starts: Vec<usize>,
+}
+
+impl TreeRLastLayout {
+    fn new<Tree: MerkleTreeTrait>(sector_size: SectorSize) -> Result<Self> {
+        let arity = Tree::Arity::to_usize();
+        let tree_count = get_base_tree_count::<Tree>();
+        let base_tree_leafs = u64::from(sector_size) as usize / NODE_SIZE / tree_count;
+        let rows_to_discard = default_rows_to_discard(base_tree_leafs, arity);
+
+        let mut row_starts = vec![0];
+        let mut width = base_tree_leafs;
+        while width > 1 {
+            row_starts.push(row_starts[row_starts.len() - 1] + width);
+            width /= arity;
+        }
+        ensure!(
+            rows_to_discard + 1 < row_starts.len(),
+            "invalid rows_to_discard {} for tree_r_last",
+            rows_to_discard
+        );
+
+        Ok(TreeRLastLayout {
+            arity,
+            tree_count,
+            base_tree_leafs,
+            rows_to_discard,
+            row_starts,
+        })
+    }
+
+    /// Reads `count` nodes of `row` of base tree `tree_index`, starting at `index`. The row has
+    /// to be one which is cached in the tree file.
+    fn read_tree_nodes<D: Domain, S: ReplicaStore + ?Sized>(
+        &self,
+        store: &S,
+        tree_index: usize,
+        row: usize,
+        index: usize,
+        count: usize,
+    ) -> Result<Vec<D>> {
+        let cache_start = self.row_starts[self.rows_to_discard + 1];
+        let offset = (self.row_starts[row] + index - cache_start) * NODE_SIZE;
+        let mut buf = vec![0u8; count * NODE_SIZE];
+        store.read_tree_r_last(tree_index, offset as u64, &mut buf)?;
+
+        buf.chunks(NODE_SIZE).map(D::try_from_bytes).collect()
+    }
+
+    fn root_row(&self) -> usize {
+        self.row_starts.len() - 1
+    }
+}
+
+fn hash_nodes<H: Hasher>(nodes: &[H::Domain], height: usize) -> H::Domain {
+    let mut hasher = H::Function::default();
+    hasher.reset();
+    hasher.multi_node(nodes, height)
+}
+
+/// Returns the siblings of `index` within its group of `arity` nodes in `row`, which starts at
+/// node `row_offset`, together with the position of `index` in the group.
+fn path_element<D: Copy>(
+    row: &[D],
+    row_offset: usize,
+    index: usize,
+    arity: usize,
+) -> (Vec<D>, usize) {
+    let position = index % arity;
+    let group_start = index - position - row_offset;
+    let siblings = row[group_start..group_start + arity]
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != position)
+        .map(|(_, node)| *node)
+        .collect();
+
+    (siblings, position)
+}
+
+type TreeRLastProof<Tree> = MerkleProof<
+    <Tree as MerkleTreeTrait>::Hasher,
+    <Tree as MerkleTreeTrait>::Arity,
+    <Tree as MerkleTreeTrait>::SubTreeArity,
+    <Tree as MerkleTreeTrait>::TopTreeArity,
+>;
+
+/// Generates the tree_r_last inclusion proofs of the challenged leafs from the ranges of the
+/// sector files read from `store`. Every proof is checked against `comm_r_last`.
+pub(crate) fn tree_r_last_proofs<Tree: MerkleTreeTrait, S: ReplicaStore + ?Sized>(
+    sector_size: SectorSize,
+    store: &S,
+    sector_id: SectorId,
+    comm_r_last: <Tree::Hasher as Hasher>::Domain,
+    challenges: &[u64],
+) -> Result<Vec<TreeRLastProof<Tree>>> {
+    let layout = TreeRLastLayout::new::<Tree>(sector_size)?;
+    let arity = layout.arity;
+    let sub_arity = Tree::SubTreeArity::to_usize();
+
+    let base_roots = (0..layout.tree_count)
+        .map(|tree_index| layout.read_tree_nodes(store, tree_index, layout.root_row(), 0, 1))
+        .collect::<Result<Vec<Vec<_>>>>()?
+        .concat();
+    let sub_roots: Vec<_> = if sub_arity == 0 {
+        Vec::new()
+    } else {
+        base_roots
+            .chunks(sub_arity)
+            .map(|roots| hash_nodes::<Tree::Hasher>(roots, 0))
+            .collect()
+    };
+
+    // The discarded rows of a base tree are rebuilt from the leafs of the sub-tree rooted in the
+    // lowest cached row.
+    let span = arity.pow(layout.rows_to_discard as u32 + 1);
+
+    challenges
+        .par_iter()
+        .map(|&challenge| {
+            let challenge = challenge as usize;
+            let tree_index = challenge / layout.base_tree_leafs;
+            let leaf_index = challenge % layout.base_tree_leafs;
+            ensure!(
+                tree_index < layout.tree_count,
+                "challenge {} out of range",
+                challenge
+            );
+
+            let span_start = leaf_index - leaf_index % span;
+            let mut buf = vec![0u8; span * NODE_SIZE];
+            store.read_replica(
+                ((tree_index * layout.base_tree_leafs + span_start) * NODE_SIZE) as u64,
+                &mut buf,
+            )?;
+            let mut row = buf
+                .chunks(NODE_SIZE)
+                .map(<Tree::Hasher as Hasher>::Domain::try_from_bytes)
+                .collect::<Result<Vec<_>>>()?;
+            let leaf = row[leaf_index - span_start];
+
+            let mut path = Vec::with_capacity(layout.root_row() + 2);
+            let mut index = leaf_index;
+            let mut row_offset = span_start;
+            for height in 0..=layout.rows_to_discard {
+                path.push(path_element(&row, row_offset, index, arity));
+                row = row
+                    .chunks(arity)
+                    .map(|nodes| hash_nodes::<Tree::Hasher>(nodes, height))
+                    .collect();
+                index /= arity;
+                row_offset /= arity;
+            }
+            for cached_row in layout.rows_to_discard + 1..layout.root_row() {
+                let group_start = index - index % arity;
+                let nodes =
+                    layout.read_tree_nodes(store, tree_index, cached_row, group_start, arity)?;
+                path.push(path_element(&nodes, group_start, index, arity));
+                index /= arity;
+            }
+
+            // Compound trees add a level for the sub-tree, which is none if its arity is 0.
+            if let Some(sub_index) = tree_index.checked_div(sub_arity) {
+                path.push(path_element(
+                    &base_roots[sub_index * sub_arity..(sub_index + 1) * sub_arity],
+                    sub_index * sub_arity,
+                    tree_index,
+                    sub_arity,
+                ));
+                if Tree::TopTreeArity::to_usize() > 0 {
+                    path.push(path_element(&sub_roots, 0, sub_index, sub_roots.len()));
+                }
+            }
+
+            let proof = MerkleProof::from_parts(leaf, comm_r_last, path);
+            if !proof.validate(challenge) {
+                return Err(CoreError::InvalidVanillaProof(sector_id)).with_context(|| {
+                    format!(
+                        "inclusion proof of challenge {} does not match comm_r_last",
+                        challenge
+                    )
+                });
+            }
+
+            Ok(proof)
+        })
+        .collect()
+}
@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+use std::fs::{self, rename, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use storage_proofs_core::cache_key::CacheKey;
+
+use crate::error::Error;
+
+/// The sealing phase which produced a file in the sector cache, in the order they run.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SealPhase {
+    PreCommit1,
+    PreCommit2,
+    Commit1,
+}
+
+/// The content of a file in the sector cache, derived from its name.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CacheFileKind {
+    /// The labels of the given layer, starting at 1.
+    Layer(usize),
+    TreeD,
+    TreeC,
+    TreeRLast,
+    PAux,
+    TAux,
+    Other,
+}
+
+impl CacheFileKind {
+    pub fn from_file_name(name: &str) -> Self {
+        if name == CacheKey::PAux.to_string() {
+            return CacheFileKind::PAux;
+        }
+        if name == CacheKey::TAux.to_string() {
+            return CacheFileKind::TAux;
+        }
+
+        // Store files are named `sc-<version>-data-<id>.dat`, split trees append an index.
+        let layer = name
+            .strip_suffix(".dat")
+            .and_then(|stem| stem.rsplit_once("-layer-"))
+            .and_then(|(_, layer)| layer.parse().ok());
+        if let Some(layer) = layer {
+            CacheFileKind::Layer(layer)
+        } else if name.contains(&CacheKey::CommRLastTree.to_string()) {
+            CacheFileKind::TreeRLast
+        } else if name.contains(&CacheKey::CommCTree.to_string()) {
+            CacheFileKind::TreeC
+        } else if name.contains(&CacheKey::CommDTree.to_string()) {
+            CacheFileKind::TreeD
+        } else {
+            CacheFileKind::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheManifestEntry {
+    pub size: u64,
+    /// Hex encoded BLAKE2b digest of the file content, if it was known when the file was
+    /// recorded. This is the case for the layers of a checkpointed labeling run.
+    pub digest: Option<String>,
+    pub phase: SealPhase,
+}
+
+/// Records size, and digest where available, of every file a sealing phase wrote into the sector
+/// cache, keyed by file name, so that the cache can be checked for missing or corrupt files later
+/// on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheManifest {
+    pub files: BTreeMap<String, CacheManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheFileFault {
+    Missing,
+    SizeMismatch { expected: u64, actual: u64 },
+    DigestMismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheFileIssue {
+    pub file_name: String,
+    pub kind: CacheFileKind,
+    pub phase: SealPhase,
+    pub fault: CacheFileFault,
+}
+
+/// The result of checking a sector cache against its manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SectorCacheReport {
+    /// Number of files which were checked.
+    pub checked: usize,
+    pub issues: Vec<CacheFileIssue>,
+}
+
+impl SectorCacheReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl CacheManifest {
+    pub fn path<P: AsRef<Path>>(cache_path: P) -> PathBuf {
+        cache_path.as_ref().join(CacheKey::Manifest.to_string())
+    }
+
+    /// Reads the manifest from `cache_path`, returns `None` if there is none.
+    pub fn read<P: AsRef<Path>>(cache_path: P) -> Result<Option<Self>> {
+        let path = Self::path(cache_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes =
+            fs::read(&path).with_context(|| format!("failed to read cache manifest {:?}", path))?;
+        let manifest = serde_json::from_slice(&bytes)
+            .with_context(|| format!("failed to parse cache manifest {:?}", path))?;
+
+        Ok(Some(manifest))
+    }
+
+    /// Stores the manifest atomically, by writing first to `.tmp` and then renaming.
+    pub fn write<P: AsRef<Path>>(&self, cache_path: P) -> Result<()> {
+        let path = Self::path(cache_path);
+        let tmp_path = path.with_extension("tmp");
+        let bytes = serde_json::to_vec(self).context("failed to serialize cache manifest")?;
+        fs::write(&tmp_path, bytes).context("failed to write cache manifest")?;
+        rename(tmp_path, path).context("failed to rename tmp cache manifest")?;
+
+        Ok(())
+    }
+
+    /// Records all files in `cache_path` which are not attributed to an earlier phase as
+    /// produced by `phase`, replacing what a previous run of `phase` recorded.
+    ///
+    /// Only the file sizes are read from disk. `layer_digests` are the digests of the label
+    /// layers in layer order, as kept by the label checkpoint, and are stored with the layers.
+    pub fn record_phase<P: AsRef<Path>>(
+        cache_path: P,
+        phase: SealPhase,
+        layer_digests: &[String],
+    ) -> Result<()> {
+        let cache_path = cache_path.as_ref();
+        let mut manifest = Self::read(cache_path)?.unwrap_or_default();
+        manifest.files.retain(|_, entry| entry.phase != phase);
+
+        for entry in fs::read_dir(cache_path)
+            .with_context(|| format!("failed to list cache dir {:?}", cache_path))?
+        {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if is_untracked(&name) || manifest.files.contains_key(&name) {
+                continue;
+            }
+            let digest = match CacheFileKind::from_file_name(&name) {
+                CacheFileKind::Layer(layer) if layer > 0 => layer_digests.get(layer - 1).cloned(),
+                _ => None,
+            };
+            manifest.files.insert(
+                name,
+                CacheManifestEntry {
+                    size: metadata.len(),
+                    digest,
+                    phase,
+                },
+            );
+        }
+
+        manifest.write(cache_path)
+    }
+
+    /// Checks that the files recorded by the phases preceding `phase` are still in `cache_path`
+    /// with their recorded size, and fails with `Error::CacheCorrupted` otherwise. A cache without
+    /// a manifest is not checked.
+    ///
+    /// This only reads file metadata, the digests are checked by `verify`.
+    pub fn ensure_intact<P: AsRef<Path>>(cache_path: P, phase: SealPhase) -> Result<()> {
+        let cache_path = cache_path.as_ref();
+        let manifest = match Self::read(cache_path)? {
+            Some(manifest) => manifest,
+            None => return Ok(()),
+        };
+
+        let issues = manifest.check(cache_path, |entry| entry.phase < phase, false)?;
+        ensure!(
+            issues.is_empty(),
+            Error::CacheCorrupted(format!(
+                "sector cache {:?} is damaged: {}",
+                cache_path,
+                issues
+                    .iter()
+                    .map(|issue| format!("{} {:?}", issue.file_name, issue.fault))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        );
+
+        Ok(())
+    }
+
+    /// Drops the entries of files which no longer exist, used after files were removed on purpose.
+    pub fn prune<P: AsRef<Path>>(cache_path: P) -> Result<()> {
+        let cache_path = cache_path.as_ref();
+        if let Some(mut manifest) = Self::read(cache_path)? {
+            manifest
+                .files
+                .retain(|name, _| cache_path.join(name).exists());
+            manifest.write(cache_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks every recorded file in `cache_path` against its size and, where recorded, its
+    /// digest.
+    pub fn verify<P: AsRef<Path>>(&self, cache_path: P) -> Result<SectorCacheReport> {
+        let issues = self.check(cache_path.as_ref(), |_| true, true)?;
+
+        Ok(SectorCacheReport {
+            checked: self.files.len(),
+            issues,
+        })
+    }
+
+    fn check<F>(&self, cache_path: &Path, filter: F, rehash: bool) -> Result<Vec<CacheFileIssue>>
+    where
+        F: Fn(&CacheManifestEntry) -> bool + Sync,
+    {
+        let faults = self
+            .files
+            .par_iter()
+            .filter(|(_, entry)| filter(entry))
+            .map(|(name, entry)| {
+                let path = cache_path.join(name);
+                let fault = match fs::metadata(&path) {
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                        Some(CacheFileFault::Missing)
+                    }
+                    Err(err) => {
+                        return Err(err).with_context(|| format!("failed to stat {:?}", path))
+                    }
+                    Ok(metadata) if metadata.len() != entry.size => {
+                        Some(CacheFileFault::SizeMismatch {
+                            expected: entry.size,
+                            actual: metadata.len(),
+                        })
+                    }
+                    Ok(_) => match &entry.digest {
+                        Some(expected) if rehash => (file_digest(&path)? != *expected)
+                            .then_some(CacheFileFault::DigestMismatch),
+                        _ => None,
+                    },
+                };
+
+                Ok(fault.map(|fault| CacheFileIssue {
+                    file_name: name.clone(),
+                    kind: CacheFileKind::from_file_name(name),
+                    phase: entry.phase,
+                    fault,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(faults.into_iter().flatten().collect())
+    }
+}
+
+/// Bookkeeping files which are rewritten while sealing and the retained unseal key, which is
+/// not produced by a sealing phase, are not tracked.
+fn is_untracked(name: &str) -> bool {
+    name == CacheKey::Manifest.to_string()
+        || name == CacheKey::LabelCheckpoint.to_string()
+        || name == CacheKey::SealingJob.to_string()
+        || name == CacheKey::UnsealKey.to_string()
+        || name.ends_with(".tmp")
+}
+
+/// Hashes the file at `path` the same way the label checkpoint hashes a layer.
+fn file_digest(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("failed to open {:?}", path))?;
+    let mut hasher = blake2b_simd::State::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let read = file
+            .read(&mut buf)
+            .with_context(|| format!("failed to read {:?}", path))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
@@ -2,6 +2,8 @@ pub use merkletree::store::StoreConfig;
 pub use storage_proofs_core::merkle::{MerkleProof, MerkleTreeTrait};
 pub use storage_proofs_porep::stacked::{Labels, PersistentAux, TemporaryAux};
 
+use std::path::PathBuf;
+
 use filecoin_hashers::Hasher;
 use serde::{Deserialize, Serialize};
 use storage_proofs_core::{merkle::BinaryMerkleTree, sector::SectorId};
@@ -11,6 +13,7 @@ use storage_proofs_post::fallback;
 use crate::constants::DefaultPieceHasher;
 
 mod bytes_amount;
+mod cache_manifest;
 mod piece_info;
 mod porep_config;
 mod porep_proof_partitions;
@@ -18,12 +21,19 @@ mod post_config;
 mod post_proof_partitions;
 mod private_replica_info;
 mod public_replica_info;
+mod seal_commit_phase1_codec;
 mod sector_class;
+mod sector_health;
 mod sector_size;
 mod sector_update_config;
+mod unseal_key;
 mod update_proof_partitions;
+mod window_post_diagnostics;
+mod window_post_output;
+mod winning_post_timings;
 
 pub use bytes_amount::*;
+pub use cache_manifest::*;
 pub use piece_info::*;
 pub use porep_config::*;
 pub use porep_proof_partitions::*;
@@ -31,10 +41,16 @@ pub use post_config::*;
 pub use post_proof_partitions::*;
 pub use private_replica_info::*;
 pub use public_replica_info::*;
+pub use seal_commit_phase1_codec::*;
 pub use sector_class::*;
+pub use sector_health::*;
 pub use sector_size::*;
 pub use sector_update_config::*;
+pub use unseal_key::*;
 pub use update_proof_partitions::*;
+pub use window_post_diagnostics::*;
+pub use window_post_output::*;
+pub use winning_post_timings::*;
 
 pub type Commitment = [u8; 32];
 pub type ChallengeSeed = [u8; 32];
@@ -56,7 +72,7 @@ pub struct SealPreCommitOutput {
 
 pub type VanillaSealProof<Tree> = stacked::Proof<Tree, DefaultPieceHasher>;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SealCommitPhase1Output<Tree: MerkleTreeTrait> {
     #[serde(bound(
         serialize = "VanillaSealProof<Tree>: Serialize",
@@ -70,11 +86,45 @@ pub struct SealCommitPhase1Output<Tree: MerkleTreeTrait> {
     pub ticket: Ticket,
 }
 
+impl<Tree: MerkleTreeTrait> Clone for SealCommitPhase1Output<Tree> {
+    fn clone(&self) -> Self {
+        Self {
+            vanilla_proofs: self.vanilla_proofs.clone(),
+            comm_r: self.comm_r,
+            comm_d: self.comm_d,
+            replica_id: self.replica_id,
+            seed: self.seed,
+            ticket: self.ticket,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SealCommitOutput {
     pub proof: Vec<u8>,
 }
 
+/// The proofs of some PoRep partitions of a sector, see `seal_commit_phase2_partitions`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SealCommitPartitionOutput {
+    /// The proven partitions, in the order of their proofs.
+    pub partitions: Vec<usize>,
+    pub proof: Vec<u8>,
+}
+
+/// A sector sealed by `seal_pre_commit_phase1_batch`, with the arguments `seal_pre_commit_phase1`
+/// takes for it.
+#[derive(Clone, Debug)]
+pub struct SealPreCommitPhase1Input {
+    pub cache_path: PathBuf,
+    pub in_path: PathBuf,
+    pub out_path: PathBuf,
+    pub prover_id: ProverId,
+    pub sector_id: SectorId,
+    pub ticket: Ticket,
+    pub piece_infos: Vec<PieceInfo>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SealPreCommitPhase1Output<Tree: MerkleTreeTrait> {
     #[serde(bound(
@@ -86,6 +136,16 @@ pub struct SealPreCommitPhase1Output<Tree: MerkleTreeTrait> {
     pub comm_d: Commitment,
 }
 
+impl<Tree: MerkleTreeTrait> Clone for SealPreCommitPhase1Output<Tree> {
+    fn clone(&self) -> Self {
+        Self {
+            labels: self.labels.clone(),
+            config: self.config.clone(),
+            comm_d: self.comm_d,
+        }
+    }
+}
+
 #[repr(transparent)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PartitionSnarkProof(pub Vec<u8>);
@@ -3,7 +3,10 @@ use std::fmt::{self, Debug, Formatter};
 use anyhow::{ensure, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::types::{Commitment, UnpaddedBytesAmount};
+use crate::{
+    error::Error,
+    types::{Commitment, UnpaddedBytesAmount},
+};
 
 #[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PieceInfo {
@@ -22,7 +25,10 @@ impl Debug for PieceInfo {
 
 impl PieceInfo {
     pub fn new(commitment: Commitment, size: UnpaddedBytesAmount) -> Result<Self> {
-        ensure!(commitment != [0; 32], "Invalid all zero commitment");
+        ensure!(
+            commitment != [0; 32],
+            Error::InvalidCommitment("Invalid all zero commitment".into())
+        );
         Ok(PieceInfo { commitment, size })
     }
 }
@@ -21,6 +21,7 @@ use storage_proofs_core::{
 
 use crate::{
     api::{as_safe_commitment, get_base_tree_leafs, get_base_tree_size},
+    error::Error,
     types::{Commitment, PersistentAux, SectorSize},
 };
 
@@ -86,14 +87,20 @@ impl<Tree: MerkleTreeTrait> PartialOrd for PrivateReplicaInfo<Tree> {
 
 impl<Tree: 'static + MerkleTreeTrait> PrivateReplicaInfo<Tree> {
     pub fn new(replica: PathBuf, comm_r: Commitment, cache_dir: PathBuf) -> Result<Self> {
-        ensure!(comm_r != [0; 32], "Invalid all zero commitment (comm_r)");
+        ensure!(
+            comm_r != [0; 32],
+            Error::InvalidCommitment("Invalid all zero commitment (comm_r)".into())
+        );
 
         let aux = {
             let f_aux_path = cache_dir.join(CacheKey::PAux.to_string());
-            let aux_bytes = fs::read(&f_aux_path)
-                .with_context(|| format!("could not read from path={:?}", f_aux_path))?;
+            let aux_bytes = fs::read(&f_aux_path).with_context(|| {
+                Error::CacheCorrupted(format!("could not read from path={:?}", f_aux_path))
+            })?;
 
-            deserialize(&aux_bytes)
+            deserialize(&aux_bytes).with_context(|| {
+                Error::CacheCorrupted(format!("could not deserialize p_aux={:?}", f_aux_path))
+            })
         }?;
 
         ensure!(replica.exists(), "Sealed replica does not exist");
@@ -115,6 +122,10 @@ impl<Tree: 'static + MerkleTreeTrait> PrivateReplicaInfo<Tree> {
         self.replica.as_path()
     }
 
+    pub fn comm_r(&self) -> Commitment {
+        self.comm_r
+    }
+
     pub fn safe_comm_r(&self) -> Result<<Tree::Hasher as Hasher>::Domain> {
         as_safe_commitment(&self.comm_r, "comm_r")
     }
@@ -4,7 +4,7 @@ use std::hash::Hash;
 use anyhow::{ensure, Result};
 use filecoin_hashers::Domain;
 
-use crate::{api::as_safe_commitment, types::Commitment};
+use crate::{api::as_safe_commitment, error::Error, types::Commitment};
 
 /// The minimal information required about a replica, in order to be able to verify
 /// a PoSt over it.
@@ -28,7 +28,10 @@ impl PartialOrd for PublicReplicaInfo {
 
 impl PublicReplicaInfo {
     pub fn new(comm_r: Commitment) -> Result<Self> {
-        ensure!(comm_r != [0; 32], "Invalid all zero commitment (comm_r)");
+        ensure!(
+            comm_r != [0; 32],
+            Error::InvalidCommitment("Invalid all zero commitment (comm_r)".into())
+        );
         Ok(PublicReplicaInfo { comm_r })
     }
 
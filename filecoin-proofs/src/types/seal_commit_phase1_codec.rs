@@ -0,0 +1,371 @@
+use std::convert::TryInto;
+
+use anyhow::{ensure, Context, Result};
+use filecoin_hashers::{Domain, Hasher, PoseidonArity};
+use sha2::{Digest, Sha256};
+use storage_proofs_core::merkle::{MerkleProof, MerkleProofTrait, MerkleTreeTrait};
+use storage_proofs_porep::stacked::{
+    Column, ColumnProof, EncodingProof, LabelingProof, ReplicaColumnProof,
+};
+
+use crate::{
+    error::Error,
+    types::{SealCommitPhase1Output, VanillaSealProof},
+};
+
+/// Magic bytes every encoded `SealCommitPhase1Output` starts with.
+pub const SEAL_COMMIT_PHASE1_OUTPUT_MAGIC: [u8; 8] = *b"FILC1OUT";
+
+/// Version of the encoding written by `encode_seal_commit_phase1_output`.
+pub const SEAL_COMMIT_PHASE1_OUTPUT_VERSION: u32 = 1;
+
+const CHECKSUM_LEN: usize = 32;
+
+/// Encodes `output` into a compact binary form, which is a lot smaller and faster to parse than
+/// its JSON form.
+///
+/// The layout, with all integers little endian and all hashes and field elements as their 32
+/// byte representation, is:
+///
+/// - magic (8 bytes) and version (u32)
+/// - comm_r, comm_d, replica_id, seed and ticket
+/// - the number of partitions (u64), then for every partition the number of its proofs (u64)
+///   followed by every proof as
+///   - the comm_d and comm_r_last merkle proofs
+///   - the column proof of c_x, then the number of drg parents (u64) and their column proofs,
+///     and the number of expander parents (u64) and their column proofs
+///   - the number of labeling proofs (u64) and every labeling proof
+///   - the encoding proof
+/// - the SHA256 digest of all bytes before it
+///
+/// A merkle proof is its leaf, its root and the number of path elements (u64), with every path
+/// element as the number of its hashes (u64), the hashes and its index (u64). A column proof is
+/// the column index (u32), the number of rows (u64), the rows and the merkle proof. A labeling or
+/// encoding proof is its layer index (u32), its node (u64), the number of parents (u64) and the
+/// parents.
+pub fn encode_seal_commit_phase1_output<Tree: MerkleTreeTrait>(
+    output: &SealCommitPhase1Output<Tree>,
+) -> Result<Vec<u8>> {
+    let mut writer = Writer { bytes: Vec::new() };
+    writer
+        .bytes
+        .extend_from_slice(&SEAL_COMMIT_PHASE1_OUTPUT_MAGIC);
+    writer.u32(SEAL_COMMIT_PHASE1_OUTPUT_VERSION);
+    writer.bytes.extend_from_slice(&output.comm_r);
+    writer.bytes.extend_from_slice(&output.comm_d);
+    writer.domain(&output.replica_id);
+    writer.bytes.extend_from_slice(&output.seed);
+    writer.bytes.extend_from_slice(&output.ticket);
+
+    writer.len(output.vanilla_proofs.len());
+    for partition_proofs in &output.vanilla_proofs {
+        writer.len(partition_proofs.len());
+        for proof in partition_proofs {
+            writer.seal_proof(proof);
+        }
+    }
+
+    let mut bytes = writer.bytes;
+    let checksum = Sha256::digest(&bytes);
+    bytes.extend_from_slice(&checksum);
+
+    Ok(bytes)
+}
+
+/// Decodes a `SealCommitPhase1Output` encoded by `encode_seal_commit_phase1_output`.
+///
+/// Fails with `Error::CacheCorrupted` if the magic, the version, the checksum or any length
+/// does not match.
+pub fn decode_seal_commit_phase1_output<Tree: MerkleTreeTrait>(
+    bytes: &[u8],
+) -> Result<SealCommitPhase1Output<Tree>> {
+    ensure!(
+        bytes.len() >= SEAL_COMMIT_PHASE1_OUTPUT_MAGIC.len() + CHECKSUM_LEN
+            && bytes.starts_with(&SEAL_COMMIT_PHASE1_OUTPUT_MAGIC),
+        Error::CacheCorrupted("not an encoded seal commit phase1 output".into())
+    );
+    let (payload, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    ensure!(
+        Sha256::digest(payload).as_slice() == checksum,
+        Error::CacheCorrupted("seal commit phase1 output checksum mismatch".into())
+    );
+
+    let mut reader = Reader {
+        bytes: &payload[SEAL_COMMIT_PHASE1_OUTPUT_MAGIC.len()..],
+    };
+    let version = u32::from_le_bytes(reader.array()?);
+    ensure!(
+        version == SEAL_COMMIT_PHASE1_OUTPUT_VERSION,
+        Error::CacheCorrupted(format!(
+            "unsupported seal commit phase1 output version {}",
+            version
+        ))
+    );
+
+    let comm_r = reader.array()?;
+    let comm_d = reader.array()?;
+    let replica_id = reader.domain()?;
+    let seed = reader.array()?;
+    let ticket = reader.array()?;
+
+    let num_partitions = reader.read_len()?;
+    let mut vanilla_proofs = Vec::with_capacity(num_partitions);
+    for _ in 0..num_partitions {
+        let num_proofs = reader.read_len()?;
+        let mut partition_proofs = Vec::with_capacity(num_proofs);
+        for _ in 0..num_proofs {
+            partition_proofs.push(reader.seal_proof::<Tree>()?);
+        }
+        vanilla_proofs.push(partition_proofs);
+    }
+    ensure!(
+        reader.bytes.is_empty(),
+        Error::CacheCorrupted("trailing bytes in seal commit phase1 output".into())
+    );
+
+    Ok(SealCommitPhase1Output {
+        vanilla_proofs,
+        comm_r,
+        comm_d,
+        replica_id,
+        seed,
+        ticket,
+    })
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u64(len as u64);
+    }
+
+    fn domain<D: Domain>(&mut self, domain: &D) {
+        self.bytes.extend_from_slice(domain.as_ref());
+    }
+
+    fn domains<D: Domain>(&mut self, domains: &[D]) {
+        self.len(domains.len());
+        for domain in domains {
+            self.domain(domain);
+        }
+    }
+
+    fn merkle_proof<P: MerkleProofTrait>(&mut self, proof: &P) {
+        self.domain(&proof.leaf());
+        self.domain(&proof.root());
+        let path = proof.path();
+        self.len(path.len());
+        for (hashes, index) in &path {
+            self.domains(hashes);
+            self.len(*index);
+        }
+    }
+
+    fn column_proof<P: MerkleProofTrait>(&mut self, proof: &ColumnProof<P>) {
+        self.u32(proof.column().index());
+        self.domains(proof.column().rows());
+        self.merkle_proof(proof.inclusion_proof());
+    }
+
+    fn column_proofs<P: MerkleProofTrait>(&mut self, proofs: &[ColumnProof<P>]) {
+        self.len(proofs.len());
+        for proof in proofs {
+            self.column_proof(proof);
+        }
+    }
+
+    fn parents_proof<D: Domain>(&mut self, layer_index: u32, node: u64, parents: &[D]) {
+        self.u32(layer_index);
+        self.u64(node);
+        self.domains(parents);
+    }
+
+    fn seal_proof<Tree: MerkleTreeTrait>(&mut self, proof: &VanillaSealProof<Tree>) {
+        self.merkle_proof(&proof.comm_d_proofs);
+        self.merkle_proof(&proof.comm_r_last_proof);
+
+        let column_proofs = &proof.replica_column_proofs;
+        self.column_proof(&column_proofs.c_x);
+        self.column_proofs(&column_proofs.drg_parents);
+        self.column_proofs(&column_proofs.exp_parents);
+
+        self.len(proof.labeling_proofs.len());
+        for labeling_proof in &proof.labeling_proofs {
+            self.parents_proof(
+                labeling_proof.layer_index(),
+                labeling_proof.node(),
+                labeling_proof.parents(),
+            );
+        }
+
+        let encoding_proof = &proof.encoding_proof;
+        self.parents_proof(
+            encoding_proof.layer_index(),
+            encoding_proof.node(),
+            encoding_proof.parents(),
+        );
+    }
+}
+
+/// Bounds checked cursor over the payload of an encoded output.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        ensure!(
+            len <= self.bytes.len(),
+            Error::CacheCorrupted("truncated seal commit phase1 output".into())
+        );
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("length checked by take"))
+    }
+
+    /// Reads a u64 length or count, which can never exceed the remaining bytes.
+    fn read_len(&mut self) -> Result<usize> {
+        let len = u64::from_le_bytes(self.array()?);
+        ensure!(
+            len <= self.bytes.len() as u64,
+            Error::CacheCorrupted("invalid length in seal commit phase1 output".into())
+        );
+
+        Ok(len as usize)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn domain<D: Domain>(&mut self) -> Result<D> {
+        D::try_from_bytes(self.take(32)?)
+            .with_context(|| Error::CacheCorrupted("invalid field element".into()))
+    }
+
+    fn domains<D: Domain>(&mut self) -> Result<Vec<D>> {
+        let len = self.read_len()?;
+        (0..len).map(|_| self.domain()).collect()
+    }
+
+    fn merkle_proof<H, BaseArity, SubTreeArity, TopTreeArity>(
+        &mut self,
+    ) -> Result<MerkleProof<H, BaseArity, SubTreeArity, TopTreeArity>>
+    where
+        H: Hasher,
+        BaseArity: 'static + PoseidonArity,
+        SubTreeArity: 'static + PoseidonArity,
+        TopTreeArity: 'static + PoseidonArity,
+    {
+        let leaf = self.domain()?;
+        let root = self.domain()?;
+        let path_len = self.read_len()?;
+        let mut path = Vec::with_capacity(path_len);
+        for _ in 0..path_len {
+            let hashes = self.domains()?;
+            let index = self.read_index()?;
+            path.push((hashes, index));
+        }
+        // The sub and top tree elements are split off the end of the path.
+        let min_path_len = [SubTreeArity::to_usize(), TopTreeArity::to_usize()]
+            .iter()
+            .filter(|arity| **arity > 0)
+            .count();
+        ensure!(
+            path.len() >= min_path_len,
+            Error::CacheCorrupted("merkle proof path too short".into())
+        );
+
+        Ok(MerkleProof::from_parts(leaf, root, path))
+    }
+
+    fn column_proof<H, BaseArity, SubTreeArity, TopTreeArity>(
+        &mut self,
+    ) -> Result<ColumnProof<MerkleProof<H, BaseArity, SubTreeArity, TopTreeArity>>>
+    where
+        H: Hasher,
+        BaseArity: 'static + PoseidonArity,
+        SubTreeArity: 'static + PoseidonArity,
+        TopTreeArity: 'static + PoseidonArity,
+    {
+        let index = self.u32()?;
+        let rows = self.domains()?;
+        let inclusion_proof = self.merkle_proof()?;
+
+        ColumnProof::from_column(Column::new(index, rows)?, inclusion_proof)
+    }
+
+    fn column_proofs<H, BaseArity, SubTreeArity, TopTreeArity>(
+        &mut self,
+    ) -> Result<Vec<ColumnProof<MerkleProof<H, BaseArity, SubTreeArity, TopTreeArity>>>>
+    where
+        H: Hasher,
+        BaseArity: 'static + PoseidonArity,
+        SubTreeArity: 'static + PoseidonArity,
+        TopTreeArity: 'static + PoseidonArity,
+    {
+        let len = self.read_len()?;
+        (0..len).map(|_| self.column_proof()).collect()
+    }
+
+    /// Reads the layer index, node and parents of a labeling or encoding proof.
+    fn parents_proof<D: Domain>(&mut self) -> Result<(u32, u64, Vec<D>)> {
+        Ok((self.u32()?, self.u64()?, self.domains()?))
+    }
+
+    fn seal_proof<Tree: MerkleTreeTrait>(&mut self) -> Result<VanillaSealProof<Tree>> {
+        let comm_d_proofs = self.merkle_proof()?;
+        let comm_r_last_proof = self.merkle_proof()?;
+        let replica_column_proofs = ReplicaColumnProof {
+            c_x: self.column_proof()?,
+            drg_parents: self.column_proofs()?,
+            exp_parents: self.column_proofs()?,
+        };
+
+        let num_labeling_proofs = self.read_len()?;
+        let labeling_proofs = (0..num_labeling_proofs)
+            .map(|_| {
+                let (layer_index, node, parents) = self.parents_proof()?;
+                Ok(LabelingProof::new(layer_index, node, parents))
+            })
+            .collect::<Result<_>>()?;
+
+        let (layer_index, node, parents) = self.parents_proof()?;
+        let encoding_proof = EncodingProof::new(layer_index, node, parents);
+
+        Ok(VanillaSealProof::<Tree> {
+            comm_d_proofs,
+            comm_r_last_proof,
+            replica_column_proofs,
+            labeling_proofs,
+            encoding_proof,
+        })
+    }
+
+    /// Reads a path element index, which is stored as u64.
+    fn read_index(&mut self) -> Result<usize> {
+        self.u64()?
+            .try_into()
+            .map_err(|_| Error::CacheCorrupted("invalid merkle path index".into()).into())
+    }
+}
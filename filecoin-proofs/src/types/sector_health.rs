@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use storage_proofs_core::sector::SectorId;
+
+/// The outcome of checking a single sector with `check_sectors_for_post`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SectorHealth {
+    /// Every challenge was proven, which took the given time.
+    Ok(Duration),
+    /// The replica or one of the files of its cache is missing.
+    MissingFile(String),
+    /// An inclusion proof of a challenged leaf did not validate against comm_r_last.
+    BadMerklePath,
+    /// comm_r does not match comm_c and comm_r_last from p_aux.
+    CommRMismatch,
+    /// Proving the challenges did not finish within the given timeout.
+    SlowRead(Duration),
+    /// The check never started because every thread of the check pool was blocked on other
+    /// sectors for the whole timeout, so nothing is known about this sector.
+    NotStarted,
+    /// Any other failure, e.g. a corrupt tree file or a sector without challenges.
+    Error(String),
+}
+
+impl SectorHealth {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, SectorHealth::Ok(_))
+    }
+}
+
+/// The health of every sector checked by `check_sectors_for_post`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SectorHealthReport {
+    pub sectors: BTreeMap<SectorId, SectorHealth>,
+}
+
+impl SectorHealthReport {
+    /// Returns the sectors which would fail a Window PoSt or could not be checked in time, to be
+    /// declared as faulty.
+    pub fn faulty_sectors(&self) -> Vec<SectorId> {
+        self.sectors
+            .iter()
+            .filter(|(_, health)| !health.is_ok())
+            .map(|(sector_id, _)| *sector_id)
+            .collect()
+    }
+}
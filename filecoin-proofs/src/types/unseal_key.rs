@@ -0,0 +1,32 @@
+use std::path::{Path, PathBuf};
+
+use storage_proofs_core::cache_key::CacheKey;
+
+/// How `retain_unseal_key` keeps the key of a sector, i.e. the labels of its last SDR layer,
+/// so that it can be unsealed without relabeling.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UnsealKeyRetention {
+    /// Hard link the last layer labels. No additional disk space is used until the layer data is
+    /// cleared, after which the labels of the last layer are the only ones left.
+    LastLayer,
+    /// Copy the last layer labels into a standalone key file, e.g. to move it to other storage.
+    KeyFile,
+}
+
+/// The disk space taken by a retained unseal key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsealKeyReport {
+    /// Path to the retained key.
+    pub path: PathBuf,
+    pub retention: UnsealKeyRetention,
+    /// Size of the key, which always equals the padded sector size.
+    pub key_bytes: u64,
+    /// Disk space the key takes on top of the sector cache as it is right now. This is zero
+    /// for `UnsealKeyRetention::LastLayer` as long as the layer data was not cleared.
+    pub additional_bytes: u64,
+}
+
+/// Returns the path of the retained unseal key in `cache_path`.
+pub fn unseal_key_path<P: AsRef<Path>>(cache_path: P) -> PathBuf {
+    cache_path.as_ref().join(CacheKey::UnsealKey.to_string())
+}
@@ -0,0 +1,81 @@
+use storage_proofs_core::sector::SectorId;
+
+/// A single reason for a Window PoSt to fail verification, found by
+/// `verify_window_post_with_diagnostics`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowPoStFailure {
+    /// The Groth16 proof of the partition does not verify against the public inputs derived for
+    /// it.
+    SnarkProof { partition: usize },
+    /// No vanilla proof was given for a sector of the partition.
+    MissingVanillaProof {
+        partition: usize,
+        sector_id: SectorId,
+    },
+    /// H(comm_c || comm_r_last) of the vanilla proof does not match comm_r of the sector.
+    CommRMismatch {
+        partition: usize,
+        sector_id: SectorId,
+    },
+    /// The vanilla proof of the sector does not have one inclusion proof per challenge.
+    ChallengeCount {
+        partition: usize,
+        sector_id: SectorId,
+        expected: usize,
+        actual: usize,
+    },
+    /// The inclusion proof for the challenge with the given index does not prove the challenged
+    /// leaf up to comm_r_last, or has a path of the wrong length.
+    InclusionProof {
+        partition: usize,
+        sector_id: SectorId,
+        challenge_index: usize,
+        challenge: u64,
+    },
+    /// The vanilla proof of the sector is rejected by `FallbackPoSt::verify` without any of the
+    /// failures above, e.g. because it does not prove exactly one sector.
+    InvalidVanillaProof {
+        partition: usize,
+        sector_id: SectorId,
+    },
+}
+
+impl WindowPoStFailure {
+    pub fn partition(&self) -> usize {
+        match self {
+            WindowPoStFailure::SnarkProof { partition }
+            | WindowPoStFailure::MissingVanillaProof { partition, .. }
+            | WindowPoStFailure::CommRMismatch { partition, .. }
+            | WindowPoStFailure::ChallengeCount { partition, .. }
+            | WindowPoStFailure::InclusionProof { partition, .. }
+            | WindowPoStFailure::InvalidVanillaProof { partition, .. } => *partition,
+        }
+    }
+}
+
+/// The outcome of `verify_window_post_with_diagnostics`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowPoStReport {
+    /// The number of partitions which were checked.
+    pub partitions: usize,
+    pub failures: Vec<WindowPoStFailure>,
+}
+
+impl WindowPoStReport {
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Returns the indices of all partitions with at least one failure, in ascending order.
+    pub fn failed_partitions(&self) -> Vec<usize> {
+        let mut partitions: Vec<_> = self
+            .failures
+            .iter()
+            .map(WindowPoStFailure::partition)
+            .collect();
+        partitions.sort_unstable();
+        partitions.dedup();
+
+        partitions
+    }
+}
@@ -0,0 +1,11 @@
+use storage_proofs_core::sector::SectorId;
+
+use crate::types::SnarkProof;
+
+/// A Window PoSt proof generated by `generate_window_post_skip_faulty`, with the sectors it
+/// skipped as faulty.
+#[derive(Clone, Debug)]
+pub struct WindowPoStOutput {
+    pub proof: SnarkProof,
+    pub skipped: Vec<SectorId>,
+}
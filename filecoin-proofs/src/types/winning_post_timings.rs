@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use storage_proofs_core::sector::SectorId;
+
+/// Wall time spent on a single challenged sector by `generate_winning_post_with_timings`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WinningPoStSectorTimings {
+    pub sector_id: SectorId,
+    /// Opening tree_r_last of the sector.
+    pub tree_open: Duration,
+    /// Reading the challenged leafs and generating their inclusion proofs.
+    pub proof_read: Duration,
+}
+
+/// Breakdown of the wall time of a Winning PoSt generated by
+/// `generate_winning_post_with_timings`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WinningPoStTimings {
+    /// Deriving the challenged leafs of every sector.
+    pub challenge_derivation: Duration,
+    /// Every challenged sector, in the order of the given replicas.
+    pub sectors: Vec<WinningPoStSectorTimings>,
+    /// Building and synthesizing the circuit.
+    pub synthesis: Duration,
+    /// Creating the SNARK from the synthesized circuit.
+    pub proving: Duration,
+    pub total: Duration,
+}
+
+impl WinningPoStTimings {
+    /// Total time spent opening trees, over all sectors.
+    pub fn tree_open(&self) -> Duration {
+        self.sectors.iter().map(|sector| sector.tree_open).sum()
+    }
+
+    /// Total time spent reading challenged leafs and proving their inclusion, over all sectors.
+    pub fn proof_read(&self) -> Duration {
+        self.sectors.iter().map(|sector| sector.proof_read).sum()
+    }
+}
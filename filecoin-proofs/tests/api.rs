@@ -1,7 +1,8 @@
 use std::collections::BTreeMap;
-use std::fs::{metadata, read_dir, remove_file, File, OpenOptions};
+use std::fs::{metadata, read_dir, remove_file, rename, File, OpenOptions};
 use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use anyhow::{ensure, Context, Error, Result};
 use bellperson::groth16;
@@ -10,29 +11,38 @@ use blstrs::{Bls12, Scalar as Fr};
 use ff::Field;
 use filecoin_hashers::Hasher;
 use filecoin_proofs::{
-    add_piece, aggregate_seal_commit_proofs, clear_cache, clear_layer_data, clear_synthetic_proofs,
-    compute_comm_d, decode_from, decode_from_range, encode_into, fauxrep_aux,
+    add_piece, aggregate_seal_commit_proofs, check_sectors_for_post, clear_cache, clear_layer_data,
+    clear_synthetic_proofs, compute_comm_d, decode_from, decode_from_range,
+    decode_seal_commit_phase1_output, encode_into, encode_seal_commit_phase1_output, fauxrep_aux,
     generate_empty_sector_update_proof, generate_empty_sector_update_proof_with_vanilla,
     generate_fallback_sector_challenges, generate_partition_proofs, generate_piece_commitment,
     generate_single_partition_proof, generate_single_vanilla_proof,
-    generate_single_window_post_with_vanilla, generate_synth_proofs, generate_tree_c,
-    generate_tree_r_last, generate_window_post, generate_window_post_with_vanilla,
-    generate_winning_post, generate_winning_post_sector_challenge,
+    generate_single_vanilla_proof_with_store, generate_single_window_post_with_vanilla,
+    generate_synth_proofs, generate_tree_c, generate_tree_r_last, generate_window_post,
+    generate_window_post_skip_faulty, generate_window_post_streaming,
+    generate_window_post_with_vanilla, generate_winning_post,
+    generate_winning_post_sector_challenge, generate_winning_post_with_timings,
     generate_winning_post_with_vanilla, get_num_partition_for_fallback_post, get_seal_inputs,
-    merge_window_post_partition_proofs, remove_encoded_data, seal_commit_phase1,
-    seal_commit_phase2, seal_pre_commit_phase1, seal_pre_commit_phase2, unseal_range,
+    merge_seal_commit_partition_proofs, merge_window_post_partition_proofs, rebuild_tree_c,
+    rebuild_tree_r_last, remove_encoded_data, resume_seal_pre_commit_phase1, retain_unseal_key,
+    seal_commit_phase1, seal_commit_phase2, seal_commit_phase2_partitions, seal_pre_commit_phase1,
+    seal_pre_commit_phase1_batch, seal_pre_commit_phase2, unseal_range, unseal_range_with_key,
     validate_cache_for_commit, validate_cache_for_precommit_phase2,
     verify_aggregate_seal_commit_proofs, verify_empty_sector_update_proof, verify_partition_proofs,
-    verify_seal, verify_single_partition_proof, verify_window_post, verify_winning_post,
-    Commitment, DefaultTreeDomain, MerkleTreeTrait, PaddedBytesAmount, PieceInfo, PoRepConfig,
-    PoStConfig, PoStType, PrivateReplicaInfo, ProverId, PublicReplicaInfo, SealCommitOutput,
-    SealPreCommitOutput, SealPreCommitPhase1Output, SectorShape16KiB, SectorShape2KiB,
-    SectorShape32KiB, SectorShape4KiB, SectorUpdateConfig, UnpaddedByteIndex, UnpaddedBytesAmount,
+    verify_seal, verify_sector_cache, verify_single_partition_proof, verify_window_post,
+    verify_window_post_with_diagnostics, verify_winning_post, CacheFileFault, CacheFileKind,
+    Commitment, DefaultPieceDomain, DefaultTreeDomain, Error as ApiError, FallbackPoStSectorProof,
+    LocalReplicaStore, MerkleTreeTrait, PaddedBytesAmount, PieceInfo, PoRepConfig, PoStConfig,
+    PoStType, PrivateReplicaInfo, ProverId, PublicReplicaInfo, SealCommitOutput,
+    SealCommitPhase1Output, SealPhase, SealPreCommitOutput, SealPreCommitPhase1Input,
+    SealPreCommitPhase1Output, SealingJob, SealingPhase, SectorHealth, SectorShape16KiB,
+    SectorShape2KiB, SectorShape32KiB, SectorShape4KiB, SectorUpdateConfig, UnpaddedByteIndex,
+    UnpaddedBytesAmount, UnsealKeyRetention, VanillaSealProof, WindowPoStFailure,
     SECTOR_SIZE_16_KIB, SECTOR_SIZE_2_KIB, SECTOR_SIZE_32_KIB, SECTOR_SIZE_4_KIB,
     WINDOW_POST_CHALLENGE_COUNT, WINDOW_POST_SECTOR_COUNT, WINNING_POST_CHALLENGE_COUNT,
     WINNING_POST_SECTOR_COUNT,
 };
-use fr32::bytes_into_fr;
+use fr32::{bytes_into_fr, Fr32Writer};
 use log::info;
 use memmap2::MmapOptions;
 use merkletree::store::StoreConfig;
@@ -43,10 +53,14 @@ use storage_proofs_core::{
     api_version::{ApiFeature, ApiVersion},
     cache_key::CacheKey,
     is_legacy_porep_id,
-    merkle::get_base_tree_count,
+    merkle::{get_base_tree_count, MerkleProof},
     sector::SectorId,
     util::NODE_SIZE,
 };
+use storage_proofs_porep::stacked::{
+    ColumnProof, EncodingProof, LabelingProof, ReplicaColumnProof,
+};
+use storage_proofs_post::fallback;
 use storage_proofs_update::constants::TreeRHasher;
 use tempfile::{tempdir, NamedTempFile, TempDir};
 
@@ -821,189 +835,1619 @@ fn run_resumable_seal<Tree: 'static + MerkleTreeTrait>(
 }
 
 #[test]
-#[ignore]
-fn test_winning_post_2kib_base_8() -> Result<()> {
-    winning_post::<SectorShape2KiB>(SECTOR_SIZE_2_KIB, false, ApiVersion::V1_0_0)?;
-    winning_post::<SectorShape2KiB>(SECTOR_SIZE_2_KIB, true, ApiVersion::V1_0_0)?;
-    winning_post::<SectorShape2KiB>(SECTOR_SIZE_2_KIB, false, ApiVersion::V1_1_0)?;
-    winning_post::<SectorShape2KiB>(SECTOR_SIZE_2_KIB, true, ApiVersion::V1_1_0)
-}
+fn test_resume_seal_pre_commit_phase1_from_checkpoint() -> Result<()> {
+    fil_logger::maybe_init();
 
-#[test]
-#[ignore]
-fn test_winning_post_4kib_sub_8_2() -> Result<()> {
-    winning_post::<SectorShape4KiB>(SECTOR_SIZE_4_KIB, false, ApiVersion::V1_0_0)?;
-    winning_post::<SectorShape4KiB>(SECTOR_SIZE_4_KIB, true, ApiVersion::V1_0_0)?;
-    winning_post::<SectorShape4KiB>(SECTOR_SIZE_4_KIB, false, ApiVersion::V1_1_0)?;
-    winning_post::<SectorShape4KiB>(SECTOR_SIZE_4_KIB, true, ApiVersion::V1_1_0)
-}
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
 
-#[test]
-#[ignore]
-fn test_winning_post_16kib_sub_8_8() -> Result<()> {
-    winning_post::<SectorShape16KiB>(SECTOR_SIZE_16_KIB, false, ApiVersion::V1_0_0)?;
-    winning_post::<SectorShape16KiB>(SECTOR_SIZE_16_KIB, true, ApiVersion::V1_0_0)?;
-    winning_post::<SectorShape16KiB>(SECTOR_SIZE_16_KIB, false, ApiVersion::V1_1_0)?;
-    winning_post::<SectorShape16KiB>(SECTOR_SIZE_16_KIB, true, ApiVersion::V1_1_0)
-}
+    let (mut piece_file, _piece_bytes) = generate_piece_file(sector_size)?;
+    let sealed_sector_file = NamedTempFile::new()?;
+    let cache_dir = tempdir()?;
 
-#[test]
-#[ignore]
-fn test_winning_post_32kib_top_8_8_2() -> Result<()> {
-    winning_post::<SectorShape32KiB>(SECTOR_SIZE_32_KIB, false, ApiVersion::V1_0_0)?;
-    winning_post::<SectorShape32KiB>(SECTOR_SIZE_32_KIB, true, ApiVersion::V1_0_0)?;
-    winning_post::<SectorShape32KiB>(SECTOR_SIZE_32_KIB, false, ApiVersion::V1_1_0)?;
-    winning_post::<SectorShape32KiB>(SECTOR_SIZE_32_KIB, true, ApiVersion::V1_1_0)
+    let config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let ticket = rng.gen();
+    let sector_id = rng.gen::<u64>().into();
+
+    let piece_infos = vec![generate_piece_commitment(
+        piece_file.as_file_mut(),
+        config.unpadded_bytes_amount(),
+    )?];
+    let run_phase1 = |piece_file: &mut NamedTempFile| -> Result<()> {
+        piece_file.rewind()?;
+        let mut staged_sector_file = NamedTempFile::new()?;
+        add_piece(
+            piece_file,
+            &mut staged_sector_file,
+            config.unpadded_bytes_amount(),
+            &[],
+        )?;
+
+        let phase1_output = resume_seal_pre_commit_phase1::<_, _, _, SectorShape2KiB>(
+            &config,
+            cache_dir.path(),
+            staged_sector_file.path(),
+            sealed_sector_file.path(),
+            prover_id,
+            sector_id,
+            ticket,
+            &piece_infos,
+        )?;
+        validate_cache_for_precommit_phase2(
+            cache_dir.path(),
+            staged_sector_file.path(),
+            &phase1_output,
+        )
+    };
+
+    run_phase1(&mut piece_file)?;
+    let layers = get_layer_file_paths(&cache_dir);
+    assert_eq!(layers.len(), 2, "not all expected layers were created");
+    let layer_data = layers
+        .iter()
+        .map(std::fs::read)
+        .collect::<io::Result<Vec<_>>>()?;
+
+    // Corrupt the first layer without changing its size, so that only the checkpoint digest can
+    // detect it.
+    let mut corrupt = layer_data[0].clone();
+    corrupt[0] ^= 0xff;
+    std::fs::write(&layers[0], corrupt)?;
+
+    run_phase1(&mut piece_file)?;
+
+    let recreated = get_layer_file_paths(&cache_dir)
+        .iter()
+        .map(std::fs::read)
+        .collect::<io::Result<Vec<_>>>()?;
+    assert_eq!(
+        recreated, layer_data,
+        "resumed layers don't match original ones"
+    );
+
+    Ok(())
 }
 
 #[test]
-fn test_winning_post_empty_sector_challenge() -> Result<()> {
-    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+fn test_seal_pre_commit_phase1_batch() -> Result<()> {
+    fil_logger::maybe_init();
 
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
     let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
     let mut prover_id = [0u8; 32];
     prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
 
-    let sector_count = 0;
-    let sector_size = SECTOR_SIZE_2_KIB;
-    let porep_id = ARBITRARY_POREP_ID_V1_1_0;
-    let api_version = ApiVersion::V1_1_0;
+    let config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
 
-    let porep_config = PoRepConfig::new_groth16(sector_size, porep_id, api_version);
-    let (_, replica, _, _) =
-        create_seal::<_, SectorShape2KiB>(&porep_config, &mut rng, prover_id, true)?;
+    let mut inputs = Vec::new();
+    let mut expected = Vec::new();
+    // Keep the files of the batch alive until the end of the test.
+    let mut files = Vec::new();
+    for _ in 0..3 {
+        let (mut piece_file, _piece_bytes) = generate_piece_file(sector_size)?;
+        let ticket = rng.gen();
+        let sector_id = rng.gen::<u64>().into();
 
-    let random_fr: DefaultTreeDomain = Fr::random(rng).into();
-    let mut randomness = [0u8; 32];
-    randomness.copy_from_slice(AsRef::<[u8]>::as_ref(&random_fr));
+        let cache_dir = tempdir()?;
+        let sealed_sector_file = NamedTempFile::new()?;
+        let (piece_infos, phase1_output) = run_seal_pre_commit_phase1::<SectorShape2KiB>(
+            &config,
+            prover_id,
+            sector_id,
+            ticket,
+            &cache_dir,
+            &mut piece_file,
+            &sealed_sector_file,
+        )?;
+        let layers = get_layer_file_paths(&cache_dir)
+            .iter()
+            .map(std::fs::read)
+            .collect::<io::Result<Vec<_>>>()?;
+        expected.push((phase1_output.comm_d, layers));
+
+        piece_file.rewind()?;
+        let mut staged_sector_file = NamedTempFile::new()?;
+        add_piece(
+            &mut piece_file,
+            &mut staged_sector_file,
+            config.unpadded_bytes_amount(),
+            &[],
+        )?;
+        let batch_cache_dir = tempdir()?;
+        let batch_sealed_sector_file = NamedTempFile::new()?;
+        inputs.push(SealPreCommitPhase1Input {
+            cache_path: batch_cache_dir.path().to_path_buf(),
+            in_path: staged_sector_file.path().to_path_buf(),
+            out_path: batch_sealed_sector_file.path().to_path_buf(),
+            prover_id,
+            sector_id,
+            ticket,
+            piece_infos,
+        });
+        files.push((
+            batch_cache_dir,
+            staged_sector_file,
+            batch_sealed_sector_file,
+        ));
+    }
 
-    let config = PoStConfig {
-        sector_size: sector_size.into(),
-        sector_count,
-        challenge_count: WINNING_POST_CHALLENGE_COUNT,
-        typ: PoStType::Winning,
-        priority: false,
-        api_version,
-    };
+    let outputs = seal_pre_commit_phase1_batch::<SectorShape2KiB>(&config, &inputs)?;
+    assert_eq!(outputs.len(), inputs.len());
 
-    assert!(generate_winning_post_sector_challenge::<SectorShape2KiB>(
-        &config,
-        &randomness,
-        sector_count as u64,
-        prover_id
-    )
-    .is_err());
+    for (((output, input), (comm_d, layers)), (cache_dir, _, _)) in
+        outputs.iter().zip(&inputs).zip(&expected).zip(&files)
+    {
+        validate_cache_for_precommit_phase2(&input.cache_path, &input.in_path, output)?;
+        assert_eq!(&output.comm_d, comm_d);
 
-    replica.close()?;
+        let batch_layers = get_layer_file_paths(cache_dir)
+            .iter()
+            .map(std::fs::read)
+            .collect::<io::Result<Vec<_>>>()?;
+        assert_eq!(
+            &batch_layers, layers,
+            "batched layers don't match the ones of a single sector"
+        );
+    }
 
     Ok(())
 }
 
-fn winning_post<Tree: 'static + MerkleTreeTrait>(
-    sector_size: u64,
-    fake: bool,
-    api_version: ApiVersion,
-) -> Result<()> {
-    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+#[test]
+fn test_verify_sector_cache() -> Result<()> {
+    fil_logger::maybe_init();
 
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
     let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
     let mut prover_id = [0u8; 32];
     prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
 
-    let porep_id = match api_version {
-        ApiVersion::V1_0_0 => ARBITRARY_POREP_ID_V1_0_0,
-        ApiVersion::V1_1_0 => ARBITRARY_POREP_ID_V1_1_0,
-        ApiVersion::V1_2_0 => ARBITRARY_POREP_ID_V1_2_0,
-    };
+    let (mut piece_file, _piece_bytes) = generate_piece_file(sector_size)?;
+    let sealed_sector_file = NamedTempFile::new()?;
+    let cache_dir = tempdir()?;
 
-    let porep_config = PoRepConfig::new_groth16(sector_size, porep_id, api_version);
-    let (sector_id, replica, comm_r, cache_dir) = if fake {
-        create_fake_seal::<_, Tree>(&mut rng, sector_size, &porep_id, api_version)?
-    } else {
-        create_seal::<_, Tree>(&porep_config, &mut rng, prover_id, true)?
-    };
-    let sector_count = WINNING_POST_SECTOR_COUNT;
+    let config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let ticket = rng.gen();
+    let sector_id = rng.gen::<u64>().into();
 
-    let random_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
-    let mut randomness = [0u8; 32];
-    randomness.copy_from_slice(AsRef::<[u8]>::as_ref(&random_fr));
+    // Label with a checkpoint, so that the layer digests end up in the manifest.
+    let piece_infos = vec![generate_piece_commitment(
+        piece_file.as_file_mut(),
+        config.unpadded_bytes_amount(),
+    )?];
+    piece_file.rewind()?;
+    let mut staged_sector_file = NamedTempFile::new()?;
+    add_piece(
+        &mut piece_file,
+        &mut staged_sector_file,
+        config.unpadded_bytes_amount(),
+        &[],
+    )?;
+    let phase1_output = resume_seal_pre_commit_phase1::<_, _, _, SectorShape2KiB>(
+        &config,
+        cache_dir.path(),
+        staged_sector_file.path(),
+        sealed_sector_file.path(),
+        prover_id,
+        sector_id,
+        ticket,
+        &piece_infos,
+    )?;
+    let pre_commit_output = seal_pre_commit_phase2(
+        &config,
+        phase1_output,
+        cache_dir.path(),
+        sealed_sector_file.path(),
+    )?;
 
-    let config = PoStConfig {
-        sector_size: sector_size.into(),
-        sector_count,
-        challenge_count: WINNING_POST_CHALLENGE_COUNT,
-        typ: PoStType::Winning,
-        priority: false,
-        api_version,
-    };
+    let report = verify_sector_cache(cache_dir.path())?;
+    assert!(report.is_ok(), "intact cache reported issues: {:?}", report);
+    assert!(report.checked > 0, "no files were recorded in the manifest");
 
-    let challenged_sectors = generate_winning_post_sector_challenge::<Tree>(
+    // Corrupt the second layer without changing its size and remove tree_c.
+    let layers = get_layer_file_paths(&cache_dir);
+    let mut layer = std::fs::read(&layers[1])?;
+    layer[0] ^= 0xff;
+    std::fs::write(&layers[1], layer)?;
+    for entry in read_dir(&cache_dir)? {
+        let path = entry?.path();
+        if path.to_string_lossy().contains("tree-c") {
+            remove_file(path)?;
+        }
+    }
+
+    let report = verify_sector_cache(cache_dir.path())?;
+    assert!(!report.is_ok(), "corrupt cache was not detected");
+    for issue in &report.issues {
+        match (issue.kind, &issue.fault) {
+            (CacheFileKind::Layer(2), CacheFileFault::DigestMismatch) => {
+                assert_eq!(issue.phase, SealPhase::PreCommit1);
+            }
+            (CacheFileKind::TreeC, CacheFileFault::Missing) => {
+                assert_eq!(issue.phase, SealPhase::PreCommit2);
+            }
+            _ => panic!("unexpected issue {:?}", issue),
+        }
+    }
+    assert!(report
+        .issues
+        .iter()
+        .any(|issue| issue.kind == CacheFileKind::Layer(2)));
+    assert!(report
+        .issues
+        .iter()
+        .any(|issue| issue.kind == CacheFileKind::TreeC));
+
+    // The missing tree_c is caught before commit phase1 starts.
+    let err = seal_commit_phase1::<_, SectorShape2KiB>(
         &config,
-        &randomness,
-        sector_count as u64,
+        cache_dir.path(),
+        sealed_sector_file.path(),
         prover_id,
-    )?;
-    assert_eq!(challenged_sectors.len(), sector_count);
-    assert_eq!(challenged_sectors[0], 0); // with a sector_count of 1, the only valid index is 0
+        sector_id,
+        ticket,
+        rng.gen(),
+        pre_commit_output,
+        &piece_infos,
+    )
+    .expect_err("commit phase1 on a damaged cache succeeded");
+    assert!(
+        matches!(
+            err.downcast_ref::<ApiError>(),
+            Some(ApiError::CacheCorrupted(_))
+        ),
+        "unexpected error {:?}",
+        err
+    );
 
-    let pub_replicas = vec![(sector_id, PublicReplicaInfo::new(comm_r)?)];
-    let private_replica_info =
-        PrivateReplicaInfo::new(replica.path().into(), comm_r, cache_dir.path().into())?;
+    Ok(())
+}
 
-    /////////////////////////////////////////////
-    // The following methods of proof generation are functionally equivalent:
-    // 1)
-    //
-    let priv_replicas = vec![(sector_id, private_replica_info.clone())];
-    let proof = generate_winning_post::<Tree>(&config, &randomness, &priv_replicas[..], prover_id)?;
+#[test]
+fn test_sealing_job_resumes_after_reload() -> Result<()> {
+    fil_logger::maybe_init();
 
-    let valid =
-        verify_winning_post::<Tree>(&config, &randomness, &pub_replicas[..], prover_id, &proof)?;
-    assert!(valid, "proof did not verify");
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
 
-    //
-    // 2)
-    let mut vanilla_proofs = Vec::with_capacity(sector_count);
-    let challenges =
-        generate_fallback_sector_challenges::<Tree>(&config, &randomness, &[sector_id], prover_id)?;
+    let config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
 
-    // Make sure that files can be read-only for a window post.
-    set_readonly_flag(replica.path(), true);
-    set_readonly_flag(cache_dir.path(), true);
+    let (mut piece_file, _piece_bytes) = generate_piece_file(sector_size)?;
+    let piece_info =
+        generate_piece_commitment(piece_file.as_file_mut(), config.unpadded_bytes_amount())?;
+    piece_file.rewind()?;
+    let mut staged_sector_file = NamedTempFile::new()?;
+    add_piece(
+        &mut piece_file,
+        &mut staged_sector_file,
+        config.unpadded_bytes_amount(),
+        &[],
+    )?;
+    let sealed_sector_file = NamedTempFile::new()?;
+    let cache_dir = tempdir()?;
 
-    let single_proof = generate_single_vanilla_proof::<Tree>(
-        &config,
-        sector_id,
-        &private_replica_info,
-        &challenges[&sector_id],
+    let input = SealPreCommitPhase1Input {
+        cache_path: cache_dir.path().to_path_buf(),
+        in_path: staged_sector_file.path().to_path_buf(),
+        out_path: sealed_sector_file.path().to_path_buf(),
+        prover_id,
+        sector_id: rng.gen::<u64>().into(),
+        ticket: rng.gen(),
+        piece_infos: vec![piece_info],
+    };
+
+    let mut job = SealingJob::<SectorShape2KiB>::create(&config, input.clone())?;
+    assert_eq!(job.phase(), SealingPhase::PreCommit1);
+    assert!(
+        SealingJob::<SectorShape2KiB>::create(&config, input.clone()).is_err(),
+        "an existing job must not be overwritten"
+    );
+    assert_eq!(job.advance()?, SealingPhase::PreCommit2);
+    drop(job);
+
+    // Every phase continues from the state persisted by the previous one.
+    let mut job = SealingJob::<SectorShape2KiB>::load(&config, cache_dir.path())?;
+    assert_eq!(job.phase(), SealingPhase::PreCommit2);
+    assert_eq!(job.advance()?, SealingPhase::WaitSeed);
+    assert_eq!(job.advance()?, SealingPhase::WaitSeed);
+    let pre_commit = job
+        .pre_commit_output()
+        .expect("pre commit output missing")
+        .clone();
+    validate_cache_for_commit::<_, _, SectorShape2KiB>(
+        cache_dir.path(),
+        sealed_sector_file.path(),
     )?;
 
-    vanilla_proofs.push(single_proof);
+    // A job continues in the cache dir it is loaded from, after the cache dir was moved.
+    let moved_dir = tempdir()?;
+    let cache_path = moved_dir.path().join("cache");
+    rename(cache_dir.path(), &cache_path)?;
 
-    let proof = generate_winning_post_with_vanilla::<Tree>(
+    let mut job = SealingJob::<SectorShape2KiB>::load(&config, &cache_path)?;
+    assert_eq!(job.phase(), SealingPhase::WaitSeed);
+    let seed = rng.gen();
+    job.set_seed(seed)?;
+    assert_eq!(job.phase(), SealingPhase::Commit1);
+    assert_eq!(job.advance()?, SealingPhase::Commit2);
+    assert!(
+        job.set_seed(rng.gen()).is_err(),
+        "seed changed after commit"
+    );
+    job.set_seed(seed)?;
+    assert!(!cache_dir.path().exists());
+
+    let job = SealingJob::<SectorShape2KiB>::load(&config, &cache_path)?;
+    assert_eq!(job.phase(), SealingPhase::Commit2);
+    assert_eq!(
+        job.pre_commit_output().map(|out| out.comm_r),
+        Some(pre_commit.comm_r)
+    );
+    assert!(job.proof().is_none());
+
+    let other_config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_0_0, ApiVersion::V1_0_0);
+    let err = SealingJob::<SectorShape2KiB>::load(&other_config, &cache_path)
+        .expect_err("job loaded with a different porep config");
+    assert!(matches!(
+        err.downcast_ref::<ApiError>(),
+        Some(ApiError::InvalidConfig(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_seal_commit_phase1_output_encode_decode() -> Result<()> {
+    fil_logger::maybe_init();
+
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+    let sector_id: SectorId = rng.gen::<u64>().into();
+    let ticket = rng.gen();
+    let seed = rng.gen();
+
+    let config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let (mut piece_file, _piece_bytes) = generate_piece_file(sector_size)?;
+    let sealed_sector_file = NamedTempFile::new()?;
+    let cache_dir = tempdir()?;
+
+    let (piece_infos, phase1_output) = run_seal_pre_commit_phase1::<SectorShape2KiB>(
+        &config,
+        prover_id,
+        sector_id,
+        ticket,
+        &cache_dir,
+        &mut piece_file,
+        &sealed_sector_file,
+    )?;
+    let pre_commit_output = seal_pre_commit_phase2(
+        &config,
+        phase1_output,
+        cache_dir.path(),
+        sealed_sector_file.path(),
+    )?;
+    let output = seal_commit_phase1::<_, SectorShape2KiB>(
+        &config,
+        cache_dir.path(),
+        sealed_sector_file.path(),
+        prover_id,
+        sector_id,
+        ticket,
+        seed,
+        pre_commit_output,
+        &piece_infos,
+    )?;
+
+    let encoded = encode_seal_commit_phase1_output(&output)?;
+    let json = serde_json::to_vec(&output)?;
+    assert!(encoded.len() < json.len());
+
+    let decoded = decode_seal_commit_phase1_output::<SectorShape2KiB>(&encoded)?;
+    assert_eq!(serde_json::to_vec(&decoded)?, json);
+
+    for corrupted in [encoded[..encoded.len() - 1].to_vec(), {
+        let mut bytes = encoded.clone();
+        bytes[encoded.len() / 2] ^= 1;
+        bytes
+    }] {
+        let err = decode_seal_commit_phase1_output::<SectorShape2KiB>(&corrupted)
+            .expect_err("corrupted output decoded");
+        assert!(matches!(
+            err.downcast_ref::<ApiError>(),
+            Some(ApiError::CacheCorrupted(_))
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_seal_commit_phase1_output_golden_bytes() -> Result<()> {
+    let domain = |value: u64| -> DefaultTreeDomain { Fr::from(value).into() };
+    let piece_domain = |value: u64| -> DefaultPieceDomain { Fr::from(value).into() };
+    let inclusion_proof = MerkleProof::from_parts(domain(5), domain(6), vec![(vec![domain(7)], 1)]);
+
+    let proof = VanillaSealProof::<SectorShape2KiB> {
+        comm_d_proofs: MerkleProof::from_parts(
+            piece_domain(1),
+            piece_domain(2),
+            vec![(vec![piece_domain(3)], 0)],
+        ),
+        comm_r_last_proof: MerkleProof::from_parts(domain(4), domain(6), vec![(vec![], 2)]),
+        replica_column_proofs: ReplicaColumnProof {
+            c_x: ColumnProof::new(9, vec![domain(8)], inclusion_proof.clone()),
+            drg_parents: vec![ColumnProof::new(10, vec![], inclusion_proof)],
+            exp_parents: vec![],
+        },
+        labeling_proofs: vec![LabelingProof::new(1, 9, vec![domain(11)])],
+        encoding_proof: EncodingProof::new(2, 9, vec![]),
+    };
+    let output = SealCommitPhase1Output::<SectorShape2KiB> {
+        vanilla_proofs: vec![vec![proof]],
+        comm_r: [0x11; 32],
+        comm_d: [0x22; 32],
+        replica_id: domain(0x33),
+        seed: [0x44; 32],
+        ticket: [0x55; 32],
+    };
+
+    let encoded = encode_seal_commit_phase1_output(&output)?;
+    let expected = hex::decode(concat!(
+        // magic and version
+        "46494c43314f5554",
+        "01000000",
+        // comm_r, comm_d, replica_id, seed and ticket
+        "1111111111111111111111111111111111111111111111111111111111111111",
+        "2222222222222222222222222222222222222222222222222222222222222222",
+        "3300000000000000000000000000000000000000000000000000000000000000",
+        "4444444444444444444444444444444444444444444444444444444444444444",
+        "5555555555555555555555555555555555555555555555555555555555555555",
+        // one partition with one proof
+        "0100000000000000",
+        "0100000000000000",
+        // comm_d proof: leaf, root and a path of one element with one hash
+        "0100000000000000000000000000000000000000000000000000000000000000",
+        "0200000000000000000000000000000000000000000000000000000000000000",
+        "0100000000000000",
+        "0100000000000000",
+        "0300000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000",
+        // comm_r_last proof: leaf, root and a path of one element without hashes
+        "0400000000000000000000000000000000000000000000000000000000000000",
+        "0600000000000000000000000000000000000000000000000000000000000000",
+        "0100000000000000",
+        "0000000000000000",
+        "0200000000000000",
+        // c_x: column index, one row and its inclusion proof
+        "09000000",
+        "0100000000000000",
+        "0800000000000000000000000000000000000000000000000000000000000000",
+        "0500000000000000000000000000000000000000000000000000000000000000",
+        "0600000000000000000000000000000000000000000000000000000000000000",
+        "0100000000000000",
+        "0100000000000000",
+        "0700000000000000000000000000000000000000000000000000000000000000",
+        "0100000000000000",
+        // one drg parent: column index, no rows and its inclusion proof
+        "0100000000000000",
+        "0a000000",
+        "0000000000000000",
+        "0500000000000000000000000000000000000000000000000000000000000000",
+        "0600000000000000000000000000000000000000000000000000000000000000",
+        "0100000000000000",
+        "0100000000000000",
+        "0700000000000000000000000000000000000000000000000000000000000000",
+        "0100000000000000",
+        // no expander parents
+        "0000000000000000",
+        // one labeling proof: layer index, node and one parent
+        "0100000000000000",
+        "01000000",
+        "0900000000000000",
+        "0100000000000000",
+        "0b00000000000000000000000000000000000000000000000000000000000000",
+        // encoding proof: layer index, node and no parents
+        "02000000",
+        "0900000000000000",
+        "0000000000000000",
+        // checksum
+        "4f83eb69a204ce7c23def411bd5e404950b5f13565fac04be763f0d701a64094",
+    ))?;
+    assert_eq!(encoded, expected);
+
+    let decoded = decode_seal_commit_phase1_output::<SectorShape2KiB>(&encoded)?;
+    assert_eq!(serde_json::to_vec(&decoded)?, serde_json::to_vec(&output)?);
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_seal_commit_phase2_partitions_2kib_base_8() -> Result<()> {
+    fil_logger::maybe_init();
+
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+    let sector_id: SectorId = rng.gen::<u64>().into();
+    let ticket = rng.gen();
+    let seed = rng.gen();
+
+    let config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let (mut piece_file, _piece_bytes) = generate_piece_file(sector_size)?;
+    let sealed_sector_file = NamedTempFile::new()?;
+    let cache_dir = tempdir()?;
+
+    let (piece_infos, phase1_output) = run_seal_pre_commit_phase1::<SectorShape2KiB>(
+        &config,
+        prover_id,
+        sector_id,
+        ticket,
+        &cache_dir,
+        &mut piece_file,
+        &sealed_sector_file,
+    )?;
+    let pre_commit_output = seal_pre_commit_phase2(
+        &config,
+        phase1_output,
+        cache_dir.path(),
+        sealed_sector_file.path(),
+    )?;
+    let SealPreCommitOutput { comm_r, comm_d } = pre_commit_output;
+    let output = seal_commit_phase1::<_, SectorShape2KiB>(
+        &config,
+        cache_dir.path(),
+        sealed_sector_file.path(),
+        prover_id,
+        sector_id,
+        ticket,
+        seed,
+        pre_commit_output,
+        &piece_infos,
+    )?;
+
+    // Every partition is proven on its own, as it would be on separate machines.
+    let parts = (0..usize::from(config.partitions))
+        .rev()
+        .map(|k| seal_commit_phase2_partitions(&config, output.clone(), sector_id, &[k]))
+        .collect::<Result<Vec<_>>>()?;
+
+    assert!(
+        merge_seal_commit_partition_proofs::<SectorShape2KiB>(
+            &config,
+            parts[1..].to_vec(),
+            comm_r,
+            comm_d,
+            prover_id,
+            sector_id,
+            ticket,
+            seed,
+        )
+        .is_err(),
+        "merged a proof with a missing partition"
+    );
+
+    let SealCommitOutput { proof } = merge_seal_commit_partition_proofs::<SectorShape2KiB>(
+        &config, parts, comm_r, comm_d, prover_id, sector_id, ticket, seed,
+    )?;
+    assert!(verify_seal::<SectorShape2KiB>(
+        &config, comm_r, comm_d, prover_id, sector_id, ticket, seed, &proof,
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn test_unseal_range_with_key() -> Result<()> {
+    fil_logger::maybe_init();
+
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+
+    let config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+
+    for retention in [UnsealKeyRetention::LastLayer, UnsealKeyRetention::KeyFile] {
+        let (mut piece_file, piece_bytes) = generate_piece_file(sector_size)?;
+        let sealed_sector_file = NamedTempFile::new()?;
+        let cache_dir = tempdir()?;
+        let ticket = rng.gen();
+        let sector_id = rng.gen::<u64>().into();
+
+        let (_, phase1_output) = run_seal_pre_commit_phase1::<SectorShape2KiB>(
+            &config,
+            prover_id,
+            sector_id,
+            ticket,
+            &cache_dir,
+            &mut piece_file,
+            &sealed_sector_file,
+        )?;
+        seal_pre_commit_phase2(
+            &config,
+            phase1_output,
+            cache_dir.path(),
+            sealed_sector_file.path(),
+        )?;
+
+        let report = retain_unseal_key::<SectorShape2KiB>(&config, cache_dir.path(), retention)?;
+        assert_eq!(report.retention, retention);
+        assert_eq!(report.key_bytes, sector_size);
+        let expected_additional = match retention {
+            UnsealKeyRetention::LastLayer => 0,
+            UnsealKeyRetention::KeyFile => sector_size,
+        };
+        assert_eq!(report.additional_bytes, expected_additional);
+
+        // The key must survive clearing the layer data.
+        clear_layer_data::<SectorShape2KiB>(cache_dir.path())?;
+        assert!(get_layer_file_paths(&cache_dir).is_empty());
+        assert_eq!(metadata(&report.path)?.len(), sector_size);
+
+        let offset = 127;
+        let num_bytes = 508;
+        let mut unsealed = Vec::new();
+        let written = unseal_range_with_key(
+            &config,
+            cache_dir.path(),
+            File::open(sealed_sector_file.path())?,
+            &mut unsealed,
+            UnpaddedByteIndex(offset),
+            UnpaddedBytesAmount(num_bytes),
+        )?;
+        assert_eq!(written, UnpaddedBytesAmount(num_bytes));
+        assert_eq!(
+            &unsealed[..],
+            &piece_bytes[offset as usize..(offset + num_bytes) as usize],
+            "unsealed range does not match the piece"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_rebuild_sub_trees_4kib_sub_8_2() -> Result<()> {
+    fil_logger::maybe_init();
+
+    let sector_size = SECTOR_SIZE_4_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+
+    let (mut piece_file, _piece_bytes) = generate_piece_file(sector_size)?;
+    let sealed_sector_file = NamedTempFile::new()?;
+    let cache_dir = tempdir()?;
+
+    let config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let ticket = rng.gen();
+    let sector_id = rng.gen::<u64>().into();
+
+    let (_, phase1_output) = run_seal_pre_commit_phase1::<SectorShape4KiB>(
+        &config,
+        prover_id,
+        sector_id,
+        ticket,
+        &cache_dir,
+        &mut piece_file,
+        &sealed_sector_file,
+    )?;
+    let num_layers = phase1_output.labels.len();
+    seal_pre_commit_phase2(
+        &config,
+        phase1_output,
+        cache_dir.path(),
+        sealed_sector_file.path(),
+    )?;
+
+    // Lose one sub-tree of each tree.
+    let tree_c_path =
+        StoreConfig::data_path(cache_dir.path(), &format!("{}-0", CacheKey::CommCTree));
+    let tree_r_last_path =
+        StoreConfig::data_path(cache_dir.path(), &format!("{}-1", CacheKey::CommRLastTree));
+    let tree_c = std::fs::read(&tree_c_path)?;
+    let tree_r_last = std::fs::read(&tree_r_last_path)?;
+    remove_file(&tree_c_path)?;
+    remove_file(&tree_r_last_path)?;
+
+    rebuild_tree_c::<_, _, SectorShape4KiB>(
+        sector_size,
+        cache_dir.path(),
+        cache_dir.path(),
+        num_layers,
+        &[0],
+    )?;
+    rebuild_tree_r_last::<_, _, SectorShape4KiB>(
+        sector_size,
+        sealed_sector_file.path(),
+        cache_dir.path(),
+        &[1],
+    )?;
+    assert_eq!(std::fs::read(&tree_c_path)?, tree_c, "tree_c differs");
+    assert_eq!(
+        std::fs::read(&tree_r_last_path)?,
+        tree_r_last,
+        "tree_r_last differs"
+    );
+    assert!(verify_sector_cache(cache_dir.path())?.is_ok());
+
+    assert!(rebuild_tree_r_last::<_, _, SectorShape4KiB>(
+        sector_size,
+        sealed_sector_file.path(),
+        cache_dir.path(),
+        &[2],
+    )
+    .is_err());
+
+    // A rebuild from a damaged replica is rejected and leaves the existing sub-tree in place.
+    let mut replica = std::fs::read(sealed_sector_file.path())?;
+    let damaged = replica.len() - 1;
+    replica[damaged] ^= 0x01;
+    std::fs::write(sealed_sector_file.path(), replica)?;
+    let err = rebuild_tree_r_last::<_, _, SectorShape4KiB>(
+        sector_size,
+        sealed_sector_file.path(),
+        cache_dir.path(),
+        &[1],
+    )
+    .expect_err("rebuild from a damaged replica succeeded");
+    assert!(matches!(
+        err.downcast_ref::<ApiError>(),
+        Some(ApiError::CacheCorrupted(_))
+    ));
+    assert_eq!(
+        std::fs::read(&tree_r_last_path)?,
+        tree_r_last,
+        "tree_r_last was replaced"
+    );
+    assert!(read_dir(&cache_dir)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<io::Result<Vec<_>>>()?
+        .iter()
+        .all(|name| !name.to_string_lossy().contains("rebuild")));
+
+    Ok(())
+}
+
+#[test]
+fn test_api_errors_are_classified() -> Result<()> {
+    let err = PieceInfo::new([0; 32], UnpaddedBytesAmount(127))
+        .expect_err("all zero piece commitment accepted");
+    assert!(matches!(
+        err.downcast_ref::<ApiError>(),
+        Some(ApiError::InvalidCommitment(_))
+    ));
+
+    let cache_dir = tempdir()?;
+    let mut replica_file = NamedTempFile::new()?;
+    replica_file.write_all(&[1u8; 128])?;
+    let err =
+        validate_cache_for_commit::<_, _, SectorShape2KiB>(cache_dir.path(), replica_file.path())
+            .expect_err("empty cache validated");
+    assert!(matches!(
+        err.downcast_ref::<ApiError>(),
+        Some(ApiError::CacheCorrupted(_))
+    ));
+
+    let config = PoStConfig {
+        sector_size: SECTOR_SIZE_2_KIB.into(),
+        sector_count: WINNING_POST_SECTOR_COUNT,
+        challenge_count: WINNING_POST_CHALLENGE_COUNT,
+        typ: PoStType::Winning,
+        priority: false,
+        api_version: ApiVersion::V1_1_0,
+    };
+    let replicas = BTreeMap::<SectorId, PrivateReplicaInfo<SectorShape2KiB>>::new();
+    let err = generate_window_post(&config, &[1u8; 32], &replicas, [0u8; 32])
+        .expect_err("window post generated with a winning post config");
+    assert!(matches!(
+        err.downcast_ref::<ApiError>(),
+        Some(ApiError::InvalidConfig(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_winning_post_2kib_base_8() -> Result<()> {
+    winning_post::<SectorShape2KiB>(SECTOR_SIZE_2_KIB, false, ApiVersion::V1_0_0)?;
+    winning_post::<SectorShape2KiB>(SECTOR_SIZE_2_KIB, true, ApiVersion::V1_0_0)?;
+    winning_post::<SectorShape2KiB>(SECTOR_SIZE_2_KIB, false, ApiVersion::V1_1_0)?;
+    winning_post::<SectorShape2KiB>(SECTOR_SIZE_2_KIB, true, ApiVersion::V1_1_0)
+}
+
+#[test]
+#[ignore]
+fn test_winning_post_4kib_sub_8_2() -> Result<()> {
+    winning_post::<SectorShape4KiB>(SECTOR_SIZE_4_KIB, false, ApiVersion::V1_0_0)?;
+    winning_post::<SectorShape4KiB>(SECTOR_SIZE_4_KIB, true, ApiVersion::V1_0_0)?;
+    winning_post::<SectorShape4KiB>(SECTOR_SIZE_4_KIB, false, ApiVersion::V1_1_0)?;
+    winning_post::<SectorShape4KiB>(SECTOR_SIZE_4_KIB, true, ApiVersion::V1_1_0)
+}
+
+#[test]
+#[ignore]
+fn test_winning_post_16kib_sub_8_8() -> Result<()> {
+    winning_post::<SectorShape16KiB>(SECTOR_SIZE_16_KIB, false, ApiVersion::V1_0_0)?;
+    winning_post::<SectorShape16KiB>(SECTOR_SIZE_16_KIB, true, ApiVersion::V1_0_0)?;
+    winning_post::<SectorShape16KiB>(SECTOR_SIZE_16_KIB, false, ApiVersion::V1_1_0)?;
+    winning_post::<SectorShape16KiB>(SECTOR_SIZE_16_KIB, true, ApiVersion::V1_1_0)
+}
+
+#[test]
+#[ignore]
+fn test_winning_post_32kib_top_8_8_2() -> Result<()> {
+    winning_post::<SectorShape32KiB>(SECTOR_SIZE_32_KIB, false, ApiVersion::V1_0_0)?;
+    winning_post::<SectorShape32KiB>(SECTOR_SIZE_32_KIB, true, ApiVersion::V1_0_0)?;
+    winning_post::<SectorShape32KiB>(SECTOR_SIZE_32_KIB, false, ApiVersion::V1_1_0)?;
+    winning_post::<SectorShape32KiB>(SECTOR_SIZE_32_KIB, true, ApiVersion::V1_1_0)
+}
+
+#[test]
+fn test_winning_post_empty_sector_challenge() -> Result<()> {
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+
+    let sector_count = 0;
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let porep_id = ARBITRARY_POREP_ID_V1_1_0;
+    let api_version = ApiVersion::V1_1_0;
+
+    let porep_config = PoRepConfig::new_groth16(sector_size, porep_id, api_version);
+    let (_, replica, _, _) =
+        create_seal::<_, SectorShape2KiB>(&porep_config, &mut rng, prover_id, true)?;
+
+    let random_fr: DefaultTreeDomain = Fr::random(rng).into();
+    let mut randomness = [0u8; 32];
+    randomness.copy_from_slice(AsRef::<[u8]>::as_ref(&random_fr));
+
+    let config = PoStConfig {
+        sector_size: sector_size.into(),
+        sector_count,
+        challenge_count: WINNING_POST_CHALLENGE_COUNT,
+        typ: PoStType::Winning,
+        priority: false,
+        api_version,
+    };
+
+    assert!(generate_winning_post_sector_challenge::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        sector_count as u64,
+        prover_id
+    )
+    .is_err());
+
+    replica.close()?;
+
+    Ok(())
+}
+
+fn winning_post<Tree: 'static + MerkleTreeTrait>(
+    sector_size: u64,
+    fake: bool,
+    api_version: ApiVersion,
+) -> Result<()> {
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+
+    let porep_id = match api_version {
+        ApiVersion::V1_0_0 => ARBITRARY_POREP_ID_V1_0_0,
+        ApiVersion::V1_1_0 => ARBITRARY_POREP_ID_V1_1_0,
+        ApiVersion::V1_2_0 => ARBITRARY_POREP_ID_V1_2_0,
+    };
+
+    let porep_config = PoRepConfig::new_groth16(sector_size, porep_id, api_version);
+    let (sector_id, replica, comm_r, cache_dir) = if fake {
+        create_fake_seal::<_, Tree>(&mut rng, sector_size, &porep_id, api_version)?
+    } else {
+        create_seal::<_, Tree>(&porep_config, &mut rng, prover_id, true)?
+    };
+    let sector_count = WINNING_POST_SECTOR_COUNT;
+
+    let random_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut randomness = [0u8; 32];
+    randomness.copy_from_slice(AsRef::<[u8]>::as_ref(&random_fr));
+
+    let config = PoStConfig {
+        sector_size: sector_size.into(),
+        sector_count,
+        challenge_count: WINNING_POST_CHALLENGE_COUNT,
+        typ: PoStType::Winning,
+        priority: false,
+        api_version,
+    };
+
+    let challenged_sectors = generate_winning_post_sector_challenge::<Tree>(
+        &config,
+        &randomness,
+        sector_count as u64,
+        prover_id,
+    )?;
+    assert_eq!(challenged_sectors.len(), sector_count);
+    assert_eq!(challenged_sectors[0], 0); // with a sector_count of 1, the only valid index is 0
+
+    let pub_replicas = vec![(sector_id, PublicReplicaInfo::new(comm_r)?)];
+    let private_replica_info =
+        PrivateReplicaInfo::new(replica.path().into(), comm_r, cache_dir.path().into())?;
+
+    /////////////////////////////////////////////
+    // The following methods of proof generation are functionally equivalent:
+    // 1)
+    //
+    let priv_replicas = vec![(sector_id, private_replica_info.clone())];
+    let proof = generate_winning_post::<Tree>(&config, &randomness, &priv_replicas[..], prover_id)?;
+
+    let valid =
+        verify_winning_post::<Tree>(&config, &randomness, &pub_replicas[..], prover_id, &proof)?;
+    assert!(valid, "proof did not verify");
+
+    //
+    // 2)
+    let mut vanilla_proofs = Vec::with_capacity(sector_count);
+    let challenges =
+        generate_fallback_sector_challenges::<Tree>(&config, &randomness, &[sector_id], prover_id)?;
+
+    // Make sure that files can be read-only for a window post.
+    set_readonly_flag(replica.path(), true);
+    set_readonly_flag(cache_dir.path(), true);
+
+    let single_proof = generate_single_vanilla_proof::<Tree>(
+        &config,
+        sector_id,
+        &private_replica_info,
+        &challenges[&sector_id],
+    )?;
+
+    vanilla_proofs.push(single_proof);
+
+    let proof = generate_winning_post_with_vanilla::<Tree>(
+        &config,
+        &randomness,
+        prover_id,
+        vanilla_proofs,
+    )?;
+    /////////////////////////////////////////////
+
+    let valid =
+        verify_winning_post::<Tree>(&config, &randomness, &pub_replicas[..], prover_id, &proof)?;
+    assert!(valid, "proof did not verify");
+
+    //
+    // 3)
+    let (proof, timings) = generate_winning_post_with_timings::<Tree>(
+        &config,
+        &randomness,
+        &priv_replicas[..],
+        prover_id,
+        None,
+    )?;
+    /////////////////////////////////////////////
+
+    let valid =
+        verify_winning_post::<Tree>(&config, &randomness, &pub_replicas[..], prover_id, &proof)?;
+    assert!(valid, "proof did not verify");
+    assert_eq!(timings.sectors.len(), sector_count);
+    assert_eq!(timings.sectors[0].sector_id, sector_id);
+    assert!(timings.synthesis > Duration::ZERO);
+    assert!(timings.proving > Duration::ZERO);
+    assert!(timings.total >= timings.challenge_derivation + timings.synthesis + timings.proving);
+
+    // Make files writeable again, so that the temporary directory can be removed.
+    set_readonly_flag(replica.path(), false);
+    set_readonly_flag(cache_dir.path(), false);
+
+    replica.close()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_winning_post_deadline_exceeded_2kib_base_8() -> Result<()> {
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let (sector_id, replica, comm_r, cache_dir) = create_fake_seal::<_, SectorShape2KiB>(
+        &mut rng,
+        SECTOR_SIZE_2_KIB,
+        &ARBITRARY_POREP_ID_V1_1_0,
+        ApiVersion::V1_1_0,
+    )?;
+    let replicas = vec![(
+        sector_id,
+        PrivateReplicaInfo::new(replica.path().into(), comm_r, cache_dir.path().into())?,
+    )];
+    let config = PoStConfig {
+        sector_size: SECTOR_SIZE_2_KIB.into(),
+        sector_count: WINNING_POST_SECTOR_COUNT,
+        challenge_count: WINNING_POST_CHALLENGE_COUNT,
+        typ: PoStType::Winning,
+        priority: false,
+        api_version: ApiVersion::V1_1_0,
+    };
+
+    let err = generate_winning_post_with_timings::<SectorShape2KiB>(
+        &config,
+        &[1u8; 32],
+        &replicas,
+        [0u8; 32],
+        Some(Instant::now()),
+    )
+    .expect_err("winning post generated after its deadline");
+    assert!(matches!(
+        err.downcast_ref::<ApiError>(),
+        Some(ApiError::DeadlineExceeded(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_single_partition_smaller_2kib_base_8() -> Result<()> {
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let sector_count = *WINDOW_POST_SECTOR_COUNT
+        .read()
+        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
+        .get(&sector_size)
+        .expect("unknown sector size");
+
+    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
+    for version in versions {
+        window_post::<SectorShape2KiB>(
+            sector_size,
+            sector_count / 2,
+            sector_count,
+            false,
+            version,
+        )?;
+        window_post::<SectorShape2KiB>(sector_size, sector_count / 2, sector_count, true, version)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_two_partitions_matching_2kib_base_8() -> Result<()> {
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let sector_count = *WINDOW_POST_SECTOR_COUNT
+        .read()
+        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
+        .get(&sector_size)
+        .expect("unknown sector size");
+
+    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
+    for version in versions {
+        window_post::<SectorShape2KiB>(
+            sector_size,
+            2 * sector_count,
+            sector_count,
+            false,
+            version,
+        )?;
+        window_post::<SectorShape2KiB>(sector_size, 2 * sector_count, sector_count, true, version)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_two_partitions_matching_4kib_sub_8_2() -> Result<()> {
+    let sector_size = SECTOR_SIZE_4_KIB;
+    let sector_count = *WINDOW_POST_SECTOR_COUNT
+        .read()
+        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
+        .get(&sector_size)
+        .expect("unknown sector size");
+
+    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
+    for version in versions {
+        window_post::<SectorShape4KiB>(
+            sector_size,
+            2 * sector_count,
+            sector_count,
+            false,
+            version,
+        )?;
+        window_post::<SectorShape4KiB>(sector_size, 2 * sector_count, sector_count, true, version)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_two_partitions_matching_16kib_sub_8_8() -> Result<()> {
+    let sector_size = SECTOR_SIZE_16_KIB;
+    let sector_count = *WINDOW_POST_SECTOR_COUNT
+        .read()
+        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
+        .get(&sector_size)
+        .expect("unknown sector size");
+
+    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
+    for version in versions {
+        window_post::<SectorShape16KiB>(
+            sector_size,
+            2 * sector_count,
+            sector_count,
+            false,
+            version,
+        )?;
+        window_post::<SectorShape16KiB>(
+            sector_size,
+            2 * sector_count,
+            sector_count,
+            true,
+            version,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_two_partitions_matching_32kib_top_8_8_2() -> Result<()> {
+    let sector_size = SECTOR_SIZE_32_KIB;
+    let sector_count = *WINDOW_POST_SECTOR_COUNT
+        .read()
+        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
+        .get(&sector_size)
+        .expect("unknown sector size");
+
+    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
+    for version in versions {
+        window_post::<SectorShape32KiB>(
+            sector_size,
+            2 * sector_count,
+            sector_count,
+            false,
+            version,
+        )?;
+        window_post::<SectorShape32KiB>(
+            sector_size,
+            2 * sector_count,
+            sector_count,
+            true,
+            version,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_two_partitions_smaller_2kib_base_8() -> Result<()> {
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let sector_count = *WINDOW_POST_SECTOR_COUNT
+        .read()
+        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
+        .get(&sector_size)
+        .expect("unknown sector size");
+
+    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
+    for version in versions {
+        window_post::<SectorShape2KiB>(
+            sector_size,
+            2 * sector_count - 1,
+            sector_count,
+            false,
+            version,
+        )?;
+        window_post::<SectorShape2KiB>(
+            sector_size,
+            2 * sector_count - 1,
+            sector_count,
+            true,
+            version,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_single_partition_matching_2kib_base_8() -> Result<()> {
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let sector_count = *WINDOW_POST_SECTOR_COUNT
+        .read()
+        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
+        .get(&sector_size)
+        .expect("unknown sector size");
+
+    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
+    for version in versions {
+        window_post::<SectorShape2KiB>(sector_size, sector_count, sector_count, false, version)?;
+        window_post::<SectorShape2KiB>(sector_size, sector_count, sector_count, true, version)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_skip_faulty_2kib_base_8() -> Result<()> {
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+
+    let porep_config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let mut sectors = Vec::new();
+    let mut priv_replicas = BTreeMap::new();
+    let mut pub_replicas = BTreeMap::new();
+    for i in 0..3 {
+        let (sector_id, replica, comm_r, cache_dir) =
+            create_seal::<_, SectorShape2KiB>(&porep_config, &mut rng, prover_id, true)?;
+        priv_replicas.insert(
+            sector_id,
+            PrivateReplicaInfo::<SectorShape2KiB>::new(
+                replica.path().into(),
+                comm_r,
+                cache_dir.path().into(),
+            )?,
+        );
+        // The replica of the second sector is truncated, so that none of its challenges can be
+        // proven.
+        if i == 1 {
+            replica.as_file().set_len(1)?;
+        } else {
+            pub_replicas.insert(sector_id, PublicReplicaInfo::new(comm_r)?);
+        }
+        sectors.push((sector_id, replica, cache_dir));
+    }
+
+    let random_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut randomness = [0u8; 32];
+    randomness.copy_from_slice(AsRef::<[u8]>::as_ref(&random_fr));
+    let config = PoStConfig {
+        sector_size: sector_size.into(),
+        sector_count: 2,
+        challenge_count: WINDOW_POST_CHALLENGE_COUNT,
+        typ: PoStType::Window,
+        priority: false,
+        api_version: ApiVersion::V1_1_0,
+    };
+
+    let output = generate_window_post_skip_faulty::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        &priv_replicas,
+        prover_id,
+    )?;
+    assert_eq!(output.skipped, vec![sectors[1].0]);
+
+    let valid = verify_window_post::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        &pub_replicas,
+        prover_id,
+        &output.proof,
+    )?;
+    assert!(valid, "proof did not verify");
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_streaming_2kib_base_8() -> Result<()> {
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+
+    let porep_config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let mut sectors = Vec::new();
+    let mut priv_replicas = BTreeMap::new();
+    let mut pub_replicas = BTreeMap::new();
+    for _ in 0..5 {
+        let (sector_id, replica, comm_r, cache_dir) =
+            create_seal::<_, SectorShape2KiB>(&porep_config, &mut rng, prover_id, true)?;
+        priv_replicas.insert(
+            sector_id,
+            PrivateReplicaInfo::<SectorShape2KiB>::new(
+                replica.path().into(),
+                comm_r,
+                cache_dir.path().into(),
+            )?,
+        );
+        pub_replicas.insert(sector_id, PublicReplicaInfo::new(comm_r)?);
+        sectors.push((replica, cache_dir));
+    }
+
+    let random_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut randomness = [0u8; 32];
+    randomness.copy_from_slice(AsRef::<[u8]>::as_ref(&random_fr));
+    // Three partitions, of which at most two are proven at once.
+    let config = PoStConfig {
+        sector_size: sector_size.into(),
+        sector_count: 2,
+        challenge_count: WINDOW_POST_CHALLENGE_COUNT,
+        typ: PoStType::Window,
+        priority: false,
+        api_version: ApiVersion::V1_1_0,
+    };
+
+    let proof = generate_window_post_streaming::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        &priv_replicas,
+        prover_id,
+        2,
+    )?;
+    let valid = verify_window_post::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        &pub_replicas,
+        prover_id,
+        &proof,
+    )?;
+    assert!(valid, "proof did not verify");
+
+    Ok(())
+}
+
+#[test]
+fn test_window_post_streaming_faulty_2kib_base_8() -> Result<()> {
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+
+    let porep_config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let mut sectors = Vec::new();
+    let mut priv_replicas = BTreeMap::new();
+    for _ in 0..3 {
+        let (sector_id, replica, comm_r, cache_dir) =
+            create_seal::<_, SectorShape2KiB>(&porep_config, &mut rng, prover_id, true)?;
+        priv_replicas.insert(
+            sector_id,
+            PrivateReplicaInfo::<SectorShape2KiB>::new(
+                replica.path().into(),
+                comm_r,
+                cache_dir.path().into(),
+            )?,
+        );
+        sectors.push((sector_id, replica, cache_dir));
+    }
+    // The replica of the first sector of the first partition is truncated.
+    let faulty_sector = *priv_replicas.keys().next().expect("no sectors");
+    for (sector_id, replica, _) in &sectors {
+        if *sector_id == faulty_sector {
+            replica.as_file().set_len(1)?;
+        }
+    }
+
+    let config = PoStConfig {
+        sector_size: sector_size.into(),
+        sector_count: 2,
+        challenge_count: WINDOW_POST_CHALLENGE_COUNT,
+        typ: PoStType::Window,
+        priority: false,
+        api_version: ApiVersion::V1_1_0,
+    };
+
+    let err = generate_window_post_streaming::<SectorShape2KiB>(
+        &config,
+        &[1u8; 32],
+        &priv_replicas,
+        prover_id,
+        0,
+    )
+    .expect_err("window post generated without any concurrent partitions");
+    assert!(matches!(
+        err.downcast_ref::<ApiError>(),
+        Some(ApiError::InvalidConfig(_))
+    ));
+
+    let err = generate_window_post_streaming::<SectorShape2KiB>(
+        &config,
+        &[1u8; 32],
+        &priv_replicas,
+        prover_id,
+        1,
+    )
+    .expect_err("window post generated with a faulty sector");
+    match err.downcast_ref::<ApiError>() {
+        Some(ApiError::FaultySectors(faulty)) => assert_eq!(faulty, &vec![faulty_sector]),
+        other => panic!("unexpected error {:?}: {:#}", other, err),
+    }
+
+    // With the second partition faulty as well and both proven at once, the error is still the
+    // one of the first partition.
+    let last_sector = *priv_replicas.keys().last().expect("no sectors");
+    for (sector_id, replica, _) in &sectors {
+        if *sector_id == last_sector {
+            replica.as_file().set_len(1)?;
+        }
+    }
+    let err = generate_window_post_streaming::<SectorShape2KiB>(
+        &config,
+        &[1u8; 32],
+        &priv_replicas,
+        prover_id,
+        2,
+    )
+    .expect_err("window post generated with faulty sectors");
+    match err.downcast_ref::<ApiError>() {
+        Some(ApiError::FaultySectors(faulty)) => assert_eq!(faulty, &vec![faulty_sector]),
+        other => panic!("unexpected error {:?}: {:#}", other, err),
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_window_post_diagnostics_2kib_base_8() -> Result<()> {
+    let sector_size = SECTOR_SIZE_2_KIB;
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
+
+    let porep_config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let mut sectors = Vec::new();
+    let mut priv_replicas = BTreeMap::new();
+    let mut pub_replicas = BTreeMap::new();
+    for _ in 0..3 {
+        let (sector_id, replica, comm_r, cache_dir) =
+            create_seal::<_, SectorShape2KiB>(&porep_config, &mut rng, prover_id, true)?;
+        priv_replicas.insert(
+            sector_id,
+            PrivateReplicaInfo::<SectorShape2KiB>::new(
+                replica.path().into(),
+                comm_r,
+                cache_dir.path().into(),
+            )?,
+        );
+        pub_replicas.insert(sector_id, PublicReplicaInfo::new(comm_r)?);
+        sectors.push((replica, cache_dir));
+    }
+
+    let random_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut randomness = [0u8; 32];
+    randomness.copy_from_slice(AsRef::<[u8]>::as_ref(&random_fr));
+    // Two sectors per partition, so that the third sector is in the second partition.
+    let config = PoStConfig {
+        sector_size: sector_size.into(),
+        sector_count: 2,
+        challenge_count: WINDOW_POST_CHALLENGE_COUNT,
+        typ: PoStType::Window,
+        priority: false,
+        api_version: ApiVersion::V1_1_0,
+    };
+
+    let sector_ids: Vec<_> = priv_replicas.keys().copied().collect();
+    let challenges = generate_fallback_sector_challenges::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        &sector_ids,
+        prover_id,
+    )?;
+    let generate_vanilla_proofs = || {
+        priv_replicas
+            .iter()
+            .map(|(sector_id, replica)| {
+                generate_single_vanilla_proof::<SectorShape2KiB>(
+                    &config,
+                    *sector_id,
+                    replica,
+                    &challenges[sector_id],
+                )
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+    let vanilla_proofs = generate_vanilla_proofs()?;
+    let proof = generate_window_post_with_vanilla::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        prover_id,
+        generate_vanilla_proofs()?,
+    )?;
+
+    let report = verify_window_post_with_diagnostics::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        &pub_replicas,
+        prover_id,
+        &proof,
+        Some(&vanilla_proofs),
+    )?;
+    assert_eq!(report.partitions, 2);
+    assert!(
+        report.is_valid(),
+        "unexpected failures {:?}",
+        report.failures
+    );
+
+    // A wrong comm_r for the third sector only breaks the second partition.
+    let mut wrong_replicas = pub_replicas.clone();
+    wrong_replicas.insert(sector_ids[2], pub_replicas[&sector_ids[0]].clone());
+    let report = verify_window_post_with_diagnostics::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        &wrong_replicas,
+        prover_id,
+        &proof,
+        Some(&vanilla_proofs),
+    )?;
+    assert_eq!(report.failed_partitions(), vec![1]);
+    assert!(report
+        .failures
+        .contains(&WindowPoStFailure::SnarkProof { partition: 1 }));
+    assert!(report.failures.contains(&WindowPoStFailure::CommRMismatch {
+        partition: 1,
+        sector_id: sector_ids[2],
+    }));
+
+    // Swapping two inclusion proofs of the first sector makes both of them invalid for their
+    // challenges.
+    let mut tampered_proofs = vanilla_proofs;
+    tampered_proofs[0].vanilla_proof.sectors[0]
+        .inclusion_proofs
+        .swap(0, 1);
+    let report = verify_window_post_with_diagnostics::<SectorShape2KiB>(
         &config,
         &randomness,
+        &pub_replicas,
         prover_id,
-        vanilla_proofs,
+        &proof,
+        Some(&tampered_proofs),
     )?;
-    /////////////////////////////////////////////
-
-    let valid =
-        verify_winning_post::<Tree>(&config, &randomness, &pub_replicas[..], prover_id, &proof)?;
-    assert!(valid, "proof did not verify");
-
-    // Make files writeable again, so that the temporary directory can be removed.
-    set_readonly_flag(replica.path(), false);
-    set_readonly_flag(cache_dir.path(), false);
-
-    replica.close()?;
+    assert_eq!(report.failed_partitions(), vec![0]);
+    let failed_challenges: Vec<_> = report
+        .failures
+        .iter()
+        .map(|failure| match failure {
+            WindowPoStFailure::InclusionProof {
+                sector_id,
+                challenge_index,
+                ..
+            } => (*sector_id, *challenge_index),
+            other => panic!("unexpected failure {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        failed_challenges,
+        vec![(sector_ids[0], 0), (sector_ids[0], 1)]
+    );
 
     Ok(())
 }
 
 #[test]
-#[ignore]
-fn test_window_post_single_partition_smaller_2kib_base_8() -> Result<()> {
+fn test_window_post_partition_matching_2kib_base_8() -> Result<()> {
     let sector_size = SECTOR_SIZE_2_KIB;
     let sector_count = *WINDOW_POST_SECTOR_COUNT
         .read()
@@ -1013,201 +2457,222 @@ fn test_window_post_single_partition_smaller_2kib_base_8() -> Result<()> {
 
     let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
     for version in versions {
-        window_post::<SectorShape2KiB>(
+        partition_window_post::<SectorShape2KiB>(
             sector_size,
-            sector_count / 2,
+            3, // Validate the scenarios of two partition
             sector_count,
             false,
             version,
         )?;
-        window_post::<SectorShape2KiB>(sector_size, sector_count / 2, sector_count, true, version)?;
+        partition_window_post::<SectorShape2KiB>(sector_size, 3, sector_count, true, version)?;
     }
 
     Ok(())
 }
 
 #[test]
-#[ignore]
-fn test_window_post_two_partitions_matching_2kib_base_8() -> Result<()> {
-    let sector_size = SECTOR_SIZE_2_KIB;
-    let sector_count = *WINDOW_POST_SECTOR_COUNT
-        .read()
-        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
-        .get(&sector_size)
-        .expect("unknown sector size");
-
-    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
-    for version in versions {
-        window_post::<SectorShape2KiB>(
-            sector_size,
-            2 * sector_count,
-            sector_count,
-            false,
-            version,
-        )?;
-        window_post::<SectorShape2KiB>(sector_size, 2 * sector_count, sector_count, true, version)?;
-    }
-
-    Ok(())
+fn test_vanilla_proof_with_store_2kib_base_8() -> Result<()> {
+    vanilla_proof_with_store::<SectorShape2KiB>(SECTOR_SIZE_2_KIB)
 }
 
 #[test]
-#[ignore]
-fn test_window_post_two_partitions_matching_4kib_sub_8_2() -> Result<()> {
-    let sector_size = SECTOR_SIZE_4_KIB;
-    let sector_count = *WINDOW_POST_SECTOR_COUNT
-        .read()
-        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
-        .get(&sector_size)
-        .expect("unknown sector size");
-
-    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
-    for version in versions {
-        window_post::<SectorShape4KiB>(
-            sector_size,
-            2 * sector_count,
-            sector_count,
-            false,
-            version,
-        )?;
-        window_post::<SectorShape4KiB>(sector_size, 2 * sector_count, sector_count, true, version)?;
-    }
-
-    Ok(())
+fn test_vanilla_proof_with_store_4kib_sub_8_2() -> Result<()> {
+    vanilla_proof_with_store::<SectorShape4KiB>(SECTOR_SIZE_4_KIB)
 }
 
 #[test]
-#[ignore]
-fn test_window_post_two_partitions_matching_16kib_sub_8_8() -> Result<()> {
-    let sector_size = SECTOR_SIZE_16_KIB;
-    let sector_count = *WINDOW_POST_SECTOR_COUNT
-        .read()
-        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
-        .get(&sector_size)
-        .expect("unknown sector size");
+fn test_vanilla_proof_with_store_32kib_top_8_8_2() -> Result<()> {
+    vanilla_proof_with_store::<SectorShape32KiB>(SECTOR_SIZE_32_KIB)
+}
 
-    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
-    for version in versions {
-        window_post::<SectorShape16KiB>(
-            sector_size,
-            2 * sector_count,
-            sector_count,
-            false,
-            version,
-        )?;
-        window_post::<SectorShape16KiB>(
-            sector_size,
-            2 * sector_count,
-            sector_count,
-            true,
-            version,
-        )?;
-    }
+fn vanilla_proof_with_store<Tree: 'static + MerkleTreeTrait>(sector_size: u64) -> Result<()> {
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
 
-    Ok(())
-}
+    let porep_config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let (sector_id, replica, comm_r, cache_dir) =
+        create_seal::<_, Tree>(&porep_config, &mut rng, prover_id, true)?;
+    let priv_replica =
+        PrivateReplicaInfo::<Tree>::new(replica.path().into(), comm_r, cache_dir.path().into())?;
 
-#[test]
-#[ignore]
-fn test_window_post_two_partitions_matching_32kib_top_8_8_2() -> Result<()> {
-    let sector_size = SECTOR_SIZE_32_KIB;
-    let sector_count = *WINDOW_POST_SECTOR_COUNT
-        .read()
-        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
-        .get(&sector_size)
-        .expect("unknown sector size");
+    let random_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut randomness = [0u8; 32];
+    randomness.copy_from_slice(AsRef::<[u8]>::as_ref(&random_fr));
+    let config = PoStConfig {
+        sector_size: sector_size.into(),
+        sector_count: 1,
+        challenge_count: WINDOW_POST_CHALLENGE_COUNT,
+        typ: PoStType::Window,
+        priority: false,
+        api_version: ApiVersion::V1_1_0,
+    };
+    let challenges =
+        generate_fallback_sector_challenges::<Tree>(&config, &randomness, &[sector_id], prover_id)?;
 
-    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
-    for version in versions {
-        window_post::<SectorShape32KiB>(
-            sector_size,
-            2 * sector_count,
-            sector_count,
-            false,
-            version,
-        )?;
-        window_post::<SectorShape32KiB>(
-            sector_size,
-            2 * sector_count,
-            sector_count,
-            true,
-            version,
-        )?;
-    }
+    // The proof read through the store matches the one generated from the merkle tree.
+    let tree = priv_replica.merkle_tree(config.sector_size)?;
+    let priv_sectors = vec![fallback::PrivateSector {
+        tree: &tree,
+        comm_c: priv_replica.safe_comm_c(),
+        comm_r_last: priv_replica.safe_comm_r_last(),
+    }];
+    let expected = FallbackPoStSectorProof::<Tree> {
+        sector_id,
+        comm_r: priv_replica.safe_comm_r()?,
+        vanilla_proof: fallback::vanilla_proof(
+            sector_id,
+            &fallback::PrivateInputs::<Tree> {
+                sectors: &priv_sectors,
+            },
+            &challenges[&sector_id],
+        )?,
+    };
+    let local = generate_single_vanilla_proof::<Tree>(
+        &config,
+        sector_id,
+        &priv_replica,
+        &challenges[&sector_id],
+    )?;
+    assert_eq!(serde_json::to_vec(&local)?, serde_json::to_vec(&expected)?);
+    let store = LocalReplicaStore::new::<Tree>(replica.path(), cache_dir.path())?;
+    let proof = generate_single_vanilla_proof_with_store::<Tree, _>(
+        &config,
+        sector_id,
+        comm_r,
+        &store,
+        &challenges[&sector_id],
+    )?;
+    assert_eq!(serde_json::to_vec(&proof)?, serde_json::to_vec(&expected)?);
+
+    // A replica which does not match tree_r_last is detected.
+    let mut data = std::fs::read(replica.path())?;
+    data[0] ^= 1;
+    let corrupted_replica = NamedTempFile::new()?;
+    std::fs::write(corrupted_replica.path(), &data)?;
+    let store = LocalReplicaStore::new::<Tree>(corrupted_replica.path(), cache_dir.path())?;
+    let challenges = [0];
+    let err = generate_single_vanilla_proof_with_store::<Tree, _>(
+        &config,
+        sector_id,
+        comm_r,
+        &store,
+        &challenges,
+    )
+    .expect_err("vanilla proof generated from a corrupted replica");
+    assert!(matches!(
+        err.downcast_ref::<ApiError>(),
+        Some(ApiError::FaultySectors(faulty)) if faulty == &vec![sector_id]
+    ));
+
+    // A comm_r which does not match p_aux is detected before the replica is read.
+    let mut wrong_comm_r = comm_r;
+    wrong_comm_r[0] ^= 1;
+    let err = generate_single_vanilla_proof_with_store::<Tree, _>(
+        &config,
+        sector_id,
+        wrong_comm_r,
+        &store,
+        &challenges,
+    )
+    .expect_err("vanilla proof generated for the wrong comm_r");
+    assert!(matches!(
+        err.downcast_ref::<ApiError>(),
+        Some(ApiError::FaultySectors(_))
+    ));
 
     Ok(())
 }
 
 #[test]
-#[ignore]
-fn test_window_post_two_partitions_smaller_2kib_base_8() -> Result<()> {
+fn test_check_sectors_for_post_2kib_base_8() -> Result<()> {
     let sector_size = SECTOR_SIZE_2_KIB;
-    let sector_count = *WINDOW_POST_SECTOR_COUNT
-        .read()
-        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
-        .get(&sector_size)
-        .expect("unknown sector size");
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let prover_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut prover_id = [0u8; 32];
+    prover_id.copy_from_slice(AsRef::<[u8]>::as_ref(&prover_fr));
 
-    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
-    for version in versions {
-        window_post::<SectorShape2KiB>(
-            sector_size,
-            2 * sector_count - 1,
-            sector_count,
-            false,
-            version,
-        )?;
-        window_post::<SectorShape2KiB>(
-            sector_size,
-            2 * sector_count - 1,
-            sector_count,
+    let porep_config = porep_config(sector_size, ARBITRARY_POREP_ID_V1_1_0, ApiVersion::V1_1_0);
+    let mut sectors = Vec::new();
+    for _ in 0..4 {
+        sectors.push(create_seal::<_, SectorShape2KiB>(
+            &porep_config,
+            &mut rng,
+            prover_id,
             true,
-            version,
-        )?;
+        )?);
     }
 
-    Ok(())
-}
-
-#[test]
-#[ignore]
-fn test_window_post_single_partition_matching_2kib_base_8() -> Result<()> {
-    let sector_size = SECTOR_SIZE_2_KIB;
-    let sector_count = *WINDOW_POST_SECTOR_COUNT
-        .read()
-        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
-        .get(&sector_size)
-        .expect("unknown sector size");
-
-    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
-    for version in versions {
-        window_post::<SectorShape2KiB>(sector_size, sector_count, sector_count, false, version)?;
-        window_post::<SectorShape2KiB>(sector_size, sector_count, sector_count, true, version)?;
+    let mut replicas = BTreeMap::new();
+    for (i, (sector_id, replica, comm_r, cache_dir)) in sectors.iter().enumerate() {
+        let comm_r = if i == 1 { sectors[0].2 } else { *comm_r };
+        replicas.insert(
+            *sector_id,
+            PrivateReplicaInfo::<SectorShape2KiB>::new(
+                replica.path().into(),
+                comm_r,
+                cache_dir.path().into(),
+            )?,
+        );
     }
 
-    Ok(())
-}
+    // Sector 0 is healthy, sector 1 has the comm_r of sector 0, the replica of sector 2 is
+    // truncated and the one of sector 3 deleted.
+    OpenOptions::new()
+        .write(true)
+        .open(sectors[2].1.path())?
+        .set_len(NODE_SIZE as u64)?;
+    remove_file(sectors[3].1.path())?;
 
-#[test]
-fn test_window_post_partition_matching_2kib_base_8() -> Result<()> {
-    let sector_size = SECTOR_SIZE_2_KIB;
-    let sector_count = *WINDOW_POST_SECTOR_COUNT
-        .read()
-        .expect("WINDOW_POST_SECTOR_COUNT poisoned")
-        .get(&sector_size)
-        .expect("unknown sector size");
+    let random_fr: DefaultTreeDomain = Fr::random(&mut rng).into();
+    let mut randomness = [0u8; 32];
+    randomness.copy_from_slice(AsRef::<[u8]>::as_ref(&random_fr));
+    let config = PoStConfig {
+        sector_size: sector_size.into(),
+        sector_count: 2,
+        challenge_count: WINDOW_POST_CHALLENGE_COUNT,
+        typ: PoStType::Window,
+        priority: false,
+        api_version: ApiVersion::V1_1_0,
+    };
 
-    let versions = vec![ApiVersion::V1_0_0, ApiVersion::V1_1_0, ApiVersion::V1_2_0];
-    for version in versions {
-        partition_window_post::<SectorShape2KiB>(
-            sector_size,
-            3, // Validate the scenarios of two partition
-            sector_count,
-            false,
-            version,
-        )?;
-        partition_window_post::<SectorShape2KiB>(sector_size, 3, sector_count, true, version)?;
-    }
+    let report = check_sectors_for_post::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        &replicas,
+        prover_id,
+        Duration::from_secs(60),
+    )?;
+    assert_eq!(report.sectors.len(), 4);
+    assert!(report.sectors[&sectors[0].0].is_ok());
+    assert_eq!(report.sectors[&sectors[1].0], SectorHealth::CommRMismatch);
+    assert!(!report.sectors[&sectors[2].0].is_ok());
+    assert!(matches!(
+        report.sectors[&sectors[3].0],
+        SectorHealth::MissingFile(_)
+    ));
+
+    let mut faulty: Vec<SectorId> = sectors[1..].iter().map(|sector| sector.0).collect();
+    faulty.sort();
+    assert_eq!(report.faulty_sectors(), faulty);
+
+    // A sector without challenges is not reported as healthy.
+    let config = PoStConfig {
+        challenge_count: 0,
+        ..config
+    };
+    let report = check_sectors_for_post::<SectorShape2KiB>(
+        &config,
+        &randomness,
+        &replicas,
+        prover_id,
+        Duration::from_secs(60),
+    )?;
+    assert!(matches!(
+        report.sectors[&sectors[0].0],
+        SectorHealth::Error(_)
+    ));
 
     Ok(())
 }
@@ -1485,14 +2950,20 @@ fn window_post<Tree: 'static + MerkleTreeTrait>(
                 )?;
                 assert!(!valid, "proof made with faulty sectors verified");
             }
-            Err(e) => match e.downcast::<FaultySectorError>() {
-                Err(_) => panic!("failed to downcast to Error"),
-                Ok(FaultySectorError::FaultySectors(sector_ids)) => {
-                    info!("faulty_sectors detected properly: {:?}", sector_ids);
-                    faulty_sectors.extend(sector_ids);
+            Err(e) => {
+                assert!(matches!(
+                    e.downcast_ref::<ApiError>(),
+                    Some(ApiError::FaultySectors(_))
+                ));
+                match e.downcast::<FaultySectorError>() {
+                    Err(_) => panic!("failed to downcast to Error"),
+                    Ok(FaultySectorError::FaultySectors(sector_ids)) => {
+                        info!("faulty_sectors detected properly: {:?}", sector_ids);
+                        faulty_sectors.extend(sector_ids);
+                    }
+                    Ok(_) => panic!("PoSt failed to return FaultySectors error."),
                 }
-                Ok(_) => panic!("PoSt failed to return FaultySectors error."),
-            },
+            }
         };
 
         // This assertion is for the case of a total failure, not a
@@ -2061,7 +3532,7 @@ fn create_seal_for_upgrade<R: Rng, Tree: 'static + MerkleTreeTrait<Hasher = Tree
     let new_cache_dir = tempdir().expect("failed to create temp dir");
 
     // create and generate some random data in staged_data_file.
-    let (mut new_piece_file, _new_piece_bytes) = generate_piece_file(sector_size)?;
+    let (mut new_piece_file, new_piece_bytes) = generate_piece_file(sector_size)?;
     let number_of_bytes_in_piece = porep_config.unpadded_bytes_amount();
 
     let new_piece_info =
@@ -2223,6 +3694,21 @@ fn create_seal_for_upgrade<R: Rng, Tree: 'static + MerkleTreeTrait<Hasher = Tree
         decoded_sector_file.path(),
     )?;
 
+    // Decoding through an `Fr32Writer` recovers the unpadded piece.
+    let nodes_count = sector_size as usize / NODE_SIZE;
+    let mut fr32_writer = Fr32Writer::new(Vec::new());
+    decode_from_range(
+        nodes_count,
+        encoded.comm_d_new,
+        comm_r,
+        File::open(new_sealed_sector_file.path())?,
+        File::open(sealed_sector_file.path())?,
+        &mut fr32_writer,
+        0,
+        nodes_count,
+    )?;
+    assert_eq!(fr32_writer.finish()?, new_piece_bytes);
+
     decoded_sector_file.close()?;
     decoded_sector_in_parts_file.close()?;
 
@@ -4,7 +4,7 @@ use std::iter::Iterator;
 use anyhow::Result;
 use blstrs::Scalar as Fr;
 use filecoin_proofs::{
-    add_piece, commitment_from_fr,
+    add_piece, add_piece_unsized, commitment_from_fr,
     pieces::{
         compute_comm_d, get_piece_alignment, get_piece_start_byte, piece_hash, verify_pieces,
         zero_padding, EmptySource, PieceAlignment,
@@ -283,6 +283,46 @@ fn test_verify_padded_pieces() {
     assert!(verify_pieces(&comm_d, &pieces, sector_size).expect("failed to verify pieces"));
 }
 
+#[test]
+fn test_add_piece_unsized() -> Result<()> {
+    let rng = &mut XorShiftRng::from_seed(TEST_SEED);
+
+    // `add_piece_unsized` commits the data in chunks of 1_040_384 unpadded bytes, which the last
+    // three lengths fill exactly, just exceed, and span with a split last chunk.
+    for data_len in [
+        1, 126, 127, 128, 1000, 2032, 2033, 1_032_197, 1_040_384, 1_040_385, 2_500_000,
+    ] {
+        let mut data = vec![0u8; data_len];
+        rng.fill_bytes(&mut data);
+
+        let mut staged = Vec::new();
+        let (piece_info, bytes_read) = add_piece_unsized(Cursor::new(&data), &mut staged)?;
+        assert_eq!(bytes_read, UnpaddedBytesAmount(data_len as u64));
+
+        // The smallest piece size containing the data is chosen.
+        let piece_size = u64::from(piece_info.size) as usize;
+        assert!(u64::from(PaddedBytesAmount::from(piece_info.size)).is_power_of_two());
+        assert!(piece_size >= data_len);
+        assert!(piece_size == 127 || piece_size / 2 < data_len);
+        assert_eq!(
+            staged.len() as u64,
+            u64::from(PaddedBytesAmount::from(piece_info.size))
+        );
+
+        // The result must match adding the data padded with zeros as a piece of that size.
+        data.resize(piece_size, 0);
+        let mut expected = Vec::new();
+        let (expected_piece_info, _) =
+            add_piece(Cursor::new(&data), &mut expected, piece_info.size, &[])?;
+        assert_eq!(piece_info, expected_piece_info);
+        assert_eq!(staged, expected);
+    }
+
+    assert!(add_piece_unsized(Cursor::new(&[]), &mut Vec::new()).is_err());
+
+    Ok(())
+}
+
 #[test]
 #[ignore] // slow test
 fn test_verify_random_pieces() -> Result<()> {
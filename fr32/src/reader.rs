@@ -1,5 +1,5 @@
 use std::cmp::min;
-use std::io::{self, Read};
+use std::io::{self, Read, Seek, SeekFrom};
 use std::mem::size_of;
 
 #[cfg(not(target_arch = "aarch64"))]
@@ -14,17 +14,20 @@ const IN_BITS_FR: usize = 254;
 /// The amount of bits in an Fr when padded.
 const OUT_BITS_FR: usize = 256;
 
-const NUM_BYTES_IN_BLOCK: usize = NUM_FRS_PER_BLOCK * IN_BITS_FR / 8;
-const NUM_BYTES_OUT_BLOCK: usize = NUM_FRS_PER_BLOCK * OUT_BITS_FR / 8;
+pub(crate) const NUM_BYTES_IN_BLOCK: usize = NUM_FRS_PER_BLOCK * IN_BITS_FR / 8;
+pub(crate) const NUM_BYTES_OUT_BLOCK: usize = NUM_FRS_PER_BLOCK * OUT_BITS_FR / 8;
 
-const NUM_U128S_PER_BLOCK: usize = NUM_BYTES_OUT_BLOCK / size_of::<u128>();
+pub(crate) const NUM_U128S_PER_BLOCK: usize = NUM_BYTES_OUT_BLOCK / size_of::<u128>();
 
-const MASK_SKIP_HIGH_2: u128 = 0b0011_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111;
+pub(crate) const MASK_SKIP_HIGH_2: u128 = 0b0011_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111_1111;
 
 #[repr(align(16))]
 struct AlignedBuffer([u8; NUM_BYTES_IN_BLOCK + 1]);
 
 /// An `io::Reader` that converts unpadded input into valid `Fr32` padded output.
+///
+/// If the source is seekable, so is the reader. Its positions are offsets into the padded output
+/// of the source from the position the source is at when the reader is created.
 pub struct Fr32Reader<R> {
     /// The source being padded.
     source: R,
@@ -36,8 +39,12 @@ pub struct Fr32Reader<R> {
     out_buffer: [u128; NUM_U128S_PER_BLOCK],
     /// The current offset into the `out_buffer` in bytes.
     out_offset: usize,
-    /// How many `Fr32`s are available in the `out_buffer`.
-    available_frs: usize,
+    /// How many bytes are available in the `out_buffer`, starting at `out_offset`.
+    available_bytes: usize,
+    /// The position in the padded output in bytes.
+    position: u64,
+    /// The position of the source relative to its position when the reader was created.
+    source_offset: u64,
     /// Are we done reading?
     done: bool,
 }
@@ -64,7 +71,9 @@ impl<R: Read> Fr32Reader<R> {
             in_buffer: AlignedBuffer([0; NUM_BYTES_IN_BLOCK + 1]),
             out_buffer: [0; NUM_U128S_PER_BLOCK],
             out_offset: 0,
-            available_frs: 0,
+            available_bytes: 0,
+            position: 0,
+            source_offset: 0,
             done: false,
         }
     }
@@ -122,9 +131,29 @@ impl<R: Read> Fr32Reader<R> {
         for val in &mut self.in_buffer.0[bytes_read..NUM_BYTES_IN_BLOCK] {
             *val = 0;
         }
+        self.source_offset += bytes_read as u64;
 
         Ok(bytes_read)
     }
+
+    /// Reads and processes the next block. Returns false, if the source has no more data.
+    fn load_block(&mut self) -> io::Result<bool> {
+        let bytes_read = self.fill_in_buffer()?;
+
+        // All data was read from the source, no new data in the buffer.
+        if bytes_read == 0 {
+            self.done = true;
+            return Ok(false);
+        }
+
+        self.process_block();
+
+        // Update state of how many new bytes are now available, which are all of the Frs
+        // containing the bytes read.
+        self.available_bytes = div_ceil(bytes_read * 8, IN_BITS_FR) * (OUT_BITS_FR / 8);
+
+        Ok(true)
+    }
 }
 
 /// Division of x by y, rounding up.
@@ -146,28 +175,15 @@ impl<R: Read> Read for Fr32Reader<R> {
         let bytes_to_read = target.len();
 
         while bytes_read < bytes_to_read {
-            // Load and process the next block, if no Frs are available anymore.
-            if self.available_frs == 0 {
-                let bytes_read = self.fill_in_buffer()?;
-
-                // All data was read from the source, no new data in the buffer.
-                if bytes_read == 0 {
-                    self.done = true;
-                    break;
-                }
-
-                self.process_block();
-
-                // Update state of how many new Frs are now available.
-                self.available_frs = div_ceil(bytes_read * 8, IN_BITS_FR);
+            // Load and process the next block, if no bytes are available anymore.
+            if self.available_bytes == 0 && !self.load_block()? {
+                break;
             }
 
-            // Write out as many Frs as available and requested
+            // Write out as many bytes as available and requested
             {
-                let available_bytes = self.available_frs * (OUT_BITS_FR / 8);
-
                 let target_start = bytes_read;
-                let target_end = min(target_start + available_bytes, bytes_to_read);
+                let target_end = min(target_start + self.available_bytes, bytes_to_read);
                 let len = target_end - target_start;
 
                 let out_start = self.out_offset;
@@ -177,14 +193,111 @@ impl<R: Read> Read for Fr32Reader<R> {
                     .copy_from_slice(&self.out_buffer.as_byte_slice()[out_start..out_end]);
                 bytes_read += len;
                 self.out_offset += len;
-                self.available_frs -= div_ceil(len * 8, OUT_BITS_FR);
+                self.available_bytes -= len;
             }
         }
+        self.position += bytes_read as u64;
 
         Ok(bytes_read)
     }
 }
 
+impl<R: Read + Seek> Seek for Fr32Reader<R> {
+    /// Seeks to an offset in the padded output. The source is repositioned to the start of the
+    /// block containing the offset, of which everything before the offset is skipped.
+    ///
+    /// Seeking beyond the end is allowed, reads return no data from there.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let source_start = self.source_start()?;
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => add_offset(self.position, offset),
+            SeekFrom::End(offset) => {
+                let source_end = self.source.seek(SeekFrom::End(0))?;
+                add_offset(padded_len(source_end.saturating_sub(source_start)), offset)
+            }
+        };
+        let position = match position {
+            Some(position) => position,
+            None => {
+                // Seeking to the end moved the source, restore it.
+                self.seek_to(source_start, self.position)?;
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "invalid seek to a negative or overflowing position",
+                ));
+            }
+        };
+        self.seek_to(source_start, position)?;
+
+        Ok(position)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+}
+
+impl<R: Read + Seek> Fr32Reader<R> {
+    /// Returns the absolute position the source was at when the reader was created.
+    fn source_start(&mut self) -> io::Result<u64> {
+        self.source
+            .stream_position()?
+            .checked_sub(self.source_offset)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "source was moved before the position of the reader",
+                )
+            })
+    }
+
+    fn seek_to(&mut self, source_start: u64, position: u64) -> io::Result<()> {
+        let block = position / NUM_BYTES_OUT_BLOCK as u64;
+        let block_offset = (position % NUM_BYTES_OUT_BLOCK as u64) as usize;
+
+        self.source_offset = block * NUM_BYTES_IN_BLOCK as u64;
+        self.source
+            .seek(SeekFrom::Start(source_start + self.source_offset))?;
+        self.out_offset = 0;
+        self.available_bytes = 0;
+        self.done = false;
+
+        if block_offset > 0 && self.load_block()? {
+            let skipped = min(block_offset, self.available_bytes);
+            self.out_offset = skipped;
+            self.available_bytes -= skipped;
+        }
+        self.position = position;
+
+        Ok(())
+    }
+}
+
+/// The length of the padded output of `len` unpadded bytes, as written by `Fr32Reader`.
+fn padded_len(len: u64) -> u64 {
+    if len == 0 {
+        return 0;
+    }
+    let full_blocks = len / NUM_BYTES_IN_BLOCK as u64;
+    let remainder = (len % NUM_BYTES_IN_BLOCK as u64) as usize;
+    let remainder_frs = if remainder == 0 {
+        0
+    } else {
+        div_ceil(remainder * 8, IN_BITS_FR)
+    };
+
+    full_blocks * NUM_BYTES_OUT_BLOCK as u64 + (remainder_frs * OUT_BITS_FR / 8) as u64
+}
+
+fn add_offset(position: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        position.checked_add(offset as u64)
+    } else {
+        position.checked_sub(offset.unsigned_abs())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -196,7 +309,7 @@ mod tests {
     use pretty_assertions::assert_eq;
     use rand::random;
 
-    use crate::bytes_into_fr;
+    use crate::{bytes_into_fr, to_padded_bytes, to_unpadded_bytes};
 
     const DATA_BITS: u64 = 254;
     const TARGET_BITS: u64 = 256;
@@ -400,4 +513,150 @@ mod tests {
 
         assert_eq!(buf.into_boxed_slice(), bit_vec_padding(source));
     }
+
+    fn random_padded(len: usize) -> (Vec<u8>, Vec<u8>) {
+        let data: Vec<u8> = (0..len).map(|_| random()).collect();
+        let mut padded = Vec::new();
+        Fr32Reader::new(Cursor::new(&data))
+            .read_to_end(&mut padded)
+            .expect("in-memory read failed");
+        (data, padded)
+    }
+
+    #[test]
+    fn test_small_reads() {
+        let (data, padded) = random_padded(300);
+
+        for chunk_size in [1, 7, 31, 32, 33, 127, 129] {
+            let mut reader = Fr32Reader::new(Cursor::new(&data));
+            let mut buf = Vec::new();
+            let mut chunk = vec![0u8; chunk_size];
+            loop {
+                let n = reader.read(&mut chunk).expect("in-memory read failed");
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            assert_eq!(buf, padded, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_seek_padded_offsets() {
+        // A full block, followed by an incomplete one ending within an Fr.
+        let (data, padded) = random_padded(127 + 100);
+        let mut reader = Fr32Reader::new(Cursor::new(&data));
+
+        for offset in 0..padded.len() as u64 + 40 {
+            assert_eq!(
+                reader.seek(SeekFrom::Start(offset)).expect("seek failed"),
+                offset
+            );
+            assert_eq!(reader.stream_position().expect("seek failed"), offset);
+
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf).expect("in-memory read failed");
+            let start = min(offset as usize, padded.len());
+            assert_eq!(&buf[..], &padded[start..], "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn test_seek_unpadded_offsets() {
+        let (data, padded) = random_padded(127 * 3);
+        let mut reader = Fr32Reader::new(Cursor::new(&data));
+
+        // Unpadded offsets of every class: block aligned, Fr aligned and within an Fr.
+        for unpadded_offset in 0..data.len() as u64 {
+            let offset = to_padded_bytes(unpadded_offset as usize) as u64;
+            assert_eq!(to_unpadded_bytes(offset), unpadded_offset);
+
+            reader.seek(SeekFrom::Start(offset)).expect("seek failed");
+            let mut buf = [0u8; 64];
+            let n = reader.read(&mut buf).expect("in-memory read failed");
+            let expected = &padded[offset as usize..min(offset as usize + 64, padded.len())];
+            assert_eq!(&buf[..n], &expected[..n], "offset {}", unpadded_offset);
+            assert!(n > 0);
+        }
+    }
+
+    #[test]
+    fn test_seek_relative() {
+        let (data, padded) = random_padded(500);
+        let padded_len = padded.len() as u64;
+        let mut reader = Fr32Reader::new(Cursor::new(&data));
+
+        assert_eq!(
+            reader.seek(SeekFrom::End(0)).expect("seek failed"),
+            padded_len
+        );
+        assert_eq!(
+            reader.seek(SeekFrom::End(-200)).expect("seek failed"),
+            padded_len - 200
+        );
+
+        let mut buf = [0u8; 50];
+        reader.read_exact(&mut buf).expect("in-memory read failed");
+        let position = padded_len - 150;
+        assert_eq!(&buf[..], &padded[position as usize - 50..position as usize]);
+        assert_eq!(reader.stream_position().expect("seek failed"), position);
+
+        assert_eq!(
+            reader.seek(SeekFrom::Current(-70)).expect("seek failed"),
+            position - 70
+        );
+        reader.read_exact(&mut buf).expect("in-memory read failed");
+        assert_eq!(
+            &buf[..],
+            &padded[position as usize - 70..position as usize - 20]
+        );
+        assert_eq!(
+            reader.seek(SeekFrom::Current(3)).expect("seek failed"),
+            position - 17
+        );
+
+        assert!(reader
+            .seek(SeekFrom::Current(-(padded_len as i64)))
+            .is_err());
+        assert!(reader
+            .seek(SeekFrom::End(-(padded_len as i64) - 1))
+            .is_err());
+        // A failed seek keeps the position.
+        assert_eq!(
+            reader.stream_position().expect("seek failed"),
+            position - 17
+        );
+    }
+
+    #[test]
+    fn test_seek_source_offset() {
+        let (data, padded) = random_padded(300);
+        let prefix = 100;
+        let mut source = Cursor::new([vec![0xff; prefix], data].concat());
+        source.set_position(prefix as u64);
+        let mut reader = Fr32Reader::new(source);
+
+        // Positions are relative to where the source was, also after reading from it first.
+        let mut buf = [0u8; 40];
+        reader.read_exact(&mut buf).expect("in-memory read failed");
+        assert_eq!(&buf[..], &padded[..40]);
+        for offset in [0, 1, 127, 128, 200, padded.len() as u64] {
+            reader.seek(SeekFrom::Start(offset)).expect("seek failed");
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf).expect("in-memory read failed");
+            assert_eq!(&buf[..], &padded[offset as usize..], "offset {}", offset);
+        }
+        assert_eq!(
+            reader.seek(SeekFrom::End(0)).expect("seek failed"),
+            padded.len() as u64
+        );
+        assert_eq!(
+            reader.seek(SeekFrom::End(-10)).expect("seek failed"),
+            padded.len() as u64 - 10
+        );
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).expect("in-memory read failed");
+        assert_eq!(&buf[..], &padded[padded.len() - 10..]);
+    }
 }
@@ -0,0 +1,249 @@
+use std::cmp::min;
+use std::convert::TryInto;
+use std::io::{self, Write};
+use std::mem::size_of;
+
+use crate::reader::{
+    MASK_SKIP_HIGH_2, NUM_BYTES_IN_BLOCK, NUM_BYTES_OUT_BLOCK, NUM_U128S_PER_BLOCK,
+};
+use crate::to_unpadded_bytes;
+
+/// An `io::Writer` that converts valid `Fr32` padded input into unpadded output, the inverse of
+/// `Fr32Reader`.
+///
+/// The input may be written in chunks of any size. As a padded block is only unpadded once it is
+/// complete, the last, incomplete block is not written before `finish` is called.
+pub struct Fr32Writer<W: Write> {
+    /// The sink of the unpadded bytes.
+    target: W,
+    /// Currently written block of padded input.
+    in_buffer: [u8; NUM_BYTES_OUT_BLOCK],
+    /// How many bytes of `in_buffer` are filled.
+    in_len: usize,
+    /// Currently unpadded block. Only the first 127 bytes are ever valid.
+    out_buffer: [u8; NUM_BYTES_OUT_BLOCK],
+}
+
+macro_rules! unprocess_fr {
+    (
+        $in_buffer:expr,
+        $out_buffer:expr,
+        $bit_offset:expr
+    ) => {{
+        let low = $in_buffer[0];
+        let high = $in_buffer[1] & MASK_SKIP_HIGH_2; // skip the 2 padding bits
+        $out_buffer[0] |= low << 128 - $bit_offset;
+        $out_buffer[1] = low >> $bit_offset;
+        $out_buffer[1] |= high << 128 - $bit_offset;
+        $out_buffer[2] = high >> $bit_offset;
+    }};
+}
+
+impl<W: Write> Fr32Writer<W> {
+    pub fn new(target: W) -> Self {
+        Fr32Writer {
+            target,
+            in_buffer: [0; NUM_BYTES_OUT_BLOCK],
+            in_len: 0,
+            out_buffer: [0; NUM_BYTES_OUT_BLOCK],
+        }
+    }
+
+    /// Unpads the block of padded bytes `block`, writing the result to `out_buffer`.
+    fn process_block(&mut self, block: &[u8; NUM_BYTES_OUT_BLOCK]) {
+        let mut in_buffer = [0u128; NUM_U128S_PER_BLOCK];
+        for (value, bytes) in in_buffer
+            .iter_mut()
+            .zip(block.chunks_exact(size_of::<u128>()))
+        {
+            *value = u128::from_le_bytes(bytes.try_into().expect("chunk of u128 size"));
+        }
+
+        let mut out = [0u128; NUM_U128S_PER_BLOCK];
+        // 0..254
+        {
+            out[0] = in_buffer[0];
+            out[1] = in_buffer[1] & MASK_SKIP_HIGH_2;
+        }
+        // 254..508
+        unprocess_fr!(&in_buffer[2..], &mut out[1..], 2);
+        // 508..762
+        unprocess_fr!(&in_buffer[4..], &mut out[3..], 4);
+        // 762..1016
+        unprocess_fr!(&in_buffer[6..], &mut out[5..], 6);
+
+        for (bytes, value) in self
+            .out_buffer
+            .chunks_exact_mut(size_of::<u128>())
+            .zip(out.iter())
+        {
+            bytes.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    /// Unpads the last, possibly incomplete block, flushes and returns the target.
+    ///
+    /// An incomplete block is unpadded as if it was filled up with zeros, of which only the
+    /// `to_unpadded_bytes` of its length are written.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.in_len > 0 {
+            for val in &mut self.in_buffer[self.in_len..] {
+                *val = 0;
+            }
+            let block = self.in_buffer;
+            self.process_block(&block);
+
+            let len = to_unpadded_bytes(self.in_len as u64) as usize;
+            self.target.write_all(&self.out_buffer[..len])?;
+            self.in_len = 0;
+        }
+        self.target.flush()?;
+
+        Ok(self.target)
+    }
+}
+
+impl<W: Write> Write for Fr32Writer<W> {
+    fn write(&mut self, mut source: &[u8]) -> io::Result<usize> {
+        let bytes_to_write = source.len();
+
+        // Complete the partially filled block first.
+        if self.in_len > 0 {
+            let len = min(NUM_BYTES_OUT_BLOCK - self.in_len, source.len());
+            self.in_buffer[self.in_len..self.in_len + len].copy_from_slice(&source[..len]);
+            self.in_len += len;
+            source = &source[len..];
+
+            if self.in_len < NUM_BYTES_OUT_BLOCK {
+                return Ok(bytes_to_write);
+            }
+
+            let block = self.in_buffer;
+            self.process_block(&block);
+            self.target
+                .write_all(&self.out_buffer[..NUM_BYTES_IN_BLOCK])?;
+            self.in_len = 0;
+        }
+
+        // Unpad all complete blocks directly from the source.
+        let mut blocks = source.chunks_exact(NUM_BYTES_OUT_BLOCK);
+        for block in &mut blocks {
+            self.process_block(block.try_into().expect("chunk of block size"));
+            self.target
+                .write_all(&self.out_buffer[..NUM_BYTES_IN_BLOCK])?;
+        }
+
+        // Keep the remainder for the next write.
+        let remainder = blocks.remainder();
+        self.in_buffer[..remainder.len()].copy_from_slice(remainder);
+        self.in_len = remainder.len();
+
+        Ok(bytes_to_write)
+    }
+
+    /// Flushes the target. The incomplete block which is not written yet is kept, as only
+    /// `finish` knows that no more input follows.
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{Cursor, Read};
+
+    use proptest::{collection::vec, prelude::any, proptest};
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    use crate::{write_unpadded, Fr32Reader};
+
+    const TEST_SEED: [u8; 16] = [
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ];
+
+    fn pad(data: &[u8]) -> Vec<u8> {
+        let mut padded = Vec::new();
+        Fr32Reader::new(Cursor::new(data))
+            .read_to_end(&mut padded)
+            .expect("in-memory read failed");
+        padded
+    }
+
+    #[test]
+    fn test_simple_127() {
+        let data = vec![255u8; 127];
+        let padded = pad(&data);
+        assert_eq!(padded.len(), 128);
+
+        let mut writer = Fr32Writer::new(Vec::new());
+        writer.write_all(&padded).expect("in-memory write failed");
+        assert_eq!(writer.finish().expect("in-memory write failed"), data);
+    }
+
+    #[test]
+    fn test_incomplete_block_is_written_on_finish() {
+        let data = vec![255u8; 200];
+        let padded = pad(&data);
+
+        let mut writer = Fr32Writer::new(Vec::new());
+        writer.write_all(&padded).expect("in-memory write failed");
+        writer.flush().expect("in-memory flush failed");
+        assert_eq!(writer.target, data[..127]);
+
+        let unpadded = writer.finish().expect("in-memory write failed");
+        assert_eq!(
+            unpadded.len() as u64,
+            to_unpadded_bytes(padded.len() as u64)
+        );
+        assert_eq!(unpadded[..200], data[..]);
+    }
+
+    #[test]
+    fn test_matches_write_unpadded() {
+        let rng = &mut XorShiftRng::from_seed(TEST_SEED);
+
+        for len in [1, 31, 32, 33, 127, 128, 254, 1000, 128 * 1000 + 5] {
+            let padded = pad(&(0..len).map(|_| rng.gen()).collect::<Vec<u8>>());
+            let unpadded_len = to_unpadded_bytes(padded.len() as u64) as usize;
+
+            let mut expected = Vec::new();
+            write_unpadded(&padded, &mut expected, 0, unpadded_len).expect("write_unpadded failed");
+
+            let mut writer = Fr32Writer::new(Vec::new());
+            writer.write_all(&padded).expect("in-memory write failed");
+            assert_eq!(writer.finish().expect("in-memory write failed"), expected);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn fr32_writer_roundtrip(
+            data in vec(any::<u8>(), 0..1000),
+            chunk_sizes in vec(1..300usize, 1..20),
+        ) {
+            let padded = pad(&data);
+
+            // Write the padded data in chunks of the given sizes, cycling through them.
+            let mut writer = Fr32Writer::new(Vec::new());
+            let mut offset = 0;
+            for chunk_size in chunk_sizes.iter().cycle() {
+                if offset == padded.len() {
+                    break;
+                }
+                let end = min(offset + chunk_size, padded.len());
+                writer.write_all(&padded[offset..end]).expect("in-memory write failed");
+                offset = end;
+            }
+            let unpadded = writer.finish().expect("in-memory write failed");
+
+            assert_eq!(unpadded.len() as u64, to_unpadded_bytes(padded.len() as u64));
+            assert_eq!(&unpadded[..data.len()], &data[..]);
+            // Whatever follows the data are the zeros padding its last element.
+            assert!(unpadded[data.len()..].iter().all(|byte| *byte == 0));
+        }
+    }
+}
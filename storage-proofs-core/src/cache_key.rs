@@ -7,6 +7,10 @@ pub enum CacheKey {
     CommDTree,
     CommCTree,
     CommRLastTree,
+    LabelCheckpoint,
+    Manifest,
+    UnsealKey,
+    SealingJob,
 }
 
 impl Display for CacheKey {
@@ -17,6 +21,10 @@ impl Display for CacheKey {
             CacheKey::CommDTree => write!(f, "tree-d"),
             CacheKey::CommCTree => write!(f, "tree-c"),
             CacheKey::CommRLastTree => write!(f, "tree-r-last"),
+            CacheKey::LabelCheckpoint => write!(f, "label-checkpoint"),
+            CacheKey::Manifest => write!(f, "manifest"),
+            CacheKey::UnsealKey => write!(f, "unseal-key"),
+            CacheKey::SealingJob => write!(f, "sealing-job"),
         }
     }
 }
@@ -1,3 +1,6 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use anyhow::{ensure, Context};
 use bellperson::{
     groth16::{
@@ -8,7 +11,7 @@ use bellperson::{
         create_random_proof_batch, create_random_proof_batch_in_priority, verify_proofs_batch,
         PreparedVerifyingKey,
     },
-    Circuit,
+    Circuit, ConstraintSystem, SynthesisError,
 };
 use blstrs::{Bls12, Scalar as Fr};
 use log::info;
@@ -40,6 +43,36 @@ pub struct PublicParams<'a, S: ProofScheme<'a>> {
     pub priority: bool,
 }
 
+/// Wall time spent creating Groth proofs, see `CompoundProof::prove_with_vanilla_timed`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SnarkTimings {
+    /// Building the circuits and synthesizing them, until the last synthesis is done.
+    pub synthesis: Duration,
+    /// Creating the proofs from the synthesized circuits.
+    pub proving: Duration,
+}
+
+/// A circuit which records in `synthesized` when its synthesis is done, unless a circuit sharing
+/// it finished later.
+struct TimedCircuit<C> {
+    circuit: C,
+    synthesized: Arc<Mutex<Option<Instant>>>,
+}
+
+impl<C: Circuit<Fr>> Circuit<Fr> for TimedCircuit<C> {
+    fn synthesize<CS: ConstraintSystem<Fr>>(
+        self,
+        cs: &mut CS,
+    ) -> std::result::Result<(), SynthesisError> {
+        let result = self.circuit.synthesize(cs);
+        let now = Instant::now();
+        let mut synthesized = self.synthesized.lock().expect("synthesized lock poisoned");
+        *synthesized = Some(synthesized.map_or(now, |last| last.max(now)));
+
+        result
+    }
+}
+
 /// CircuitComponent exists so parent components can pass private inputs to their subcomponents
 /// when calling CompoundProof::circuit directly. In general, there are no internal private inputs,
 /// and a default value will be passed. CompoundProof::circuit implementations should exhibit
@@ -118,22 +151,34 @@ where
         vanilla_proofs: Vec<S::Proof>,
         groth_params: &Bls12GrothParams,
     ) -> Result<Vec<groth16::Proof<Bls12>>> {
+        Self::prove_with_vanilla_timed(pub_params, pub_in, vanilla_proofs, groth_params)
+            .map(|(groth_proofs, _)| groth_proofs)
+    }
+
+    /// Like `prove_with_vanilla`, and additionally returns the time spent on synthesizing the
+    /// circuits and on proving them.
+    fn prove_with_vanilla_timed(
+        pub_params: &PublicParams<'a, S>,
+        pub_in: &S::PublicInputs,
+        vanilla_proofs: Vec<S::Proof>,
+        groth_params: &Bls12GrothParams,
+    ) -> Result<(Vec<groth16::Proof<Bls12>>, SnarkTimings)> {
         let partition_count = Self::partition_count(pub_params);
 
         // This will always run at least once, since there cannot be zero partitions.
         ensure!(partition_count > 0, "There must be partitions");
 
         info!("snark_proof:start");
-        let groth_proofs = Self::circuit_proofs(
+        let (groth_proofs, timings) = Self::circuit_proofs_for_partitions_timed(
             pub_in,
-            vanilla_proofs,
+            vanilla_proofs.into_iter().enumerate().collect(),
             &pub_params.vanilla_params,
             groth_params,
             pub_params.priority,
         )?;
         info!("snark_proof:finish");
 
-        Ok(groth_proofs)
+        Ok((groth_proofs, timings))
     }
 
     // verify is equivalent to ProofScheme::verify.
@@ -236,23 +281,65 @@ where
         groth_params: &Bls12GrothParams,
         priority: bool,
     ) -> Result<Vec<groth16::Proof<Bls12>>> {
+        Self::circuit_proofs_for_partitions(
+            pub_in,
+            vanilla_proofs.into_iter().enumerate().collect(),
+            pub_params,
+            groth_params,
+            priority,
+        )
+    }
+
+    /// Like `circuit_proofs`, but for the vanilla proofs of the given partitions only, each
+    /// paired with its partition index `k`. Proofs are returned in the order of the input.
+    fn circuit_proofs_for_partitions(
+        pub_in: &S::PublicInputs,
+        vanilla_proofs: Vec<(usize, S::Proof)>,
+        pub_params: &S::PublicParams,
+        groth_params: &Bls12GrothParams,
+        priority: bool,
+    ) -> Result<Vec<groth16::Proof<Bls12>>> {
+        Self::circuit_proofs_for_partitions_timed(
+            pub_in,
+            vanilla_proofs,
+            pub_params,
+            groth_params,
+            priority,
+        )
+        .map(|(groth_proofs, _)| groth_proofs)
+    }
+
+    /// Like `circuit_proofs_for_partitions`, and additionally returns the time spent on
+    /// synthesizing the circuits and on proving them.
+    fn circuit_proofs_for_partitions_timed(
+        pub_in: &S::PublicInputs,
+        vanilla_proofs: Vec<(usize, S::Proof)>,
+        pub_params: &S::PublicParams,
+        groth_params: &Bls12GrothParams,
+        priority: bool,
+    ) -> Result<(Vec<groth16::Proof<Bls12>>, SnarkTimings)> {
         let mut rng = OsRng;
         ensure!(
             !vanilla_proofs.is_empty(),
             "cannot create a circuit proof over missing vanilla proofs"
         );
 
+        let start = Instant::now();
+        // The circuits are synthesized by the prover, which does not report when it is done.
+        let synthesized = Arc::new(Mutex::new(None));
         let circuits = vanilla_proofs
             .into_par_iter()
-            .enumerate()
             .map(|(k, vanilla_proof)| {
-                Self::circuit(
-                    pub_in,
-                    C::ComponentPrivateInputs::default(),
-                    &vanilla_proof,
-                    pub_params,
-                    Some(k),
-                )
+                Ok(TimedCircuit {
+                    circuit: Self::circuit(
+                        pub_in,
+                        C::ComponentPrivateInputs::default(),
+                        &vanilla_proof,
+                        pub_params,
+                        Some(k),
+                    )?,
+                    synthesized: synthesized.clone(),
+                })
             })
             .collect::<Result<Vec<_>>>()?;
 
@@ -261,8 +348,17 @@ where
         } else {
             create_random_proof_batch(circuits, groth_params, &mut rng)?
         };
+        let end = Instant::now();
+        let synthesized = synthesized
+            .lock()
+            .expect("synthesized lock poisoned")
+            .unwrap_or(start);
+        let timings = SnarkTimings {
+            synthesis: synthesized - start,
+            proving: end - synthesized,
+        };
 
-        groth_proofs
+        let groth_proofs = groth_proofs
             .into_iter()
             .map(|groth_proof| {
                 let mut proof_vec = Vec::new();
@@ -270,7 +366,9 @@ where
                 let gp = groth16::Proof::<Bls12>::read(&proof_vec[..])?;
                 Ok(gp)
             })
-            .collect()
+            .collect::<Result<_>>()?;
+
+        Ok((groth_proofs, timings))
     }
 
     /// Given a prover_srs key, a list of groth16 proofs, and an ordered list of seeds
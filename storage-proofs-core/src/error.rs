@@ -39,6 +39,10 @@ pub enum Error {
     MissingPrivateInput(&'static str, u64),
     #[error("faulty sectors {:?}", _0)]
     FaultySectors(Vec<SectorId>),
+    #[error("unreadable leafs {1:?} in sector {0}")]
+    UnreadableLeafs(SectorId, Vec<u64>),
+    #[error("Generated vanilla proof for sector {0} is invalid")]
+    InvalidVanillaProof(SectorId),
     #[error("Invalid parameters file: {}", _0)]
     InvalidParameters(String),
 }
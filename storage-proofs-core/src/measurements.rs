@@ -44,6 +44,9 @@ pub enum Operation {
     PostFinalizeTicket,
     PostReadChallengedRange,
     PostPartialTicketHash,
+    PostChallengeDerivation,
+    PostTreeOpen,
+    PostSnark,
 }
 
 #[cfg(feature = "measurements")]
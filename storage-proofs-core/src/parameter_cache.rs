@@ -1,9 +1,13 @@
 use std::collections::{BTreeMap, HashSet};
-use std::fs::{create_dir_all, File, OpenOptions};
+use std::convert::TryInto;
+use std::ffi::OsStr;
+use std::fs::{self, create_dir_all, File, Metadata, OpenOptions};
 use std::io::{self, Read, Seek, SeekFrom, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::time::Instant;
+use std::time::{Instant, UNIX_EPOCH};
 
 use anyhow::bail;
 use bellperson::{groth16, Circuit};
@@ -12,9 +16,10 @@ use blstrs::{Bls12, Scalar as Fr};
 use fs2::FileExt;
 use itertools::Itertools;
 use lazy_static::lazy_static;
-use log::info;
+use log::{info, warn};
 use memmap2::MmapOptions;
-use rand::RngCore;
+use rand::{rngs::OsRng, RngCore};
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
@@ -32,6 +37,12 @@ pub const PARAMETER_METADATA_EXT: &str = "meta";
 pub const VERIFYING_KEY_EXT: &str = "vk";
 pub const SRS_KEY_EXT: &str = "srs";
 pub const SRS_SHARED_KEY_NAME: &str = "fil-inner-product-v1";
+pub const VERIFIED_MARKER_EXT: &str = "verified";
+/// The secret key within the parameter cache directory, which verified markers are sealed with.
+pub const VERIFIED_MARKER_KEY_FILE: &str = ".verified-marker-key";
+
+/// Personalization of the BLAKE2b seal of verified markers.
+const VERIFIED_MARKER_PERSONAL: &[u8; 16] = b"fil-param-marker";
 
 #[derive(Debug)]
 pub struct LockedFile(File);
@@ -42,7 +53,7 @@ pub type Bls12GrothParams = groth16::MappedParameters<Bls12>;
 #[cfg(feature = "cuda-supraseal")]
 pub type Bls12GrothParams = groth16::SuprasealParameters<Bls12>;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ParameterData {
     pub cid: String,
     pub digest: String,
@@ -60,6 +71,16 @@ lazy_static! {
     /// Contains the parameters that were previously verified. This way the parameter files are
     /// only hashed once and not on every usage.
     static ref VERIFIED_PARAMETERS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    /// The key verified markers are sealed with. If it can not be loaded, no markers are trusted
+    /// or written.
+    static ref VERIFIED_MARKER_KEY: Option<[u8; 32]> =
+        match load_verified_marker_key(&parameter_cache_dir()) {
+            Ok(key) => Some(key),
+            Err(err) => {
+                warn!("failed to load verified marker key: {}", err);
+                None
+            }
+        };
 }
 
 pub fn parameter_id(cache_id: &str) -> String {
@@ -405,6 +426,355 @@ fn ensure_parent(path: &Path) -> io::Result<()> {
 
 type GetParameterDataCallback = fn(&str) -> Option<&ParameterData>;
 
+/// Records that a file of the parameter cache matched its digest in the manifest. A marker is only
+/// valid as long as the size and modification time of the file are unchanged, and if it is sealed
+/// with the key of the parameter cache. Verifying keys are cheap to hash and never get a marker.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VerifiedMarker {
+    pub size: u64,
+    pub modified_secs: u64,
+    pub modified_nanos: u32,
+    pub digest: String,
+    /// BLAKE2b of the file name and all other fields, keyed with the key of the parameter cache.
+    /// Markers can not be forged without the key, and are invalid if edited or copied next to
+    /// another file.
+    pub seal: String,
+}
+
+impl VerifiedMarker {
+    fn new(file_name: &str, metadata: &Metadata, digest: &str, key: &[u8; 32]) -> io::Result<Self> {
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let mut marker = VerifiedMarker {
+            size: metadata.len(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+            digest: digest.to_string(),
+            seal: String::new(),
+        };
+        marker.seal = marker.compute_seal(file_name, key);
+
+        Ok(marker)
+    }
+
+    fn compute_seal(&self, file_name: &str, key: &[u8; 32]) -> String {
+        let mut hasher = Blake2bParams::new()
+            .hash_length(32)
+            .key(key)
+            .personal(VERIFIED_MARKER_PERSONAL)
+            .to_state();
+        hasher.update(file_name.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(&self.size.to_le_bytes());
+        hasher.update(&self.modified_secs.to_le_bytes());
+        hasher.update(&self.modified_nanos.to_le_bytes());
+        hasher.update(self.digest.as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// Returns true, if the marker is sealed with `key` for `file_name`, matches its current
+    /// `metadata` and records `digest`.
+    fn is_valid_for(
+        &self,
+        file_name: &str,
+        metadata: &Metadata,
+        digest: &str,
+        key: &[u8; 32],
+    ) -> bool {
+        match VerifiedMarker::new(file_name, metadata, digest, key) {
+            Ok(expected) => *self == expected,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Loads the key verified markers are sealed with from `dir`, generating it if there is none yet.
+/// The key must only be accessible by its owner, otherwise anyone able to read it could forge
+/// markers.
+fn load_verified_marker_key(dir: &Path) -> Result<[u8; 32]> {
+    let key_path = dir.join(VERIFIED_MARKER_KEY_FILE);
+    if !key_path.exists() {
+        create_dir_all(dir)?;
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+
+        // The key is written to a private file first and then linked into place, so that
+        // concurrent processes agree on a single complete key.
+        let tmp_path = dir.join(format!(
+            "{}.{}.tmp",
+            VERIFIED_MARKER_KEY_FILE,
+            std::process::id()
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let written = options
+            .open(&tmp_path)
+            .and_then(|mut file| file.write_all(&key).and_then(|_| file.sync_all()))
+            .and_then(|_| match fs::hard_link(&tmp_path, &key_path) {
+                Err(err) if err.kind() != io::ErrorKind::AlreadyExists => Err(err),
+                _ => Ok(()),
+            });
+        let _ = fs::remove_file(&tmp_path);
+        written?;
+    }
+
+    let key = fs::read(&key_path)?;
+    #[cfg(unix)]
+    {
+        let mode = fs::metadata(&key_path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            bail!("{:?} is accessible by other users", key_path);
+        }
+    }
+
+    key.try_into()
+        .map_err(|_| anyhow::anyhow!("{:?} is not a 32 byte key", key_path))
+}
+
+/// Returns true, if the file is one which gets verified markers. Verifying keys are small, they
+/// are hashed whenever they are verified.
+fn has_verified_marker_ext(cache_entry_path: &Path) -> bool {
+    cache_entry_path.extension() != Some(OsStr::new(VERIFYING_KEY_EXT))
+}
+
+/// The path of the verified marker of a file of the parameter cache, e.g.
+/// `v28-stacked-proof-of-replication-[...].params.verified`.
+pub fn verified_marker_path(cache_entry_path: &Path) -> PathBuf {
+    let mut file_name = cache_entry_path
+        .file_name()
+        .unwrap_or_default()
+        .to_os_string();
+    file_name.push(".");
+    file_name.push(VERIFIED_MARKER_EXT);
+    cache_entry_path.with_file_name(file_name)
+}
+
+/// Returns true, if the file has a valid verified marker for `digest`, so that it does not need
+/// to be hashed again.
+pub fn has_valid_verified_marker(cache_entry_path: &Path, digest: &str) -> bool {
+    if !has_verified_marker_ext(cache_entry_path) {
+        return false;
+    }
+    let key = match VERIFIED_MARKER_KEY.as_ref() {
+        Some(key) => key,
+        None => return false,
+    };
+    let file_name = match cache_entry_path.file_name().and_then(OsStr::to_str) {
+        Some(file_name) => file_name,
+        None => return false,
+    };
+    let marker: VerifiedMarker = match File::open(verified_marker_path(cache_entry_path))
+        .map_err(anyhow::Error::from)
+        .and_then(|file| serde_json::from_reader(file).map_err(Into::into))
+    {
+        Ok(marker) => marker,
+        Err(_) => return false,
+    };
+
+    match fs::metadata(cache_entry_path) {
+        Ok(metadata) => marker.is_valid_for(file_name, &metadata, digest, key),
+        Err(_) => false,
+    }
+}
+
+/// Writes the verified marker of a file, whose `metadata` was taken before it was hashed. The
+/// marker is replaced atomically, so that concurrent readers never see a partial one. Verifying
+/// keys get no marker.
+fn write_verified_marker(cache_entry_path: &Path, metadata: &Metadata, digest: &str) -> Result<()> {
+    if !has_verified_marker_ext(cache_entry_path) {
+        return Ok(());
+    }
+    let key = VERIFIED_MARKER_KEY
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("no verified marker key"))?;
+    let file_name = cache_entry_path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .ok_or_else(|| anyhow::anyhow!("invalid file name {:?}", cache_entry_path))?;
+    let marker = VerifiedMarker::new(file_name, metadata, digest, key)?;
+
+    let marker_path = verified_marker_path(cache_entry_path);
+    let tmp_path = marker_path.with_extension(format!("{}.tmp", VERIFIED_MARKER_EXT));
+    serde_json::to_writer(File::create(&tmp_path)?, &marker)?;
+    fs::rename(&tmp_path, &marker_path)?;
+
+    Ok(())
+}
+
+/// Removes the verified marker of a file, if there is one.
+fn remove_verified_marker(cache_entry_path: &Path) -> io::Result<()> {
+    match fs::remove_file(verified_marker_path(cache_entry_path)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Hashes a file of the parameter cache through a memory map, under a shared lock. Returns the
+/// truncated digest, as found in the manifests, and the metadata of the file hashed.
+fn mmap_digest(cache_entry_path: &Path) -> io::Result<(String, Metadata)> {
+    let file = LockedFile::open_shared_read(cache_entry_path)?;
+    let metadata = file.as_ref().metadata()?;
+
+    // Empty files can not be mapped.
+    let hash = if metadata.len() == 0 {
+        Blake2bParams::new().hash(&[])
+    } else {
+        let map = unsafe { MmapOptions::new().map(file.as_ref())? };
+        Blake2bParams::new().hash(&map)
+    };
+
+    // The hash in the parameters file is truncated to 256 bits.
+    Ok((hash.to_hex()[..32].to_string(), metadata))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheFileStatus {
+    /// The file matches the manifest, a verified marker was written for it, unless it is a
+    /// verifying key.
+    Verified,
+    /// The file has a valid verified marker and was not hashed.
+    PreviouslyVerified,
+    /// The file is not part of any manifest.
+    Unknown,
+    /// The file does not match the manifest, for the given reason.
+    Invalid(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheFileVerification {
+    pub path: PathBuf,
+    pub status: CacheFileStatus,
+}
+
+/// Verifies all `.params`, `.vk`, `.meta` and `.srs` files of the parameter cache against
+/// `parameters.json` and `srs-inner-product.json`, see `verify_parameter_cache_dir`.
+pub fn verify_parameter_cache(force: bool) -> Result<Vec<CacheFileVerification>> {
+    verify_parameter_cache_dir(
+        &parameter_cache_dir(),
+        &[&PARAMETERS, &SRS_PARAMETERS],
+        force,
+    )
+}
+
+/// Verifies all `.params`, `.vk`, `.meta` and `.srs` files of `dir` in parallel against the given
+/// manifests. The results are sorted by path.
+///
+/// Every file whose digest matches gets a verified marker, which `read_cached_params` and friends
+/// trust instead of hashing the file again. Files with a valid marker are only hashed, if `force`
+/// is set. Verifying keys get no marker and are always hashed. The markers of invalid files are removed. Metadata files have no digest in the
+/// manifests, they are checked against the sector size of their parameters instead.
+pub fn verify_parameter_cache_dir(
+    dir: &Path,
+    manifests: &[&ParameterMap],
+    force: bool,
+) -> Result<Vec<CacheFileVerification>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_cache_entry = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(|ext| {
+                [
+                    GROTH_PARAMETER_EXT,
+                    VERIFYING_KEY_EXT,
+                    PARAMETER_METADATA_EXT,
+                    SRS_KEY_EXT,
+                ]
+                .contains(&ext)
+            })
+            .unwrap_or(false);
+        if is_cache_entry && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths
+        .into_par_iter()
+        .map(|path| {
+            let status = verify_parameter_cache_file(&path, manifests, force);
+            CacheFileVerification { path, status }
+        })
+        .collect())
+}
+
+/// Verifies a single file of the parameter cache against the given manifests, see
+/// `verify_parameter_cache_dir`.
+pub fn verify_parameter_cache_file(
+    path: &Path,
+    manifests: &[&ParameterMap],
+    force: bool,
+) -> CacheFileStatus {
+    let lookup = |cache_key: &str| {
+        manifests
+            .iter()
+            .find_map(|manifest| manifest.get(cache_key))
+    };
+    let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+
+    let status = if path.extension() == Some(OsStr::new(PARAMETER_METADATA_EXT)) {
+        let params_key = Path::new(file_name)
+            .with_extension(GROTH_PARAMETER_EXT)
+            .display()
+            .to_string();
+        match lookup(&params_key) {
+            Some(data) => verify_metadata_file(path, data),
+            None => CacheFileStatus::Unknown,
+        }
+    } else {
+        match lookup(file_name) {
+            Some(data) => verify_digest_file(path, data, force),
+            None => CacheFileStatus::Unknown,
+        }
+    };
+    if let CacheFileStatus::Invalid(reason) = &status {
+        warn!("{:?} is INVALID: {}", path, reason);
+    }
+
+    status
+}
+
+fn verify_digest_file(path: &Path, data: &ParameterData, force: bool) -> CacheFileStatus {
+    if !force && has_valid_verified_marker(path, &data.digest) {
+        return CacheFileStatus::PreviouslyVerified;
+    }
+
+    info!("generating consistency digest for {:?}", path);
+    let status = match mmap_digest(path) {
+        Ok((digest, metadata)) if digest == data.digest => {
+            if let Err(err) = write_verified_marker(path, &metadata, &digest) {
+                warn!("failed to write verified marker for {:?}: {}", path, err);
+            }
+            return CacheFileStatus::Verified;
+        }
+        Ok((digest, _)) => {
+            CacheFileStatus::Invalid(format!("digest {} != {}", digest, data.digest))
+        }
+        Err(err) => CacheFileStatus::Invalid(format!("failed to hash: {}", err)),
+    };
+
+    if let Err(err) = remove_verified_marker(path) {
+        warn!("failed to remove verified marker for {:?}: {}", path, err);
+    }
+    status
+}
+
+fn verify_metadata_file(path: &Path, data: &ParameterData) -> CacheFileStatus {
+    match read_cached_metadata(path) {
+        Ok(meta) if meta.sector_size == data.sector_size => CacheFileStatus::Verified,
+        Ok(meta) => CacheFileStatus::Invalid(format!(
+            "sector size {} != {}",
+            meta.sector_size, data.sector_size
+        )),
+        Err(err) => CacheFileStatus::Invalid(format!("failed to read: {}", err)),
+    }
+}
+
 // This method verifies that the parameter/verifying_key file
 // specified appears in the parameters.json manifest and that the
 // content digest matches the recorded entry.
@@ -422,25 +792,38 @@ pub fn verify_production_entry(
                 .get(&cache_key)
                 .is_none();
             if not_yet_verified {
-                info!("generating consistency digest for parameters");
-                let hash =
-                    with_exclusive_read_lock::<_, io::Error, _>(cache_entry_path, |mut file| {
-                        let mut hasher = Blake2bParams::new().to_state();
-                        io::copy(&mut file, &mut hasher).expect("copying file into hasher failed");
-                        Ok(hasher.finalize())
-                    })?;
-                info!("generated consistency digest for parameters");
-
-                // The hash in the parameters file is truncated to 256 bits.
-                let digest_hex = &hash.to_hex()[..32];
-                if digest_hex != data.digest {
-                    info!("parameter data is INVALID [{}]", digest_hex);
-                    return Err(
-                        Error::InvalidParameters(cache_entry_path.display().to_string()).into(),
-                    );
-                }
+                if has_valid_verified_marker(cache_entry_path, &data.digest) {
+                    info!("parameter data was VERIFIED before [{}]", data.digest);
+                } else {
+                    info!("generating consistency digest for parameters");
+                    let (hash, metadata) = with_exclusive_read_lock::<_, io::Error, _>(
+                        cache_entry_path,
+                        |mut file| {
+                            let metadata = file.as_ref().metadata()?;
+                            let mut hasher = Blake2bParams::new().to_state();
+                            io::copy(&mut file, &mut hasher)
+                                .expect("copying file into hasher failed");
+                            Ok((hasher.finalize(), metadata))
+                        },
+                    )?;
+                    info!("generated consistency digest for parameters");
+
+                    // The hash in the parameters file is truncated to 256 bits.
+                    let digest_hex = &hash.to_hex()[..32];
+                    if digest_hex != data.digest {
+                        info!("parameter data is INVALID [{}]", digest_hex);
+                        return Err(Error::InvalidParameters(
+                            cache_entry_path.display().to_string(),
+                        )
+                        .into());
+                    }
 
-                info!("parameter data is VALID [{}]", digest_hex);
+                    info!("parameter data is VALID [{}]", digest_hex);
+                    if let Err(err) = write_verified_marker(cache_entry_path, &metadata, digest_hex)
+                    {
+                        warn!("failed to write verified marker: {}", err);
+                    }
+                }
                 VERIFIED_PARAMETERS
                     .lock()
                     .expect("verified parameters lock failed")
@@ -676,3 +1059,165 @@ where
     ensure_parent(file_path)?;
     f(&mut open_file(file_path)?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    fn digest(bytes: &[u8]) -> String {
+        Blake2bParams::new().hash(bytes).to_hex()[..32].to_string()
+    }
+
+    fn manifest(entries: &[(&str, &[u8], u64)]) -> ParameterMap {
+        entries
+            .iter()
+            .map(|(name, bytes, sector_size)| {
+                let data = ParameterData {
+                    cid: String::new(),
+                    digest: digest(bytes),
+                    sector_size: *sector_size,
+                };
+                (name.to_string(), data)
+            })
+            .collect()
+    }
+
+    fn statuses(
+        dir: &Path,
+        manifest: &ParameterMap,
+        force: bool,
+    ) -> Vec<(String, CacheFileStatus)> {
+        verify_parameter_cache_dir(dir, &[manifest], force)
+            .expect("verification failed")
+            .into_iter()
+            .map(|verification| {
+                let file_name = verification.path.file_name().expect("no file name");
+                (file_name.to_string_lossy().to_string(), verification.status)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_parameter_cache_dir() {
+        let dir = tempdir().expect("tempdir failed");
+        let params_path = dir.path().join("v28-test.params");
+        let vk_path = dir.path().join("v28-test.vk");
+        fs::write(&params_path, b"params").expect("write failed");
+        fs::write(&vk_path, b"vk").expect("write failed");
+        fs::write(dir.path().join("v28-test.meta"), br#"{"sector_size":2048}"#)
+            .expect("write failed");
+        fs::write(dir.path().join("v28-other.vk"), b"other").expect("write failed");
+        fs::write(dir.path().join("unrelated.txt"), b"").expect("write failed");
+
+        let manifest = manifest(&[
+            ("v28-test.params", b"params", 2048),
+            ("v28-test.vk", b"vk", 2048),
+        ]);
+
+        assert_eq!(
+            statuses(dir.path(), &manifest, false),
+            vec![
+                ("v28-other.vk".to_string(), CacheFileStatus::Unknown),
+                ("v28-test.meta".to_string(), CacheFileStatus::Verified),
+                ("v28-test.params".to_string(), CacheFileStatus::Verified),
+                ("v28-test.vk".to_string(), CacheFileStatus::Verified),
+            ]
+        );
+        assert!(has_valid_verified_marker(&params_path, &digest(b"params")));
+        assert!(!has_valid_verified_marker(&params_path, &digest(b"vk")));
+        assert!(!verified_marker_path(&vk_path).exists());
+
+        // The markers are trusted, unless verification is forced. Verifying keys are always
+        // hashed.
+        let results = statuses(dir.path(), &manifest, false);
+        assert_eq!(results[2].1, CacheFileStatus::PreviouslyVerified);
+        assert_eq!(results[3].1, CacheFileStatus::Verified);
+        let results = statuses(dir.path(), &manifest, true);
+        assert_eq!(results[2].1, CacheFileStatus::Verified);
+
+        // A marker does not apply to another file.
+        fs::copy(
+            verified_marker_path(&params_path),
+            verified_marker_path(&vk_path),
+        )
+        .expect("copy failed");
+        assert!(!has_valid_verified_marker(&vk_path, &digest(b"params")));
+        assert!(!has_valid_verified_marker(&vk_path, &digest(b"vk")));
+
+        // A marker sealed with another key is not trusted.
+        let metadata = fs::metadata(&params_path).expect("metadata failed");
+        let forged =
+            VerifiedMarker::new("v28-test.params", &metadata, &digest(b"params"), &[0; 32])
+                .expect("marker failed");
+        let marker_path = verified_marker_path(&params_path);
+        let valid = fs::read(&marker_path).expect("read failed");
+        fs::write(
+            &marker_path,
+            serde_json::to_vec(&forged).expect("serialize failed"),
+        )
+        .expect("write failed");
+        assert!(!has_valid_verified_marker(&params_path, &digest(b"params")));
+        fs::write(&marker_path, valid).expect("write failed");
+        assert!(has_valid_verified_marker(&params_path, &digest(b"params")));
+
+        // Changing a file invalidates its marker.
+        fs::write(&params_path, b"changed params").expect("write failed");
+        assert!(!has_valid_verified_marker(&params_path, &digest(b"params")));
+        let results = statuses(dir.path(), &manifest, false);
+        assert!(matches!(results[2].1, CacheFileStatus::Invalid(_)));
+        assert!(!verified_marker_path(&params_path).exists());
+    }
+
+    #[test]
+    fn test_load_verified_marker_key() {
+        let dir = tempdir().expect("tempdir failed");
+        let key = load_verified_marker_key(dir.path()).expect("failed to generate key");
+        assert_eq!(
+            load_verified_marker_key(dir.path()).expect("failed to load key"),
+            key
+        );
+        assert_eq!(
+            fs::read_dir(dir.path()).expect("read_dir failed").count(),
+            1
+        );
+
+        #[cfg(unix)]
+        {
+            let key_path = dir.path().join(VERIFIED_MARKER_KEY_FILE);
+            let mut permissions = fs::metadata(&key_path)
+                .expect("metadata failed")
+                .permissions();
+            assert_eq!(permissions.mode() & 0o777, 0o600);
+
+            // A key readable by other users is not used.
+            permissions.set_mode(0o644);
+            fs::set_permissions(&key_path, permissions).expect("set_permissions failed");
+            assert!(load_verified_marker_key(dir.path()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_verify_parameter_cache_dir_detects_invalid_files() {
+        let dir = tempdir().expect("tempdir failed");
+        let params_path = dir.path().join("v28-test.params");
+        fs::write(&params_path, b"params").expect("write failed");
+        fs::write(dir.path().join("v28-test.meta"), br#"{"sector_size":4096}"#)
+            .expect("write failed");
+
+        let valid = manifest(&[("v28-test.params", b"params", 2048)]);
+        let results = statuses(dir.path(), &valid, false);
+        assert!(matches!(results[0].1, CacheFileStatus::Invalid(_)));
+        assert_eq!(results[1].1, CacheFileStatus::Verified);
+        assert!(verified_marker_path(&params_path).exists());
+
+        // A forced verification hashes files with a marker as well, and removes the marker if
+        // the digest does not match.
+        let corrupted = manifest(&[("v28-test.params", b"corrupted", 4096)]);
+        let results = statuses(dir.path(), &corrupted, true);
+        assert_eq!(results[0].1, CacheFileStatus::Verified);
+        assert!(matches!(results[1].1, CacheFileStatus::Invalid(_)));
+        assert!(!verified_marker_path(&params_path).exists());
+    }
+}
@@ -24,7 +24,9 @@ pub struct Settings {
     pub rows_to_discard: u32,
     pub sdr_parents_cache_size: u32,
     pub window_post_synthesis_num_cpus: u32,
+    pub sector_check_num_threads: u32,
     pub parameter_cache: String,
+    pub parameter_sources: String,
     pub parent_cache: String,
     pub use_multicore_sdr: bool,
     pub multicore_sdr_producers: usize,
@@ -46,10 +48,13 @@ impl Default for Settings {
             rows_to_discard: 2,
             sdr_parents_cache_size: 2_048,
             window_post_synthesis_num_cpus: num_cpus::get() as u32,
+            sector_check_num_threads: num_cpus::get() as u32,
             // `parameter_cache` does not use the cache() mechanism because it is now used
             // for durable, canonical Groth parameters and verifying keys.
             // The name is retained for backwards compatibility.
             parameter_cache: "/var/tmp/filecoin-proof-parameters/".to_string(),
+            // The comma-separated sources `paramfetch` downloads parameters from, in order.
+            parameter_sources: "ipfs".to_string(),
             parent_cache: cache("filecoin-parents"),
             use_multicore_sdr: false,
             multicore_sdr_producers: 3,
@@ -44,10 +44,14 @@ impl<Proof: MerkleProofTrait> ColumnProof<Proof> {
         self.inclusion_proof.root()
     }
 
-    fn column(&self) -> &Column<Proof::Hasher> {
+    pub fn column(&self) -> &Column<Proof::Hasher> {
         &self.column
     }
 
+    pub fn inclusion_proof(&self) -> &Proof {
+        &self.inclusion_proof
+    }
+
     pub fn get_node_at_layer(&self, layer: usize) -> Result<&<Proof::Hasher as Hasher>::Domain> {
         self.column().get_node_at_layer(layer)
     }
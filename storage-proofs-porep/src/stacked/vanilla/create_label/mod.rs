@@ -1,11 +1,12 @@
 use std::fs::{self, create_dir_all, remove_file, rename, File};
-use std::io::{self, BufReader};
-use std::path::Path;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
 
-use anyhow::Context;
+use anyhow::{ensure, Context};
 use filecoin_hashers::Hasher;
 use log::{info, warn};
 use merkletree::{merkle::Element, store::StoreConfig};
+use serde::{Deserialize, Serialize};
 use storage_proofs_core::{
     cache_key::CacheKey, drgraph::Graph, error::Result, merkle::MerkleTreeTrait,
     util::default_rows_to_discard,
@@ -109,3 +110,174 @@ pub fn is_layer_written<Tree: 'static + MerkleTreeTrait>(
 
     Ok(true)
 }
+
+/// Durable record of the labeling progress of a sector, stored in the cache directory and
+/// updated after each layer is written to disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelCheckpoint {
+    /// Hex encoded replica id the layers were generated for.
+    pub replica_id: String,
+    /// BLAKE2b digests of the completed layer files, in layer order. The last completed layer is
+    /// `digests.len()`.
+    pub digests: Vec<String>,
+}
+
+impl LabelCheckpoint {
+    pub fn new<T: AsRef<[u8]>>(replica_id: T) -> Self {
+        LabelCheckpoint {
+            replica_id: hex::encode(replica_id.as_ref()),
+            digests: Vec::new(),
+        }
+    }
+
+    /// The last layer which is known to be completely written to disk, `0` if none.
+    pub fn last_layer(&self) -> usize {
+        self.digests.len()
+    }
+
+    pub fn path<P: AsRef<Path>>(cache_path: P) -> PathBuf {
+        cache_path
+            .as_ref()
+            .join(CacheKey::LabelCheckpoint.to_string())
+    }
+
+    /// Reads the checkpoint from `cache_path`, returns `None` if there is none.
+    pub fn read<P: AsRef<Path>>(cache_path: P) -> Result<Option<Self>> {
+        let path = Self::path(cache_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(&path)
+            .with_context(|| format!("failed to read label checkpoint {:?}", path))?;
+        let checkpoint = serde_json::from_slice(&bytes)
+            .with_context(|| format!("failed to parse label checkpoint {:?}", path))?;
+
+        Ok(Some(checkpoint))
+    }
+
+    /// Stores the checkpoint atomically, by writing first to `.tmp` and then renaming.
+    pub fn write<P: AsRef<Path>>(&self, cache_path: P) -> Result<()> {
+        let path = Self::path(cache_path);
+        let tmp_path = path.with_extension("tmp");
+        let bytes = serde_json::to_vec(self).context("failed to serialize label checkpoint")?;
+        fs::write(&tmp_path, bytes).context("failed to write label checkpoint")?;
+        rename(tmp_path, path).context("failed to rename tmp label checkpoint")?;
+
+        Ok(())
+    }
+
+    pub fn remove<P: AsRef<Path>>(cache_path: P) -> Result<()> {
+        let path = Self::path(cache_path);
+        if path.exists() {
+            remove_file(&path)
+                .with_context(|| format!("failed to remove label checkpoint {:?}", path))?;
+        }
+
+        Ok(())
+    }
+
+    /// Records `data` as the content of `layer` and persists the checkpoint. Layers which are
+    /// already recorded were validated on resume and are not hashed again.
+    pub fn complete_layer<P: AsRef<Path>>(
+        &mut self,
+        cache_path: P,
+        layer: usize,
+        data: &[u8],
+    ) -> Result<()> {
+        if layer <= self.last_layer() {
+            return Ok(());
+        }
+        ensure!(
+            layer == self.last_layer() + 1,
+            "label checkpoint is missing layer {}",
+            self.last_layer() + 1
+        );
+        self.digests.push(layer_digest(data));
+        self.write(cache_path)
+    }
+}
+
+fn layer_digest(data: &[u8]) -> String {
+    blake2b_simd::blake2b(data).to_hex().to_string()
+}
+
+fn layer_file_digest(config: &StoreConfig) -> Result<String> {
+    let data_path = StoreConfig::data_path(&config.path, &config.id);
+    let mut file = File::open(&data_path).context("failed to open layer")?;
+    let mut hasher = blake2b_simd::State::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut buf).context("failed to read layer")?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Validates the layers recorded in the label checkpoint in `cache_path` against their digests,
+/// so that labeling can resume after the last valid layer.
+///
+/// If a recorded layer is missing or corrupt, it and all following layers are removed from disk,
+/// as each layer depends on its predecessor, and the checkpoint is truncated accordingly. Layers
+/// past the recorded ones are left in place and picked up like on a fresh run. Returns the
+/// checkpoint to continue labeling with.
+pub fn validate_checkpoint<P, Tree: 'static + MerkleTreeTrait>(
+    graph: &StackedBucketGraph<Tree::Hasher>,
+    cache_path: P,
+    layers: usize,
+    replica_id: &[u8],
+) -> Result<LabelCheckpoint>
+where
+    P: AsRef<Path>,
+{
+    let mut checkpoint = match LabelCheckpoint::read(&cache_path)? {
+        Some(checkpoint) => {
+            ensure!(
+                checkpoint.replica_id == hex::encode(replica_id),
+                "label checkpoint belongs to a different replica id"
+            );
+            checkpoint
+        }
+        None => return Ok(LabelCheckpoint::new(replica_id)),
+    };
+    ensure!(
+        checkpoint.last_layer() <= layers,
+        "label checkpoint has {} layers, expected at most {}",
+        checkpoint.last_layer(),
+        layers
+    );
+
+    let layer_states = prepare_layers::<_, Tree>(graph, &cache_path, layers);
+
+    let mut valid = 0;
+    for (layer_state, digest) in layer_states.iter().zip(checkpoint.digests.iter()) {
+        if !layer_state.generated || layer_file_digest(&layer_state.config)? != *digest {
+            warn!("layer {} does not match the label checkpoint", valid + 1);
+            break;
+        }
+        valid += 1;
+    }
+    info!(
+        "label checkpoint: {} of {} recorded layers valid",
+        valid,
+        checkpoint.last_layer()
+    );
+
+    if valid < checkpoint.last_layer() {
+        for layer_state in &layer_states[valid..] {
+            let data_path =
+                StoreConfig::data_path(&layer_state.config.path, &layer_state.config.id);
+            if data_path.exists() {
+                remove_file(&data_path)
+                    .with_context(|| format!("failed to remove layer {:?}", data_path))?;
+            }
+        }
+        checkpoint.digests.truncate(valid);
+        checkpoint.write(&cache_path)?;
+    }
+
+    Ok(checkpoint)
+}
@@ -2,6 +2,7 @@ use std::convert::TryInto;
 use std::marker::PhantomData;
 use std::mem::{self, size_of};
 use std::path::Path;
+use std::slice;
 use std::sync::{
     atomic::{AtomicU64, Ordering::SeqCst},
     Arc, MutexGuard,
@@ -9,7 +10,7 @@ use std::sync::{
 use std::thread;
 use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use byte_slice_cast::{AsByteSlice, AsMutSliceOf};
 use filecoin_hashers::Hasher;
 use generic_array::{
@@ -30,9 +31,9 @@ use storage_proofs_core::{
 use crate::stacked::vanilla::{
     cache::ParentCache,
     cores::{bind_core, checkout_core_group, CoreIndex},
-    create_label::{prepare_layers, read_layer, write_layer},
-    graph::{StackedBucketGraph, DEGREE, EXP_DEGREE},
-    memory_handling::{setup_create_label_memory, CacheReader},
+    create_label::{prepare_layers, read_layer, write_layer, LabelCheckpoint},
+    graph::{StackedBucketGraph, DEGREE},
+    memory_handling::{setup_batch_create_label_memory, setup_create_label_memory, CacheReader},
     params::{Labels, LabelsCache},
     proof::LayerState,
     utils::{memset, prepare_block, BitMask, RingBuf, UnsafeSlice},
@@ -138,11 +139,15 @@ fn fill_buffer(
 // - base_parent_missing - Bit mask of any base parent nodes that could not
 //                         be filled in. This is an array of size lookahead.
 // - is_layer0    - Indicates first (no expander parents) or subsequent layer
+//
+// When labeling a batch of sectors, the parents of a node are read once from
+// the cache and used to fill the buffers of every sector. The ring_buf then
+// holds one slot per node and sector, base_parent_missing likewise.
 #[allow(clippy::too_many_arguments)]
 fn create_label_runner(
     parents_cache: &CacheReader<u32>,
-    layer_labels: &UnsafeSlice<'_, u32>,
-    exp_labels: Option<&UnsafeSlice<'_, u32>>, // None for layer 0
+    layer_labels: &[UnsafeSlice<'_, u32>],
+    exp_labels: Option<&[UnsafeSlice<'_, u32>]>, // None for layer 0
     num_nodes: u64,
     cur_producer: &AtomicU64,
     cur_awaiting: &AtomicU64,
@@ -152,6 +157,7 @@ fn create_label_runner(
     base_parent_missing: &UnsafeSlice<'_, BitMask>,
 ) {
     info!("created label runner");
+    let num_sectors = layer_labels.len();
     // Label data bytes per node
     loop {
         // Get next work items
@@ -176,19 +182,22 @@ fn create_label_runner(
                 thread::sleep(Duration::from_micros(10));
             }
 
-            let buf = unsafe { ring_buf.slot_mut(cur_slot as usize) };
-            let bpm = unsafe { base_parent_missing.get_mut(cur_slot as usize) };
-
             let pc = unsafe { parents_cache.slice_at(cur_node as usize * DEGREE) };
-            fill_buffer(
-                cur_node,
-                parents_cache,
-                pc,
-                layer_labels,
-                exp_labels,
-                buf,
-                bpm,
-            );
+            for sector in 0..num_sectors {
+                let sector_slot = cur_slot as usize * num_sectors + sector;
+                let buf = unsafe { ring_buf.slot_mut(sector_slot) };
+                let bpm = unsafe { base_parent_missing.get_mut(sector_slot) };
+
+                fill_buffer(
+                    cur_node,
+                    parents_cache,
+                    pc,
+                    &layer_labels[sector],
+                    exp_labels.map(|exp_labels| &exp_labels[sector]),
+                    buf,
+                    bpm,
+                );
+            }
         }
 
         // Wait for the previous node to finish
@@ -201,16 +210,29 @@ fn create_label_runner(
     }
 }
 
+/// Labels layer `cur_layer` of every sector in the batch, each sector is defined by its replica id
+/// and the matching entries of `layer_labels` and `exp_labels`.
 fn create_layer_labels(
     parents_cache: &CacheReader<u32>,
-    replica_id: &[u8],
-    layer_labels: &mut MmapMut,
-    exp_labels: Option<&mut MmapMut>,
+    replica_ids: &[&[u8]],
+    layer_labels: &mut [MmapMut],
+    exp_labels: Option<&mut [MmapMut]>,
     num_nodes: u64,
     cur_layer: u32,
     core_group: Arc<Option<MutexGuard<'_, Vec<CoreIndex>>>>,
 ) {
-    info!("Creating labels for layer {}", cur_layer);
+    info!(
+        "Creating labels for layer {} of {} sector(s)",
+        cur_layer,
+        replica_ids.len()
+    );
+    assert_eq!(
+        replica_ids.len(),
+        layer_labels.len(),
+        "missing layer labels"
+    );
+    let num_sectors = replica_ids.len();
+
     // num_producers is the number of producer threads
     let (lookahead, num_producers, producer_stride) = {
         let settings = &SETTINGS;
@@ -227,12 +249,13 @@ fn create_layer_labels(
 
     const BYTES_PER_NODE: usize = (NODE_SIZE * DEGREE) + SHA_BLOCK_SIZE;
 
-    let mut ring_buf = RingBuf::new(BYTES_PER_NODE, lookahead);
-    let mut base_parent_missing = vec![BitMask::default(); lookahead];
+    // Slot `node_slot * num_sectors + sector` holds the buffer of `sector`.
+    let mut ring_buf = RingBuf::new(BYTES_PER_NODE, lookahead * num_sectors);
+    let mut base_parent_missing = vec![BitMask::default(); lookahead * num_sectors];
 
     // Fill in the fixed portion of all buffers
-    for buf in ring_buf.iter_slot_mut() {
-        prepare_block(replica_id, cur_layer, buf);
+    for (i, buf) in ring_buf.iter_slot_mut().enumerate() {
+        prepare_block(replica_ids[i % num_sectors], cur_layer, buf);
     }
 
     // Highest node that is ready from the producer
@@ -242,13 +265,19 @@ fn create_layer_labels(
 
     // These UnsafeSlices are managed through the 2 Atomics above and the `CacheReader`, to
     // minimize any locking overhead.
-    let layer_labels = UnsafeSlice::from_slice(
-        layer_labels
-            .as_mut_slice_of::<u32>()
-            .expect("failed as mut slice of"),
-    );
-    let exp_labels = exp_labels.map(|m| {
-        UnsafeSlice::from_slice(m.as_mut_slice_of::<u32>().expect("failed as mut slice of"))
+    let layer_labels: Vec<_> = layer_labels
+        .iter_mut()
+        .map(|m| {
+            UnsafeSlice::from_slice(m.as_mut_slice_of::<u32>().expect("failed as mut slice of"))
+        })
+        .collect();
+    let exp_labels: Option<Vec<_>> = exp_labels.map(|exp_labels| {
+        exp_labels
+            .iter_mut()
+            .map(|m| {
+                UnsafeSlice::from_slice(m.as_mut_slice_of::<u32>().expect("failed as mut slice of"))
+            })
+            .collect()
     });
     let base_parent_missing = UnsafeSlice::from_slice(&mut base_parent_missing);
 
@@ -256,8 +285,8 @@ fn create_layer_labels(
         let mut runners = Vec::with_capacity(num_producers);
 
         for i in 0..num_producers {
-            let layer_labels = &layer_labels;
-            let exp_labels = exp_labels.as_ref();
+            let layer_labels = &layer_labels[..];
+            let exp_labels = exp_labels.as_deref();
             let cur_producer = &cur_producer;
             let cur_awaiting = &cur_awaiting;
             let ring_buf = &ring_buf;
@@ -290,23 +319,25 @@ fn create_layer_labels(
             }));
         }
 
-        let mut cur_node_ptr = unsafe { layer_labels.as_mut_slice() };
         let mut cur_parent_ptr = unsafe { parents_cache.consumer_slice_at(DEGREE) };
         let mut cur_parent_ptr_offset = DEGREE;
 
         // Calculate node 0 (special case with no parents)
         // Which is replica_id || cur_layer || 0
         // TODO - Hash and save intermediate result: replica_id || cur_layer
-        let mut buf = [0u8; (NODE_SIZE * DEGREE) + 64];
-        prepare_block(replica_id, cur_layer, &mut buf);
+        for (replica_id, layer_labels) in replica_ids.iter().zip(layer_labels.iter()) {
+            let cur_node_ptr = unsafe { layer_labels.as_mut_slice() };
+            let mut buf = [0u8; (NODE_SIZE * DEGREE) + 64];
+            prepare_block(replica_id, cur_layer, &mut buf);
 
-        cur_node_ptr[..8].copy_from_slice(&SHA256_INITIAL_DIGEST);
-        compress256!(cur_node_ptr, buf, 2);
+            cur_node_ptr[..8].copy_from_slice(&SHA256_INITIAL_DIGEST);
+            compress256!(cur_node_ptr, buf, 2);
 
-        // Fix endianess
-        cur_node_ptr[..8].iter_mut().for_each(|x| *x = x.to_be());
+            // Fix endianess
+            cur_node_ptr[..8].iter_mut().for_each(|x| *x = x.to_be());
 
-        cur_node_ptr[7] &= 0x3FFF_FFFF; // Strip last two bits to ensure in Fr
+            cur_node_ptr[7] &= 0x3FFF_FFFF; // Strip last two bits to ensure in Fr
+        }
 
         // Keep track of which node slot in the ring_buffer to use
         let mut cur_slot = 0;
@@ -343,76 +374,80 @@ fn create_layer_labels(
                     }
                 }
 
-                cur_node_ptr = &mut cur_node_ptr[8..];
-                // Grab the current slot of the ring_buf
-                let buf = unsafe { ring_buf.slot_mut(cur_slot) };
-                // Fill in the base parents
-                for k in 0..BASE_DEGREE {
-                    let bpm = unsafe { base_parent_missing.get(cur_slot) };
-                    if bpm.get(k) {
-                        let source = unsafe {
-                            let start = cur_parent_ptr[0] as usize * NODE_WORDS;
-                            let end = start + NODE_WORDS;
-                            &layer_labels.as_slice()[start..end]
-                        };
-
-                        buf[64 + (NODE_SIZE * k)..64 + (NODE_SIZE * (k + 1))]
-                            .copy_from_slice(source.as_byte_slice());
+                // The base parents of this node are shared by all sectors.
+                let base_parents = &cur_parent_ptr[..BASE_DEGREE];
+                for (sector, layer_labels) in layer_labels.iter().enumerate() {
+                    let sector_slot = cur_slot * num_sectors + sector;
+                    let cur_node_ptr =
+                        unsafe { &mut layer_labels.as_mut_slice()[i as usize * NODE_WORDS..] };
+                    // Grab the current slot of the ring_buf
+                    let buf = unsafe { ring_buf.slot_mut(sector_slot) };
+                    // Fill in the base parents
+                    let bpm = unsafe { base_parent_missing.get(sector_slot) };
+                    for (k, parent) in base_parents.iter().enumerate() {
+                        if bpm.get(k) {
+                            let source = unsafe {
+                                let start = *parent as usize * NODE_WORDS;
+                                let end = start + NODE_WORDS;
+                                &layer_labels.as_slice()[start..end]
+                            };
+
+                            buf[64 + (NODE_SIZE * k)..64 + (NODE_SIZE * (k + 1))]
+                                .copy_from_slice(source.as_byte_slice());
+                        }
                     }
-                    cur_parent_ptr = &cur_parent_ptr[1..];
-                    cur_parent_ptr_offset += 1;
-                }
 
-                // Expanders are already all filled in (layer 1 doesn't use expanders)
-                cur_parent_ptr = &cur_parent_ptr[EXP_DEGREE..];
-                cur_parent_ptr_offset += EXP_DEGREE;
-
-                if cur_layer == 1 {
-                    // Six rounds of all base parents
-                    for _j in 0..6 {
-                        compress256!(cur_node_ptr, &buf[64..], 3);
+                    // Expanders are already all filled in (layer 1 doesn't use expanders)
+                    if cur_layer == 1 {
+                        // Six rounds of all base parents
+                        for _j in 0..6 {
+                            compress256!(cur_node_ptr, &buf[64..], 3);
+                        }
+
+                        // round 7 is only first parent
+                        memset(&mut buf[96..128], 0); // Zero out upper half of last block
+                        buf[96] = 0x80; // Padding
+                        buf[126] = 0x27; // Length (0x2700 = 9984 bits -> 1248 bytes)
+                        compress256!(cur_node_ptr, &buf[64..], 1);
+                    } else {
+                        // Two rounds of all parents
+                        let blocks = [
+                            *GenericArray::<u8, U64>::from_slice(&buf[64..128]),
+                            *GenericArray::<u8, U64>::from_slice(&buf[128..192]),
+                            *GenericArray::<u8, U64>::from_slice(&buf[192..256]),
+                            *GenericArray::<u8, U64>::from_slice(&buf[256..320]),
+                            *GenericArray::<u8, U64>::from_slice(&buf[320..384]),
+                            *GenericArray::<u8, U64>::from_slice(&buf[384..448]),
+                            *GenericArray::<u8, U64>::from_slice(&buf[448..512]),
+                        ];
+                        sha2::compress256(
+                            (&mut cur_node_ptr[..8])
+                                .try_into()
+                                .expect("compress failed"),
+                            &blocks,
+                        );
+                        sha2::compress256(
+                            (&mut cur_node_ptr[..8])
+                                .try_into()
+                                .expect("compress failed"),
+                            &blocks,
+                        );
+
+                        // Final round is only nine parents
+                        memset(&mut buf[352..384], 0); // Zero out upper half of last block
+                        buf[352] = 0x80; // Padding
+                        buf[382] = 0x27; // Length (0x2700 = 9984 bits -> 1248 bytes)
+                        compress256!(cur_node_ptr, &buf[64..], 5);
                     }
 
-                    // round 7 is only first parent
-                    memset(&mut buf[96..128], 0); // Zero out upper half of last block
-                    buf[96] = 0x80; // Padding
-                    buf[126] = 0x27; // Length (0x2700 = 9984 bits -> 1248 bytes)
-                    compress256!(cur_node_ptr, &buf[64..], 1);
-                } else {
-                    // Two rounds of all parents
-                    let blocks = [
-                        *GenericArray::<u8, U64>::from_slice(&buf[64..128]),
-                        *GenericArray::<u8, U64>::from_slice(&buf[128..192]),
-                        *GenericArray::<u8, U64>::from_slice(&buf[192..256]),
-                        *GenericArray::<u8, U64>::from_slice(&buf[256..320]),
-                        *GenericArray::<u8, U64>::from_slice(&buf[320..384]),
-                        *GenericArray::<u8, U64>::from_slice(&buf[384..448]),
-                        *GenericArray::<u8, U64>::from_slice(&buf[448..512]),
-                    ];
-                    sha2::compress256(
-                        (&mut cur_node_ptr[..8])
-                            .try_into()
-                            .expect("compress failed"),
-                        &blocks,
-                    );
-                    sha2::compress256(
-                        (&mut cur_node_ptr[..8])
-                            .try_into()
-                            .expect("compress failed"),
-                        &blocks,
-                    );
-
-                    // Final round is only nine parents
-                    memset(&mut buf[352..384], 0); // Zero out upper half of last block
-                    buf[352] = 0x80; // Padding
-                    buf[382] = 0x27; // Length (0x2700 = 9984 bits -> 1248 bytes)
-                    compress256!(cur_node_ptr, &buf[64..], 5);
-                }
+                    // Fix endianess
+                    cur_node_ptr[..8].iter_mut().for_each(|x| *x = x.to_be());
 
-                // Fix endianess
-                cur_node_ptr[..8].iter_mut().for_each(|x| *x = x.to_be());
+                    cur_node_ptr[7] &= 0x3FFF_FFFF; // Strip last two bits to fit in Fr
+                }
 
-                cur_node_ptr[7] &= 0x3FFF_FFFF; // Strip last two bits to fit in Fr
+                cur_parent_ptr = &cur_parent_ptr[DEGREE..];
+                cur_parent_ptr_offset += DEGREE;
 
                 // Safety:
                 // It's possible that this increment will trigger moving the cache window.
@@ -447,10 +482,57 @@ pub fn create_labels_for_encoding<
     layers: usize,
     replica_id: T,
     cache_path: P,
+    checkpoint: Option<&mut LabelCheckpoint>,
 ) -> Result<(Labels<Tree>, Vec<LayerState>)> {
-    info!("create labels");
+    let mut labels = create_labels_for_encoding_batch::<Tree, _, _>(
+        graph,
+        parents_cache,
+        layers,
+        &[replica_id],
+        &[cache_path],
+        checkpoint.map(std::slice::from_mut),
+    )?;
+
+    Ok(labels.remove(0))
+}
+
+/// Labels several sectors in lockstep, reading the parents of every node only once.
+///
+/// Each sector is defined by its replica id and cache path, both slices must be of the same
+/// length. A layer is only skipped if it was already generated for every sector of the batch,
+/// otherwise it is labeled (again) for all of them. If `checkpoints` are given, one per sector,
+/// they are updated after each completed layer.
+#[allow(clippy::type_complexity)]
+pub fn create_labels_for_encoding_batch<
+    Tree: 'static + MerkleTreeTrait,
+    T: AsRef<[u8]>,
+    P: AsRef<Path>,
+>(
+    graph: &StackedBucketGraph<Tree::Hasher>,
+    parents_cache: &ParentCache,
+    layers: usize,
+    replica_ids: &[T],
+    cache_paths: &[P],
+    mut checkpoints: Option<&mut [LabelCheckpoint]>,
+) -> Result<Vec<(Labels<Tree>, Vec<LayerState>)>> {
+    info!("create labels for {} sector(s)", replica_ids.len());
+    ensure!(!replica_ids.is_empty(), "no sectors to label");
+    ensure!(
+        replica_ids.len() == cache_paths.len(),
+        "number of replica ids and cache paths must match"
+    );
+    if let Some(checkpoints) = checkpoints.as_deref() {
+        ensure!(
+            checkpoints.len() == replica_ids.len(),
+            "number of replica ids and label checkpoints must match"
+        );
+    }
 
-    let layer_states = prepare_layers::<_, Tree>(graph, &cache_path, layers);
+    let layer_states: Vec<_> = cache_paths
+        .iter()
+        .map(|cache_path| prepare_layers::<_, Tree>(graph, cache_path, layers))
+        .collect();
+    let replica_ids: Vec<&[u8]> = replica_ids.iter().map(AsRef::as_ref).collect();
 
     let sector_size = graph.size() * NODE_SIZE;
     let node_count = graph.size() as u64;
@@ -468,22 +550,31 @@ pub fn create_labels_for_encoding<
         group.get(0).map(|core_index| bind_core(*core_index))
     });
 
-    // NOTE: this means we currently keep 2x sector size around, to improve speed
-    let (parents_cache, mut layer_labels, mut exp_labels) = setup_create_label_memory(
+    // NOTE: this means we currently keep 2x sector size around per sector, to improve speed
+    let (parents_cache, mut layer_labels, mut exp_labels) = setup_batch_create_label_memory(
         sector_size,
         DEGREE,
         Some(default_cache_size),
         &parents_cache.path,
+        replica_ids.len(),
     )?;
 
-    for (layer, layer_state) in (1..=layers).zip(layer_states.iter()) {
+    for layer in 1..=layers {
         info!("Layer {}", layer);
 
-        if layer_state.generated {
+        if layer_states
+            .iter()
+            .all(|states| states[layer - 1].generated)
+        {
             info!("skipping layer {}, already generated", layer);
 
             // load the already generated layer into exp_labels
-            read_layer(&layer_state.config, &mut exp_labels)?;
+            for (states, exp_labels) in layer_states.iter().zip(exp_labels.iter_mut()) {
+                read_layer(&states[layer - 1].config, exp_labels)?;
+            }
+            if let Some(checkpoints) = checkpoints.as_deref_mut() {
+                record_layer(checkpoints, cache_paths, layer, &exp_labels)?;
+            }
             continue;
         }
 
@@ -495,7 +586,7 @@ pub fn create_labels_for_encoding<
 
         create_layer_labels(
             &parents_cache,
-            replica_id.as_ref(),
+            &replica_ids,
             &mut layer_labels,
             if layer == 1 {
                 None
@@ -514,26 +605,48 @@ pub fn create_labels_for_encoding<
         }
 
         mem::swap(&mut layer_labels, &mut exp_labels);
-        {
-            let layer_config = &layer_state.config;
+        for (states, exp_labels) in layer_states.iter().zip(exp_labels.iter()) {
+            let layer_config = &states[layer - 1].config;
 
             info!("  storing labels on disk");
-            write_layer(&exp_labels, layer_config).context("failed to store labels")?;
+            write_layer(exp_labels, layer_config).context("failed to store labels")?;
 
             info!(
                 "  generated layer {} store with id {}",
                 layer, layer_config.id
             );
         }
+        if let Some(checkpoints) = checkpoints.as_deref_mut() {
+            record_layer(checkpoints, cache_paths, layer, &exp_labels)
+                .context("failed to store label checkpoint")?;
+        }
     }
 
-    Ok((
-        Labels::<Tree> {
-            labels: layer_states.iter().map(|s| s.config.clone()).collect(),
-            _h: PhantomData,
-        },
-        layer_states,
-    ))
+    Ok(layer_states
+        .into_iter()
+        .map(|states| {
+            (
+                Labels::<Tree> {
+                    labels: states.iter().map(|s| s.config.clone()).collect(),
+                    _h: PhantomData,
+                },
+                states,
+            )
+        })
+        .collect())
+}
+
+fn record_layer<P: AsRef<Path>>(
+    checkpoints: &mut [LabelCheckpoint],
+    cache_paths: &[P],
+    layer: usize,
+    labels: &[MmapMut],
+) -> Result<()> {
+    for ((checkpoint, cache_path), labels) in checkpoints.iter_mut().zip(cache_paths).zip(labels) {
+        checkpoint.complete_layer(cache_path, layer, labels)?;
+    }
+
+    Ok(())
 }
 
 #[allow(clippy::type_complexity)]
@@ -585,12 +698,12 @@ pub fn create_labels_for_decoding<Tree: 'static + MerkleTreeTrait, T: AsRef<[u8]
 
         create_layer_labels(
             &parents_cache,
-            replica_id.as_ref(),
-            &mut layer_labels,
+            &[replica_id.as_ref()],
+            slice::from_mut(&mut layer_labels),
             if layer == 1 {
                 None
             } else {
-                Some(&mut exp_labels)
+                Some(slice::from_mut(&mut exp_labels))
             },
             node_count,
             layer as u32,
@@ -648,6 +761,8 @@ mod tests {
     use storage_proofs_core::{api_version::ApiVersion, merkle::LCTree};
     use tempfile::tempdir;
 
+    use crate::stacked::vanilla::{create_label::single, graph::EXP_DEGREE};
+
     #[test]
     fn test_create_labels() {
         let layers = 11;
@@ -719,6 +834,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_create_labels_batch() {
+        type Tree = LCTree<PoseidonHasher, U8, U0, U2>;
+
+        let layers = 3;
+        // Enough nodes to have base parents filled in by the producers.
+        let nodes = 1 << 12;
+        let replica_ids = [[9u8; 32], [21u8; 32], [42u8; 32]];
+
+        let graph = StackedBucketGraph::<PoseidonHasher>::new(
+            None,
+            nodes,
+            BASE_DEGREE,
+            EXP_DEGREE,
+            [123; 32],
+            ApiVersion::V1_1_0,
+        )
+        .expect("stacked bucket graph new failed");
+        let mut cache = graph.parent_cache().expect("parent_cache failed");
+
+        let cache_dirs: Vec<_> = replica_ids
+            .iter()
+            .map(|_| tempdir().expect("tempdir failure"))
+            .collect();
+        let batch = create_labels_for_encoding_batch::<Tree, _, _>(
+            &graph,
+            &cache,
+            layers,
+            &replica_ids,
+            &cache_dirs.iter().map(|dir| dir.path()).collect::<Vec<_>>(),
+            None,
+        )
+        .expect("create_labels_for_encoding_batch failed");
+        assert_eq!(batch.len(), replica_ids.len());
+
+        for (replica_id, (labels, _)) in replica_ids.iter().zip(batch) {
+            let cache_dir = tempdir().expect("tempdir failure");
+            let (expected, _) = single::create_labels_for_encoding::<Tree, _, _>(
+                &graph,
+                &mut cache,
+                layers,
+                replica_id,
+                cache_dir.path(),
+                None,
+            )
+            .expect("create_labels_for_encoding failed");
+
+            for (layer, (actual, expected)) in
+                labels.labels.iter().zip(&expected.labels).enumerate()
+            {
+                let actual = std::fs::read(StoreConfig::data_path(&actual.path, &actual.id))
+                    .expect("failed to read batch layer");
+                let expected = std::fs::read(StoreConfig::data_path(&expected.path, &expected.id))
+                    .expect("failed to read layer");
+                assert_eq!(actual, expected, "layer {} differs", layer + 1);
+            }
+        }
+    }
+
     fn test_create_labels_aux(
         sector_size: usize,
         layers: usize,
@@ -16,7 +16,7 @@ use storage_proofs_core::{
 
 use crate::stacked::vanilla::{
     cache::ParentCache,
-    create_label::{prepare_layers, read_layer, write_layer},
+    create_label::{prepare_layers, read_layer, write_layer, LabelCheckpoint},
     proof::LayerState,
     Labels, LabelsCache, StackedBucketGraph,
 };
@@ -32,6 +32,7 @@ pub fn create_labels_for_encoding<
     layers: usize,
     replica_id: T,
     cache_path: P,
+    mut checkpoint: Option<&mut LabelCheckpoint>,
 ) -> Result<(Labels<Tree>, Vec<LayerState>)> {
     info!("generate labels");
 
@@ -49,6 +50,9 @@ pub fn create_labels_for_encoding<
 
             // load the already generated layer into exp_labels
             read_layer(&layer_state.config, &mut exp_labels)?;
+            if let Some(checkpoint) = checkpoint.as_deref_mut() {
+                checkpoint.complete_layer(&cache_path, layer, &exp_labels)?;
+            }
             continue;
         }
 
@@ -84,6 +88,11 @@ pub fn create_labels_for_encoding<
 
         info!("  storing labels on disk");
         write_layer(&layer_labels, layer_config).context("failed to store labels")?;
+        if let Some(checkpoint) = checkpoint.as_deref_mut() {
+            checkpoint
+                .complete_layer(&cache_path, layer, &layer_labels)
+                .context("failed to store label checkpoint")?;
+        }
 
         info!(
             "  generated layer {} store with id {}",
@@ -28,6 +28,18 @@ impl<H: Hasher> EncodingProof<H> {
         }
     }
 
+    pub fn layer_index(&self) -> u32 {
+        self.layer_index
+    }
+
+    pub fn node(&self) -> u64 {
+        self.node
+    }
+
+    pub fn parents(&self) -> &[H::Domain] {
+        &self.parents
+    }
+
     fn create_key(&self, replica_id: &H::Domain) -> H::Domain {
         let mut hasher = Sha256::new();
         let mut buffer = [0u8; 64];
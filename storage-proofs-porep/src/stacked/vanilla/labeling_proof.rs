@@ -25,6 +25,18 @@ impl<H: Hasher> LabelingProof<H> {
         }
     }
 
+    pub fn layer_index(&self) -> u32 {
+        self.layer_index
+    }
+
+    pub fn node(&self) -> u64 {
+        self.node
+    }
+
+    pub fn parents(&self) -> &[H::Domain] {
+        &self.parents
+    }
+
     fn create_label(&self, replica_id: &H::Domain) -> H::Domain {
         let mut hasher = Sha256::new();
         let mut buffer = [0u8; 64];
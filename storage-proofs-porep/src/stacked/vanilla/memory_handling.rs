@@ -304,3 +304,23 @@ pub fn setup_create_label_memory(
 
     Ok((parents_cache, layer_labels, exp_labels))
 }
+
+/// Like `setup_create_label_memory`, but allocates the two layers for each of `num_sectors`
+/// sectors, which are labeled together while sharing the parents cache.
+pub fn setup_batch_create_label_memory(
+    sector_size: usize,
+    degree: usize,
+    window_size: Option<usize>,
+    cache_path: &Path,
+    num_sectors: usize,
+) -> Result<(CacheReader<u32>, Vec<MmapMut>, Vec<MmapMut>)> {
+    let parents_cache = CacheReader::new(cache_path, window_size, degree)?;
+    let layer_labels = (0..num_sectors)
+        .map(|_| allocate_layer(sector_size))
+        .collect::<Result<_>>()?;
+    let exp_labels = (0..num_sectors)
+        .map(|_| allocate_layer(sector_size))
+        .collect::<Result<_>>()?;
+
+    Ok((parents_cache, layer_labels, exp_labels))
+}
@@ -28,6 +28,7 @@ pub use challenges::{
 };
 pub use column::Column;
 pub use column_proof::ColumnProof;
+pub use create_label::LabelCheckpoint;
 pub use encoding_proof::EncodingProof;
 pub use graph::{StackedBucketGraph, StackedGraph, EXP_DEGREE};
 pub use labeling_proof::LabelingProof;
@@ -30,9 +30,9 @@ use storage_proofs_core::{
 };
 
 use crate::stacked::vanilla::{
-    Column, ColumnProof, EncodingProof, LabelingProof, LayerChallenges, StackedBucketGraph,
-    EXP_DEGREE, SYNTHETIC_POREP_VANILLA_PROOFS_EXT, SYNTHETIC_POREP_VANILLA_PROOFS_KEY,
-    TOTAL_PARENTS,
+    Column, ColumnProof, EncodingProof, LabelCheckpoint, LabelingProof, LayerChallenges,
+    StackedBucketGraph, EXP_DEGREE, SYNTHETIC_POREP_VANILLA_PROOFS_EXT,
+    SYNTHETIC_POREP_VANILLA_PROOFS_KEY, TOTAL_PARENTS,
 };
 
 pub const BINARY_ARITY: usize = 2;
@@ -959,6 +959,9 @@ impl<Tree: MerkleTreeTrait, G: Hasher> TemporaryAux<Tree, G> {
                 trace!("layer {} deleted", i);
             }
         }
+        if let Some(config) = self.labels.labels.first() {
+            LabelCheckpoint::remove(&config.path).context("label checkpoint")?;
+        }
 
         Ok(())
     }
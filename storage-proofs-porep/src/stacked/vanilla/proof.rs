@@ -16,8 +16,9 @@ use generic_array::typenum::{Unsigned, U0, U11, U2, U8};
 use lazy_static::lazy_static;
 use log::{error, info, trace, warn};
 use merkletree::{
+    hash::Algorithm,
     merkle::{get_merkle_tree_len, is_merkle_tree_size_valid},
-    store::{DiskStore, Store, StoreConfig},
+    store::{DiskStore, ExternalReader, LevelCacheStore, Store, StoreConfig},
 };
 use rayon::prelude::{
     IndexedParallelIterator, IntoParallelIterator, ParallelIterator, ParallelSliceMut,
@@ -43,7 +44,7 @@ use crate::{
     stacked::vanilla::{
         challenges::LayerChallenges,
         column::Column,
-        create_label,
+        create_label::{self, LabelCheckpoint},
         graph::StackedBucketGraph,
         hash::hash_single_column,
         params::{
@@ -540,6 +541,27 @@ impl<'a, Tree: 'static + MerkleTreeTrait, G: 'static + Hasher> StackedDrg<'a, Tr
         replica_id: &<Tree::Hasher as Hasher>::Domain,
         cache_path: P,
     ) -> Result<(Labels<Tree>, Vec<LayerState>)>
+    where
+        P: AsRef<Path>,
+    {
+        Self::generate_labels_for_encoding_with_checkpoint(
+            graph,
+            layer_challenges,
+            replica_id,
+            cache_path,
+            None,
+        )
+    }
+
+    /// Generates the layers as needed for encoding, recording each completed layer in
+    /// `checkpoint` if given.
+    fn generate_labels_for_encoding_with_checkpoint<P>(
+        graph: &StackedBucketGraph<Tree::Hasher>,
+        layer_challenges: &LayerChallenges,
+        replica_id: &<Tree::Hasher as Hasher>::Domain,
+        cache_path: P,
+        checkpoint: Option<&mut LabelCheckpoint>,
+    ) -> Result<(Labels<Tree>, Vec<LayerState>)>
     where
         P: AsRef<Path>,
     {
@@ -555,6 +577,7 @@ impl<'a, Tree: 'static + MerkleTreeTrait, G: 'static + Hasher> StackedDrg<'a, Tr
                     layer_challenges.layers(),
                     replica_id,
                     &cache_path,
+                    checkpoint,
                 )
             } else {
                 info!("single core replication");
@@ -564,6 +587,7 @@ impl<'a, Tree: 'static + MerkleTreeTrait, G: 'static + Hasher> StackedDrg<'a, Tr
                     layer_challenges.layers(),
                     replica_id,
                     &cache_path,
+                    checkpoint,
                 )
             }
         }
@@ -577,10 +601,54 @@ impl<'a, Tree: 'static + MerkleTreeTrait, G: 'static + Hasher> StackedDrg<'a, Tr
                 layer_challenges.layers(),
                 replica_id,
                 &cache_path,
+                checkpoint,
             )
         }
     }
 
+    /// Generates the layers of several sectors as needed for encoding.
+    ///
+    /// With multicore SDR the sectors are labeled in lockstep, so that the parents cache is
+    /// only read once for all of them. Otherwise they are labeled one after another.
+    pub fn generate_labels_for_encoding_batch<P>(
+        graph: &StackedBucketGraph<Tree::Hasher>,
+        layer_challenges: &LayerChallenges,
+        replica_ids: &[<Tree::Hasher as Hasher>::Domain],
+        cache_paths: &[P],
+    ) -> Result<Vec<(Labels<Tree>, Vec<LayerState>)>>
+    where
+        P: AsRef<Path>,
+    {
+        ensure!(
+            replica_ids.len() == cache_paths.len(),
+            "number of replica ids and cache paths must match"
+        );
+
+        #[cfg(feature = "multicore-sdr")]
+        {
+            if SETTINGS.use_multicore_sdr {
+                info!("multi core replication of {} sectors", replica_ids.len());
+                let parent_cache = graph.parent_cache()?;
+                return create_label::multi::create_labels_for_encoding_batch(
+                    graph,
+                    &parent_cache,
+                    layer_challenges.layers(),
+                    replica_ids,
+                    cache_paths,
+                    None,
+                );
+            }
+        }
+
+        replica_ids
+            .iter()
+            .zip(cache_paths)
+            .map(|(replica_id, cache_path)| {
+                Self::generate_labels_for_encoding(graph, layer_challenges, replica_id, cache_path)
+            })
+            .collect()
+    }
+
     /// Generates the layers, as needed for decoding.
     pub fn generate_labels_for_decoding(
         graph: &StackedBucketGraph<Tree::Hasher>,
@@ -1471,6 +1539,98 @@ impl<'a, Tree: 'static + MerkleTreeTrait, G: 'static + Hasher> StackedDrg<'a, Tr
         )
     }
 
+    /// Rebuilds the base trees of tree_c at `indices` from the labels, the other base trees are
+    /// left untouched.
+    ///
+    /// The rebuilt base trees are written next to the existing ones and only moved into place if
+    /// their roots, combined with the roots of the other base trees, match `comm_c`. Returns
+    /// whether they did.
+    pub fn rebuild_tree_c_sub_trees(
+        nodes_count: usize,
+        configs: &[StoreConfig],
+        labels: &LabelsCache<Tree>,
+        indices: &[usize],
+        comm_c: &<Tree::Hasher as Hasher>::Domain,
+    ) -> Result<bool> {
+        check_sub_tree_indices("tree_c", configs.len(), indices)?;
+        let tree_len = configs[0].size.expect("config size failure");
+
+        rebuild_sub_trees::<Tree, _, _>(
+            configs,
+            indices,
+            comm_c,
+            |i, config| {
+                info!("rebuilding base tree_c {}/{}", i + 1, configs.len());
+
+                let hashes = (i * nodes_count..(i + 1) * nodes_count)
+                    .into_par_iter()
+                    .map(|node| Ok(labels.column(node as u32)?.hash().into()))
+                    .collect::<Result<Vec<<Tree::Hasher as Hasher>::Domain>>>()?;
+
+                let tree =
+                    DiskTree::<Tree::Hasher, Tree::Arity, U0, U0>::from_par_iter_with_config(
+                        hashes,
+                        config.clone(),
+                    )
+                    .with_context(|| format!("failed to rebuild tree_c {}", i))?;
+
+                Ok(tree.root())
+            },
+            |_, config| DiskStore::new_from_disk(tree_len, Tree::Arity::to_usize(), config)?.last(),
+        )
+    }
+
+    /// Rebuilds the base trees of tree_r_last at `indices` from the replica, the other base trees
+    /// are left untouched.
+    ///
+    /// The rebuilt base trees are written next to the existing ones and only moved into place if
+    /// their roots, combined with the roots of the other base trees, match `comm_r_last`. Returns
+    /// whether they did.
+    #[allow(clippy::too_many_arguments)]
+    pub fn rebuild_tree_r_last_sub_trees(
+        nodes_count: usize,
+        tree_count: usize,
+        tree_r_last_config: StoreConfig,
+        replica_path: PathBuf,
+        source: &DiskStore<<Tree::Hasher as Hasher>::Domain>,
+        indices: &[usize],
+        comm_r_last: &<Tree::Hasher as Hasher>::Domain,
+    ) -> Result<bool> {
+        check_sub_tree_indices("tree_r_last", tree_count, indices)?;
+        let tree_len = tree_r_last_config.size.expect("config size failure");
+        let (configs, replica_config) =
+            split_config_and_replica(tree_r_last_config, replica_path, nodes_count, tree_count)?;
+
+        rebuild_sub_trees::<Tree, _, _>(
+            &configs,
+            indices,
+            comm_r_last,
+            |i, config| {
+                info!("rebuilding base tree_r_last {}/{}", i + 1, tree_count);
+
+                let start = i * nodes_count;
+                let encoded_data = source.read_range(start..start + nodes_count)?;
+
+                let tree = LCTree::<Tree::Hasher, Tree::Arity, U0, U0>::from_par_iter_with_config(
+                    encoded_data,
+                    config.clone(),
+                )
+                .with_context(|| format!("failed to rebuild tree_r_last {}", i))?;
+
+                Ok(tree.root())
+            },
+            |i, config| {
+                LevelCacheStore::<<Tree::Hasher as Hasher>::Domain, File>::new_from_disk_with_reader(
+                    tree_len,
+                    Tree::Arity::to_usize(),
+                    config,
+                    ExternalReader::new_from_config(&replica_config, i)?,
+                )?
+                .last()
+            },
+        )
+    }
+
     pub(crate) fn transform_and_replicate_layers(
         graph: &StackedBucketGraph<Tree::Hasher>,
         layer_challenges: &LayerChallenges,
@@ -1711,6 +1871,71 @@ impl<'a, Tree: 'static + MerkleTreeTrait, G: 'static + Hasher> StackedDrg<'a, Tr
         Ok(labels)
     }
 
+    /// Phase1 of replication for a batch of sectors sharing the same public params, the labels
+    /// are returned in the order of `replica_ids`.
+    pub fn replicate_phase1_batch<P>(
+        pp: &'a PublicParams<Tree>,
+        replica_ids: &[<Tree::Hasher as Hasher>::Domain],
+        cache_paths: &[P],
+    ) -> Result<Vec<Labels<Tree>>>
+    where
+        P: AsRef<Path>,
+    {
+        info!("replicate_phase1_batch");
+
+        let labels = measure_op(Operation::EncodeWindowTimeAll, || {
+            Self::generate_labels_for_encoding_batch(
+                &pp.graph,
+                &pp.layer_challenges,
+                replica_ids,
+                cache_paths,
+            )
+        })?
+        .into_iter()
+        .map(|(labels, _)| labels)
+        .collect();
+
+        Ok(labels)
+    }
+
+    /// Phase1 of replication with a label checkpoint in `cache_path`, which is updated after each
+    /// completed layer.
+    ///
+    /// If the cache holds the checkpoint of an interrupted run, the layers recorded in it are
+    /// validated against their digests and labeling continues with the layer following the last
+    /// valid one.
+    pub fn resume_phase1<P>(
+        pp: &'a PublicParams<Tree>,
+        replica_id: &<Tree::Hasher as Hasher>::Domain,
+        cache_path: P,
+    ) -> Result<Labels<Tree>>
+    where
+        P: AsRef<Path>,
+    {
+        info!("resume_phase1");
+
+        let mut checkpoint = create_label::validate_checkpoint::<_, Tree>(
+            &pp.graph,
+            &cache_path,
+            pp.layer_challenges.layers(),
+            replica_id.as_ref(),
+        )?;
+        info!("resuming labeling after layer {}", checkpoint.last_layer());
+
+        let labels = measure_op(Operation::EncodeWindowTimeAll, || {
+            Self::generate_labels_for_encoding_with_checkpoint(
+                &pp.graph,
+                &pp.layer_challenges,
+                replica_id,
+                cache_path,
+                Some(&mut checkpoint),
+            )
+        })?
+        .0;
+
+        Ok(labels)
+    }
+
     /// Phase2 of replication.
     #[allow(clippy::type_complexity)]
     pub fn replicate_phase2(
@@ -1993,3 +2218,124 @@ impl<'a, Tree: 'static + MerkleTreeTrait, G: 'static + Hasher> StackedDrg<'a, Tr
         Ok((comm_r, p_aux))
     }
 }
+
+fn check_sub_tree_indices(name: &str, tree_count: usize, indices: &[usize]) -> Result<()> {
+    for (pos, &i) in indices.iter().enumerate() {
+        ensure!(
+            i < tree_count,
+            "invalid {} sub-tree index {} ({} sub-trees)",
+            name,
+            i,
+            tree_count
+        );
+        ensure!(
+            !indices[..pos].contains(&i),
+            "duplicate {} sub-tree index {}",
+            name,
+            i
+        );
+    }
+
+    Ok(())
+}
+
+/// Builds the base trees at `indices` with `build` into temporary stores and reads the roots of
+/// the remaining base trees with `read_root`. Only if all roots combined match `root` are the
+/// temporary stores moved into place, otherwise they are removed.
+fn rebuild_sub_trees<Tree, B, R>(
+    configs: &[StoreConfig],
+    indices: &[usize],
+    root: &<Tree::Hasher as Hasher>::Domain,
+    build: B,
+    read_root: R,
+) -> Result<bool>
+where
+    Tree: MerkleTreeTrait,
+    B: Fn(usize, &StoreConfig) -> Result<<Tree::Hasher as Hasher>::Domain>,
+    R: Fn(usize, &StoreConfig) -> Result<<Tree::Hasher as Hasher>::Domain>,
+{
+    let rebuild_configs: Vec<_> = indices
+        .iter()
+        .map(|&i| StoreConfig {
+            id: format!("{}-rebuild", configs[i].id),
+            ..configs[i].clone()
+        })
+        .collect();
+
+    let roots = (|| {
+        let mut roots = Vec::with_capacity(configs.len());
+        for (i, config) in configs.iter().enumerate() {
+            let root = match indices.iter().position(|&index| index == i) {
+                Some(pos) => {
+                    remove_store(&rebuild_configs[pos])?;
+                    build(i, &rebuild_configs[pos])?
+                }
+                None => read_root(i, config)
+                    .with_context(|| format!("failed to read the root of sub-tree {}", i))?,
+            };
+            roots.push(root);
+        }
+
+        Ok(roots)
+    })();
+
+    let matches = match roots {
+        Ok(roots) => combine_base_tree_roots::<Tree>(&roots) == *root,
+        Err(err) => {
+            for config in &rebuild_configs {
+                remove_store(config)?;
+            }
+            return Err(err);
+        }
+    };
+    if !matches {
+        warn!(
+            "rebuilt sub-trees {:?} do not match the expected root",
+            indices
+        );
+        for config in &rebuild_configs {
+            remove_store(config)?;
+        }
+        return Ok(false);
+    }
+
+    for (&i, config) in indices.iter().zip(&rebuild_configs) {
+        let from = StoreConfig::data_path(&config.path, &config.id);
+        let to = StoreConfig::data_path(&configs[i].path, &configs[i].id);
+        fs::rename(&from, &to).with_context(|| format!("failed to move {:?} to {:?}", from, to))?;
+    }
+
+    Ok(true)
+}
+
+/// Combines the roots of the base trees of a `Tree` into its root, the same way the compound
+/// merkle trees do.
+fn combine_base_tree_roots<Tree: MerkleTreeTrait>(
+    roots: &[<Tree::Hasher as Hasher>::Domain],
+) -> <Tree::Hasher as Hasher>::Domain {
+    let hash = |roots: &[<Tree::Hasher as Hasher>::Domain]| {
+        <Tree::Hasher as Hasher>::Function::default().multi_node(roots, 1)
+    };
+
+    if Tree::TopTreeArity::to_usize() > 0 {
+        let sub_tree_roots: Vec<_> = roots
+            .chunks(roots.len() / Tree::TopTreeArity::to_usize())
+            .map(hash)
+            .collect();
+        hash(&sub_tree_roots)
+    } else if Tree::SubTreeArity::to_usize() > 0 {
+        hash(roots)
+    } else {
+        roots[0]
+    }
+}
+
+/// Removes the data file of the store at `config`, if there is one.
+fn remove_store(config: &StoreConfig) -> Result<()> {
+    let path = StoreConfig::data_path(&config.path, &config.id);
+    if path.exists() {
+        fs::remove_file(&path).with_context(|| format!("failed to remove {:?}", path))?;
+    }
+
+    Ok(())
+}
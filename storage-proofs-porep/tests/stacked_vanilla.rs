@@ -24,8 +24,8 @@ use storage_proofs_core::{
 };
 use storage_proofs_porep::{
     stacked::{
-        LayerChallenges, PrivateInputs, PublicInputs, SetupParams, StackedBucketGraph, StackedDrg,
-        TemporaryAux, TemporaryAuxCache, BINARY_ARITY, EXP_DEGREE,
+        LabelCheckpoint, LayerChallenges, PrivateInputs, PublicInputs, SetupParams,
+        StackedBucketGraph, StackedDrg, TemporaryAux, TemporaryAuxCache, BINARY_ARITY, EXP_DEGREE,
     },
     PoRep,
 };
@@ -309,6 +309,112 @@ fn test_stacked_porep_resume_seal() {
     cache_dir.close().expect("Failed to remove cache dir");
 }
 
+#[test]
+fn test_stacked_porep_resume_from_checkpoint() {
+    type Tree = DiskTree<PoseidonHasher, U8, U0, U0>;
+
+    let mut rng = XorShiftRng::from_seed(TEST_SEED);
+    let replica_id = <PoseidonHasher as Hasher>::Domain::random(&mut rng);
+    let nodes = 64;
+    let layers = 4;
+
+    let cache_dir = tempdir().expect("tempdir failure");
+    let layer_challenges = LayerChallenges::new(layers, 5);
+
+    let sp = SetupParams {
+        nodes,
+        degree: BASE_DEGREE,
+        expansion_degree: EXP_DEGREE,
+        porep_id: [32; 32],
+        layer_challenges,
+        api_version: ApiVersion::V1_2_0,
+        api_features: vec![],
+    };
+
+    let pp = StackedDrg::<Tree, Blake2sHasher>::setup(&sp).expect("setup failed");
+
+    // Plain labeling does not checkpoint.
+    let plain_dir = tempdir().expect("tempdir failure");
+    StackedDrg::<Tree, Blake2sHasher>::replicate_phase1(&pp, &replica_id, plain_dir.path())
+        .expect("labeling failed");
+    assert!(LabelCheckpoint::read(plain_dir.path())
+        .expect("failed to read checkpoint")
+        .is_none());
+
+    // Without a checkpoint, resuming keeps the layers already on disk and records them.
+    let plain_layer = StoreConfig::data_path(plain_dir.path(), &CacheKey::label_layer(1));
+    let modified = std::fs::metadata(&plain_layer)
+        .and_then(|metadata| metadata.modified())
+        .expect("failed to stat layer");
+    StackedDrg::<Tree, Blake2sHasher>::resume_phase1(&pp, &replica_id, plain_dir.path())
+        .expect("resumed labeling failed");
+    assert_eq!(
+        std::fs::metadata(&plain_layer)
+            .and_then(|metadata| metadata.modified())
+            .expect("failed to stat layer"),
+        modified
+    );
+    assert_eq!(
+        LabelCheckpoint::read(plain_dir.path())
+            .expect("failed to read checkpoint")
+            .expect("checkpoint missing")
+            .last_layer(),
+        layers
+    );
+
+    let labels =
+        StackedDrg::<Tree, Blake2sHasher>::resume_phase1(&pp, &replica_id, cache_dir.path())
+            .expect("labeling failed");
+
+    let checkpoint = LabelCheckpoint::read(cache_dir.path())
+        .expect("failed to read checkpoint")
+        .expect("checkpoint missing");
+    assert_eq!(checkpoint.last_layer(), layers);
+
+    let read_layers = || {
+        labels
+            .labels
+            .iter()
+            .map(|config| {
+                std::fs::read(StoreConfig::data_path(&config.path, &config.id))
+                    .expect("failed to read layer")
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = read_layers();
+
+    // Corrupt layer 2 and drop the last layer, as if labeling crashed while writing it.
+    let config = &labels.labels[1];
+    let layer_path = StoreConfig::data_path(&config.path, &config.id);
+    let mut corrupt = expected[1].clone();
+    corrupt[0] ^= 0xff;
+    std::fs::write(&layer_path, corrupt).expect("failed to corrupt layer");
+    let config = &labels.labels[layers - 1];
+    remove_file(StoreConfig::data_path(&config.path, &config.id)).expect("failed to remove layer");
+
+    // A checkpoint for a different replica must not be resumed.
+    let other_replica_id = <PoseidonHasher as Hasher>::Domain::random(&mut rng);
+    assert!(StackedDrg::<Tree, Blake2sHasher>::resume_phase1(
+        &pp,
+        &other_replica_id,
+        cache_dir.path()
+    )
+    .is_err());
+
+    StackedDrg::<Tree, Blake2sHasher>::resume_phase1(&pp, &replica_id, cache_dir.path())
+        .expect("resumed labeling failed");
+
+    assert_eq!(read_layers(), expected);
+    assert_eq!(
+        LabelCheckpoint::read(cache_dir.path())
+            .expect("failed to read checkpoint")
+            .expect("checkpoint missing"),
+        checkpoint
+    );
+
+    cache_dir.close().expect("Failed to remove cache dir");
+}
+
 table_tests! {
     test_prove_verify_fixed {
        test_stacked_porep_prove_verify(64);
@@ -1,9 +1,11 @@
 mod circuit;
 mod compound;
+mod resampling;
 mod utils;
 mod vanilla;
 
 pub use circuit::*;
 pub use compound::*;
+pub use resampling::*;
 pub use utils::*;
 pub use vanilla::*;
@@ -0,0 +1,254 @@
+//! Fault-tolerant proving of a single sector for private audits.
+//!
+//! A challenged leaf whose inclusion proof can not be generated, e.g. because the block of the
+//! replica holding it is unreadable, is replaced by a resampled challenge instead of failing the
+//! whole sector. The unreadable leafs are reported, so that they can be repaired.
+//!
+//! These proofs are not accepted by the network: the circuit derives every challenge itself and
+//! has no notion of resampling, so they can only be checked with
+//! `verify_sector_with_resampling`.
+
+use std::collections::BTreeSet;
+
+use anyhow::ensure;
+use filecoin_hashers::{Domain, HashFunction, Hasher};
+use generic_array::typenum::Unsigned;
+use log::{error, warn};
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use storage_proofs_core::{
+    error::{Error, Result},
+    merkle::{MerkleProofTrait, MerkleTreeTrait},
+    util::{default_rows_to_discard, NODE_SIZE},
+};
+
+use super::{
+    generate_leaf_challenge_inner, get_challenge_index, PrivateSector, PublicParams, PublicSector,
+    SectorProof,
+};
+
+/// Separates the hashes of resampled challenges from the ones of the regular challenges.
+const RESAMPLING_TAG: &[u8] = b"fallback-post-resampling";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResampledSectorProof<P: MerkleProofTrait> {
+    #[serde(bound(
+        serialize = "SectorProof<P>: Serialize",
+        deserialize = "SectorProof<P>: Deserialize<'de>"
+    ))]
+    pub sector_proof: SectorProof<P>,
+    /// The number of times every challenge was resampled, 0 if its regular leaf was proven.
+    pub attempts: Vec<u64>,
+    /// The sorted leafs which could not be proven.
+    pub unreadable_leafs: Vec<u64>,
+}
+
+/// Generates the leaf challenge for `challenge_index`, resampled `attempt` times. Attempt 0 is
+/// the regular challenge, as returned by `generate_leaf_challenge`.
+pub fn generate_resampled_leaf_challenge<T: Domain>(
+    pub_params: &PublicParams,
+    randomness: T,
+    sector_id: u64,
+    challenge_index: u64,
+    attempt: u64,
+) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(AsRef::<[u8]>::as_ref(&randomness));
+    hasher.update(&sector_id.to_le_bytes()[..]);
+    if attempt > 0 {
+        hasher.update(RESAMPLING_TAG);
+        hasher.update(&attempt.to_le_bytes()[..]);
+    }
+
+    generate_leaf_challenge_inner::<T>(hasher, pub_params, challenge_index)
+}
+
+/// Proves the sector at `sector_index` of a PoSt, resampling every challenge whose leaf can not
+/// be proven, up to `max_resamples` times for the whole sector.
+///
+/// Fails with `Error::UnreadableLeafs` listing every leaf which could not be proven if more
+/// resamples are needed, and with `Error::FaultySectors` if comm_r does not match the sector.
+pub fn prove_sector_with_resampling<Tree: MerkleTreeTrait>(
+    pub_params: &PublicParams,
+    randomness: <Tree::Hasher as Hasher>::Domain,
+    sector_index: usize,
+    pub_sector: &PublicSector<<Tree::Hasher as Hasher>::Domain>,
+    priv_sector: &PrivateSector<'_, Tree>,
+    max_resamples: usize,
+) -> Result<ResampledSectorProof<Tree::Proof>> {
+    let sector_id = pub_sector.id;
+    ensure!(
+        pub_sector.comm_r
+            == <Tree::Hasher as Hasher>::Function::hash2(
+                &priv_sector.comm_c,
+                &priv_sector.comm_r_last
+            ),
+        Error::FaultySectors(vec![sector_id])
+    );
+
+    let tree = priv_sector.tree;
+    let rows_to_discard = default_rows_to_discard(tree.leafs(), Tree::Arity::to_usize());
+
+    // Every challenge may use up the whole budget, which is checked for all of them afterwards.
+    let challenges = (0..pub_params.challenge_count)
+        .into_par_iter()
+        .map(|n| {
+            let challenge_index = get_challenge_index(
+                pub_params.api_version,
+                sector_index,
+                pub_params.challenge_count,
+                n,
+            );
+            let mut unreadable_leafs = Vec::new();
+            for attempt in 0..=max_resamples as u64 {
+                let challenged_leaf = generate_resampled_leaf_challenge(
+                    pub_params,
+                    randomness,
+                    sector_id.into(),
+                    challenge_index,
+                    attempt,
+                );
+                match tree.gen_cached_proof(challenged_leaf as usize, Some(rows_to_discard)) {
+                    Ok(proof)
+                        if proof.validate(challenged_leaf as usize)
+                            && proof.root() == priv_sector.comm_r_last =>
+                    {
+                        return (Some((proof, attempt)), unreadable_leafs);
+                    }
+                    Ok(_) => {
+                        warn!("invalid leaf {} in sector {}", challenged_leaf, sector_id);
+                    }
+                    Err(err) => {
+                        warn!(
+                            "unreadable leaf {} in sector {}: {:?}",
+                            challenged_leaf, sector_id, err
+                        );
+                    }
+                }
+                unreadable_leafs.push(challenged_leaf);
+            }
+            (None, unreadable_leafs)
+        })
+        .collect::<Vec<_>>();
+
+    let unreadable_leafs: BTreeSet<u64> = challenges
+        .iter()
+        .flat_map(|(_, leafs)| leafs.iter().copied())
+        .collect();
+    let resamples: usize = challenges.iter().map(|(_, leafs)| leafs.len()).sum();
+    let proven = challenges
+        .into_iter()
+        .map(|(proof, _)| proof)
+        .collect::<Option<Vec<_>>>();
+
+    match proven {
+        Some(proven) if resamples <= max_resamples => {
+            let (inclusion_proofs, attempts) = proven.into_iter().unzip();
+            Ok(ResampledSectorProof {
+                sector_proof: SectorProof {
+                    inclusion_proofs,
+                    comm_c: priv_sector.comm_c,
+                    comm_r_last: priv_sector.comm_r_last,
+                },
+                attempts,
+                unreadable_leafs: unreadable_leafs.into_iter().collect(),
+            })
+        }
+        _ => Err(Error::UnreadableLeafs(sector_id, unreadable_leafs.into_iter().collect()).into()),
+    }
+}
+
+/// Verifies a proof generated by `prove_sector_with_resampling` with the same `sector_index` and
+/// `max_resamples`, including its list of unreadable leafs.
+pub fn verify_sector_with_resampling<Tree: MerkleTreeTrait>(
+    pub_params: &PublicParams,
+    randomness: <Tree::Hasher as Hasher>::Domain,
+    sector_index: usize,
+    pub_sector: &PublicSector<<Tree::Hasher as Hasher>::Domain>,
+    max_resamples: usize,
+    proof: &ResampledSectorProof<Tree::Proof>,
+) -> Result<bool> {
+    let sector_id = pub_sector.id;
+    let inclusion_proofs = &proof.sector_proof.inclusion_proofs;
+    if inclusion_proofs.len() != pub_params.challenge_count
+        || proof.attempts.len() != pub_params.challenge_count
+    {
+        error!(
+            "unexpected number of inclusion proofs: {} != {}: {:?}",
+            pub_params.challenge_count,
+            inclusion_proofs.len(),
+            sector_id
+        );
+        return Ok(false);
+    }
+
+    if proof.attempts.iter().sum::<u64>() > max_resamples as u64 {
+        error!("too many resamples: {:?}", sector_id);
+        return Ok(false);
+    }
+
+    // Verify that H(Comm_c || Comm_r_last) == Comm_R
+    let comm_r_last = match inclusion_proofs.first() {
+        Some(inclusion_proof) => inclusion_proof.root(),
+        None => {
+            error!("no inclusion proofs: {:?}", sector_id);
+            return Ok(false);
+        }
+    };
+    if <Tree::Hasher as Hasher>::Function::hash2(&proof.sector_proof.comm_c, &comm_r_last)
+        != pub_sector.comm_r
+    {
+        error!("hash(comm_c || comm_r_last) != comm_r: {:?}", sector_id);
+        return Ok(false);
+    }
+
+    let mut unreadable_leafs = BTreeSet::new();
+    for (n, (inclusion_proof, attempts)) in inclusion_proofs.iter().zip(&proof.attempts).enumerate()
+    {
+        let challenge_index = get_challenge_index(
+            pub_params.api_version,
+            sector_index,
+            pub_params.challenge_count,
+            n,
+        );
+        let challenged_leafs = (0..=*attempts)
+            .map(|attempt| {
+                generate_resampled_leaf_challenge(
+                    pub_params,
+                    randomness,
+                    sector_id.into(),
+                    challenge_index,
+                    attempt,
+                )
+            })
+            .collect::<Vec<_>>();
+        let (challenged_leaf, skipped) =
+            challenged_leafs.split_last().expect("at least one attempt");
+        unreadable_leafs.extend(skipped.iter().copied());
+
+        if inclusion_proof.root() != comm_r_last {
+            error!("inclusion proof root != comm_r_last: {:?}", sector_id);
+            return Ok(false);
+        }
+
+        let expected_path_length =
+            inclusion_proof.expected_len(pub_params.sector_size as usize / NODE_SIZE);
+        if expected_path_length != inclusion_proof.path().len() {
+            error!("wrong path length: {:?}", sector_id);
+            return Ok(false);
+        }
+
+        if !inclusion_proof.validate(*challenged_leaf as usize) {
+            error!("invalid inclusion proof: {:?}", sector_id);
+            return Ok(false);
+        }
+    }
+
+    if unreadable_leafs.into_iter().collect::<Vec<_>>() != proof.unreadable_leafs {
+        error!("wrong unreadable leafs: {:?}", sector_id);
+        return Ok(false);
+    }
+
+    Ok(true)
+}
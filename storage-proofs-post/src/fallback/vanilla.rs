@@ -289,8 +289,7 @@ pub fn vanilla_proof<Tree: MerkleTreeTrait>(
 
             ensure!(
                 proof.validate(challenged_leaf as usize) && proof.root() == priv_sector.comm_r_last,
-                "Generated vanilla proof for sector {} is invalid",
-                sector_id
+                Error::InvalidVanillaProof(sector_id)
             );
 
             Ok(proof)
@@ -3,13 +3,12 @@ use std::marker::PhantomData;
 use anyhow::ensure;
 use bellperson::{Circuit, ConstraintSystem, SynthesisError};
 use blstrs::Scalar as Fr;
-use generic_array::typenum::U2;
+use generic_array::typenum::Unsigned;
 use storage_proofs_core::{
     compound_proof::{CircuitComponent, CompoundProof},
-    drgraph::graph_height,
     error::Result,
     gadgets::por::PoRCompound,
-    merkle::MerkleTreeTrait,
+    merkle::{base_path_length, MerkleTreeTrait},
     parameter_cache::{CacheableParameters, ParameterSetMetadata},
     por,
     proof::ProofScheme,
@@ -130,13 +129,26 @@ where
         pub_params: &<RationalPoSt<'a, Tree> as ProofScheme<'a>>::PublicParams,
     ) -> RationalPoStCircuit<Tree> {
         let challenges_count = pub_params.challenges_count;
-        let height = graph_height::<U2>(pub_params.sector_size as usize / NODE_SIZE);
+        let leaves = pub_params.sector_size as usize / NODE_SIZE;
+
+        // The path of a compound tree ends with one element for each of its sub and top trees.
+        let base_elements =
+            base_path_length::<Tree::Arity, Tree::SubTreeArity, Tree::TopTreeArity>(leaves);
+        let mut path = vec![(vec![None; Tree::Arity::to_usize() - 1], None); base_elements];
+        for arity in [
+            Tree::SubTreeArity::to_usize(),
+            Tree::TopTreeArity::to_usize(),
+        ] {
+            if arity > 0 {
+                path.push((vec![None; arity - 1], None));
+            }
+        }
 
         let comm_rs = vec![None; challenges_count];
         let comm_cs = vec![None; challenges_count];
         let comm_r_lasts = vec![None; challenges_count];
         let leafs = vec![None; challenges_count];
-        let paths = vec![vec![(vec![None; 1], None); height - 1]; challenges_count];
+        let paths = vec![path; challenges_count];
 
         RationalPoStCircuit {
             comm_rs,
@@ -0,0 +1,273 @@
+use std::fs::{read_dir, OpenOptions};
+
+use filecoin_hashers::{poseidon::PoseidonHasher, Domain, HashFunction, Hasher};
+use generic_array::typenum::{U0, U2, U4, U8};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use storage_proofs_core::{
+    api_version::ApiVersion,
+    error::Error,
+    merkle::{generate_tree, get_base_tree_count, LCTree, MerkleTreeTrait},
+    util::NODE_SIZE,
+    TEST_SEED,
+};
+use storage_proofs_post::fallback::{
+    self, generate_leaf_challenge, generate_resampled_leaf_challenge, get_challenge_index,
+    prove_sector_with_resampling, verify_sector_with_resampling, PrivateSector, PublicSector,
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_fallback_post_resampling_undamaged_poseidon_base_8() {
+    test_fallback_post_resampling_undamaged::<LCTree<PoseidonHasher, U8, U0, U0>>();
+}
+
+#[test]
+fn test_fallback_post_resampling_undamaged_poseidon_sub_8_4() {
+    test_fallback_post_resampling_undamaged::<LCTree<PoseidonHasher, U8, U4, U0>>();
+}
+
+#[test]
+fn test_fallback_post_resampling_undamaged_poseidon_top_8_4_2() {
+    test_fallback_post_resampling_undamaged::<LCTree<PoseidonHasher, U8, U4, U2>>();
+}
+
+fn test_fallback_post_resampling_undamaged<Tree: 'static + MerkleTreeTrait>() {
+    let rng = &mut XorShiftRng::from_seed(TEST_SEED);
+
+    let leaves = 64 * get_base_tree_count::<Tree>();
+    let pub_params = fallback::PublicParams {
+        sector_size: (leaves * NODE_SIZE) as u64,
+        challenge_count: 10,
+        sector_count: 1,
+        api_version: ApiVersion::V1_2_0,
+    };
+
+    let temp_dir = tempdir().unwrap();
+    let (_data, tree) = generate_tree::<Tree, _>(rng, leaves, Some(temp_dir.path().to_path_buf()));
+    let randomness = <Tree::Hasher as Hasher>::Domain::random(rng);
+    let comm_c = <Tree::Hasher as Hasher>::Domain::random(rng);
+    let comm_r_last = tree.root();
+    let pub_sector = PublicSector {
+        id: 7.into(),
+        comm_r: <Tree::Hasher as Hasher>::Function::hash2(&comm_c, &comm_r_last),
+    };
+    let priv_sector = PrivateSector::<Tree> {
+        tree: &tree,
+        comm_c,
+        comm_r_last,
+    };
+    let sector_index = 3;
+
+    let proof = prove_sector_with_resampling(
+        &pub_params,
+        randomness,
+        sector_index,
+        &pub_sector,
+        &priv_sector,
+        0,
+    )
+    .expect("proving failed");
+    assert!(proof.attempts.iter().all(|attempts| *attempts == 0));
+    assert!(proof.unreadable_leafs.is_empty());
+    assert!(verify_sector_with_resampling::<Tree>(
+        &pub_params,
+        randomness,
+        sector_index,
+        &pub_sector,
+        0,
+        &proof,
+    )
+    .expect("verification failed"));
+
+    // Without resampling, the challenges are the ones of the regular PoSt.
+    for n in 0..pub_params.challenge_count {
+        let challenge_index = get_challenge_index(
+            pub_params.api_version,
+            sector_index,
+            pub_params.challenge_count,
+            n,
+        );
+        assert_eq!(
+            generate_resampled_leaf_challenge(&pub_params, randomness, 7, challenge_index, 0),
+            generate_leaf_challenge(&pub_params, randomness, 7, challenge_index)
+        );
+    }
+
+    assert!(!verify_sector_with_resampling::<Tree>(
+        &pub_params,
+        <Tree::Hasher as Hasher>::Domain::random(rng),
+        sector_index,
+        &pub_sector,
+        0,
+        &proof,
+    )
+    .expect("verification failed"));
+
+    // A proof without inclusion proofs is rejected, even if no challenges are expected.
+    let mut empty_proof = proof.clone();
+    empty_proof.sector_proof.inclusion_proofs.clear();
+    empty_proof.attempts.clear();
+    for challenge_count in [pub_params.challenge_count, 0] {
+        let pub_params = fallback::PublicParams {
+            challenge_count,
+            ..pub_params.clone()
+        };
+        assert!(!verify_sector_with_resampling::<Tree>(
+            &pub_params,
+            randomness,
+            sector_index,
+            &pub_sector,
+            0,
+            &empty_proof,
+        )
+        .expect("verification failed"));
+    }
+
+    let wrong_pub_sector = PublicSector {
+        id: 7.into(),
+        comm_r: <Tree::Hasher as Hasher>::Domain::random(rng),
+    };
+    let err = prove_sector_with_resampling(
+        &pub_params,
+        randomness,
+        sector_index,
+        &wrong_pub_sector,
+        &priv_sector,
+        0,
+    )
+    .expect_err("proving with a wrong comm_r succeeded");
+    match err.downcast::<Error>() {
+        Ok(Error::FaultySectors(sector_ids)) => assert_eq!(sector_ids, vec![7.into()]),
+        _ => panic!("proving failed to return FaultySectors"),
+    }
+}
+
+#[test]
+fn test_fallback_post_resampling_damaged_poseidon_base_8() {
+    type Tree = LCTree<PoseidonHasher, U8, U0, U0>;
+
+    let rng = &mut XorShiftRng::from_seed(TEST_SEED);
+
+    let leaves = 4096;
+    let pub_params = fallback::PublicParams {
+        sector_size: (leaves * NODE_SIZE) as u64,
+        challenge_count: 20,
+        sector_count: 1,
+        api_version: ApiVersion::V1_2_0,
+    };
+
+    let temp_dir = tempdir().unwrap();
+    let (_data, tree) = generate_tree::<Tree, _>(rng, leaves, Some(temp_dir.path().to_path_buf()));
+    let randomness = <<Tree as MerkleTreeTrait>::Hasher as Hasher>::Domain::random(rng);
+    let comm_c = <<Tree as MerkleTreeTrait>::Hasher as Hasher>::Domain::random(rng);
+    let comm_r_last = tree.root();
+    let pub_sector = PublicSector {
+        id: 7.into(),
+        comm_r: <<Tree as MerkleTreeTrait>::Hasher as Hasher>::Function::hash2(
+            &comm_c,
+            &comm_r_last,
+        ),
+    };
+    let priv_sector = PrivateSector::<Tree> {
+        tree: &tree,
+        comm_c,
+        comm_r_last,
+    };
+
+    // Cutting off the last eighth of the replica makes all of its leafs unreadable.
+    let readable_leaves = leaves as u64 * 7 / 8;
+    let replica_path = read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            path.file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("replica-path-")
+        })
+        .expect("no replica");
+    OpenOptions::new()
+        .write(true)
+        .open(&replica_path)
+        .unwrap()
+        .set_len(readable_leaves * NODE_SIZE as u64)
+        .unwrap();
+
+    let mut damaged_leafs = (0..pub_params.challenge_count)
+        .map(|n| {
+            let challenge_index =
+                get_challenge_index(pub_params.api_version, 0, pub_params.challenge_count, n);
+            generate_leaf_challenge(&pub_params, randomness, 7, challenge_index)
+        })
+        .filter(|leaf| *leaf >= readable_leaves)
+        .collect::<Vec<_>>();
+    damaged_leafs.sort_unstable();
+    damaged_leafs.dedup();
+    assert!(!damaged_leafs.is_empty(), "no damaged leaf challenged");
+
+    let err =
+        prove_sector_with_resampling(&pub_params, randomness, 0, &pub_sector, &priv_sector, 0)
+            .expect_err("proving damaged sector without resampling succeeded");
+    match err.downcast::<Error>() {
+        Ok(Error::UnreadableLeafs(sector_id, leafs)) => {
+            assert_eq!(sector_id, 7.into());
+            assert_eq!(leafs, damaged_leafs);
+        }
+        _ => panic!("proving failed to return UnreadableLeafs"),
+    }
+
+    let max_resamples = 10;
+    let proof = prove_sector_with_resampling(
+        &pub_params,
+        randomness,
+        0,
+        &pub_sector,
+        &priv_sector,
+        max_resamples,
+    )
+    .expect("proving with resampling failed");
+    assert!(proof
+        .unreadable_leafs
+        .iter()
+        .all(|leaf| *leaf >= readable_leaves));
+    assert!(damaged_leafs
+        .iter()
+        .all(|leaf| proof.unreadable_leafs.contains(leaf)));
+    let resamples = proof.attempts.iter().sum::<u64>() as usize;
+    assert!(resamples >= damaged_leafs.len());
+
+    assert!(verify_sector_with_resampling::<Tree>(
+        &pub_params,
+        randomness,
+        0,
+        &pub_sector,
+        max_resamples,
+        &proof,
+    )
+    .expect("verification failed"));
+
+    // The verifier enforces the same limit of resamples.
+    assert!(!verify_sector_with_resampling::<Tree>(
+        &pub_params,
+        randomness,
+        0,
+        &pub_sector,
+        resamples - 1,
+        &proof,
+    )
+    .expect("verification failed"));
+
+    // And checks the reported unreadable leafs.
+    let mut wrong_proof = proof;
+    wrong_proof.unreadable_leafs.pop();
+    assert!(!verify_sector_with_resampling::<Tree>(
+        &pub_params,
+        randomness,
+        0,
+        &pub_sector,
+        max_resamples,
+        &wrong_proof,
+    )
+    .expect("verification failed"));
+}
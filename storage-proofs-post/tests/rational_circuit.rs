@@ -1,15 +1,19 @@
 use std::collections::BTreeMap;
 use std::marker::PhantomData;
 
-use bellperson::{util_cs::test_cs::TestConstraintSystem, Circuit};
+use bellperson::{
+    util_cs::{bench_cs::BenchCS, test_cs::TestConstraintSystem},
+    Circuit,
+};
 use blstrs::Scalar as Fr;
 use ff::Field;
 use filecoin_hashers::{poseidon::PoseidonHasher, Domain, HashFunction, Hasher};
+use generic_array::typenum::{U0, U2, U4, U8};
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 use storage_proofs_core::{
     compound_proof::CompoundProof,
-    merkle::{generate_tree, get_base_tree_count, BinaryMerkleTree, MerkleTreeTrait},
+    merkle::{generate_tree, get_base_tree_count, BinaryMerkleTree, LCTree, MerkleTreeTrait},
     proof::ProofScheme,
     sector::OrderedSectorSet,
     util::NODE_SIZE,
@@ -22,13 +26,31 @@ use tempfile::tempdir;
 
 #[test]
 fn test_rational_post_circuit_poseidon() {
-    test_rational_post_circuit::<BinaryMerkleTree<PoseidonHasher>>(3_770);
+    test_rational_post_circuit::<BinaryMerkleTree<PoseidonHasher>>(32, 3_770);
+}
+
+#[test]
+fn test_rational_post_circuit_poseidon_base_8() {
+    test_rational_post_circuit::<LCTree<PoseidonHasher, U8, U0, U0>>(64, 2_750);
+}
+
+#[test]
+fn test_rational_post_circuit_poseidon_sub_8_4() {
+    test_rational_post_circuit::<LCTree<PoseidonHasher, U8, U4, U0>>(64, 3_524);
 }
 
-fn test_rational_post_circuit<Tree: 'static + MerkleTreeTrait>(expected_constraints: usize) {
+#[test]
+fn test_rational_post_circuit_poseidon_top_8_4_2() {
+    test_rational_post_circuit::<LCTree<PoseidonHasher, U8, U4, U2>>(64, 4_152);
+}
+
+fn test_rational_post_circuit<Tree: 'static + MerkleTreeTrait>(
+    base_tree_leaves: usize,
+    expected_constraints: usize,
+) {
     let rng = &mut XorShiftRng::from_seed(TEST_SEED);
 
-    let leaves = 32 * get_base_tree_count::<Tree>();
+    let leaves = base_tree_leaves * get_base_tree_count::<Tree>();
     let sector_size = (leaves * NODE_SIZE) as u64;
     let challenges_count = 2;
 
@@ -134,6 +156,18 @@ fn test_rational_post_circuit<Tree: 'static + MerkleTreeTrait>(expected_constrai
     );
     assert_eq!(cs.get_input(0, "ONE"), Fr::ONE);
 
+    // The parameters are generated from the blank circuit, which has to match the shape of the
+    // circuit of a real proof.
+    let mut blank_cs = BenchCS::<Fr>::new();
+    RationalPoStCompound::<Tree>::blank_circuit(&pub_params)
+        .synthesize(&mut blank_cs)
+        .expect("failed to synthesize blank circuit");
+    assert_eq!(
+        blank_cs.num_constraints(),
+        expected_constraints,
+        "wrong number of blank circuit constraints"
+    );
+
     let generated_inputs =
         RationalPoStCompound::<Tree>::generate_public_inputs(&pub_inputs, &pub_params, None)
             .unwrap();
@@ -2,11 +2,12 @@ use std::collections::BTreeMap;
 
 use bellperson::{groth16, util_cs::test_cs::TestConstraintSystem, Circuit};
 use filecoin_hashers::{poseidon::PoseidonHasher, Domain, HashFunction, Hasher};
+use generic_array::typenum::{U0, U2, U4, U8};
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 use storage_proofs_core::{
     compound_proof::{self, CompoundProof},
-    merkle::{generate_tree, get_base_tree_count, BinaryMerkleTree, MerkleTreeTrait},
+    merkle::{generate_tree, get_base_tree_count, BinaryMerkleTree, LCTree, MerkleTreeTrait},
     multi_proof::MultiProof,
     proof::NoRequirements,
     sector::OrderedSectorSet,
@@ -19,13 +20,31 @@ use tempfile::tempdir;
 #[ignore]
 #[test]
 fn test_rational_post_compound_poseidon() {
-    test_rational_post_compound::<BinaryMerkleTree<PoseidonHasher>>();
+    test_rational_post_compound::<BinaryMerkleTree<PoseidonHasher>>(32);
 }
 
-fn test_rational_post_compound<Tree: 'static + MerkleTreeTrait>() {
+#[ignore]
+#[test]
+fn test_rational_post_compound_poseidon_base_8() {
+    test_rational_post_compound::<LCTree<PoseidonHasher, U8, U0, U0>>(64);
+}
+
+#[ignore]
+#[test]
+fn test_rational_post_compound_poseidon_sub_8_4() {
+    test_rational_post_compound::<LCTree<PoseidonHasher, U8, U4, U0>>(64);
+}
+
+#[ignore]
+#[test]
+fn test_rational_post_compound_poseidon_top_8_4_2() {
+    test_rational_post_compound::<LCTree<PoseidonHasher, U8, U4, U2>>(64);
+}
+
+fn test_rational_post_compound<Tree: 'static + MerkleTreeTrait>(base_tree_leaves: usize) {
     let rng = &mut XorShiftRng::from_seed(TEST_SEED);
 
-    let leaves = 32 * get_base_tree_count::<Tree>();
+    let leaves = base_tree_leaves * get_base_tree_count::<Tree>();
     let sector_size = (leaves * NODE_SIZE) as u64;
     let challenges_count = 2;
 
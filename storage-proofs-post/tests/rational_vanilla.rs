@@ -8,6 +8,7 @@ use generic_array::typenum::{U0, U2, U8};
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 use storage_proofs_core::{
+    error::Error,
     merkle::{generate_tree, get_base_tree_count, LCTree, MerkleTreeTrait},
     proof::ProofScheme,
     sector::OrderedSectorSet,
@@ -237,3 +238,137 @@ fn test_rational_post_validates_challenge<Tree: 'static + MerkleTreeTrait>() {
     // A proof created with a the wrong challenge not be verified!
     assert!(!verified);
 }
+
+#[test]
+fn test_invalid_rational_post_sha256_base_8() {
+    test_invalid_rational_post::<LCTree<Sha256Hasher, U8, U0, U0>>();
+}
+
+#[test]
+fn test_invalid_rational_post_blake2s_base_8() {
+    test_invalid_rational_post::<LCTree<Blake2sHasher, U8, U0, U0>>();
+}
+
+#[test]
+fn test_invalid_rational_post_poseidon_base_8() {
+    test_invalid_rational_post::<LCTree<PoseidonHasher, U8, U0, U0>>();
+}
+
+#[test]
+fn test_invalid_rational_post_poseidon_sub_8_8() {
+    test_invalid_rational_post::<LCTree<PoseidonHasher, U8, U8, U0>>();
+}
+
+#[test]
+fn test_invalid_rational_post_poseidon_top_8_8_2() {
+    test_invalid_rational_post::<LCTree<PoseidonHasher, U8, U8, U2>>();
+}
+
+fn test_invalid_rational_post<Tree: 'static + MerkleTreeTrait>() {
+    let rng = &mut XorShiftRng::from_seed(TEST_SEED);
+
+    let leaves = 64 * get_base_tree_count::<Tree>();
+    let sector_size = leaves as u64 * 32;
+    let challenges_count = 8;
+
+    let pub_params = rational::PublicParams {
+        sector_size,
+        challenges_count,
+    };
+
+    let temp_dir = tempdir().unwrap();
+    let temp_path = temp_dir.path();
+
+    let (_data1, tree1) = generate_tree::<Tree, _>(rng, leaves, Some(temp_path.to_path_buf()));
+    let (_data2, tree2) = generate_tree::<Tree, _>(rng, leaves, Some(temp_path.to_path_buf()));
+    let (_data3, wrong_tree) = generate_tree::<Tree, _>(rng, leaves, Some(temp_path.to_path_buf()));
+
+    let seed = (0..leaves).map(|_| rng.gen()).collect::<Vec<u8>>();
+    let faults = OrderedSectorSet::new();
+    let mut sectors = OrderedSectorSet::new();
+    sectors.insert(0.into());
+    sectors.insert(1.into());
+
+    let mut trees = BTreeMap::new();
+    trees.insert(0.into(), &tree1);
+    trees.insert(1.into(), &tree2);
+
+    let challenges =
+        derive_challenges(challenges_count, sector_size, &sectors, &seed, &faults).unwrap();
+    let comm_r_lasts = challenges
+        .iter()
+        .map(|c| trees.get(&c.sector).unwrap().root())
+        .collect::<Vec<_>>();
+
+    let comm_cs: Vec<<Tree::Hasher as Hasher>::Domain> = challenges
+        .iter()
+        .map(|_c| <Tree::Hasher as Hasher>::Domain::random(rng))
+        .collect();
+
+    let comm_rs: Vec<<Tree::Hasher as Hasher>::Domain> = comm_cs
+        .iter()
+        .zip(comm_r_lasts.iter())
+        .map(|(comm_c, comm_r_last)| <Tree::Hasher as Hasher>::Function::hash2(comm_c, comm_r_last))
+        .collect();
+
+    let pub_inputs = rational::PublicInputs {
+        challenges: challenges.clone(),
+        faults,
+        comm_rs,
+    };
+
+    // A tree which does not match its comm_r_last is rejected by the prover.
+    let mut wrong_trees = trees.clone();
+    wrong_trees.insert(challenges[0].sector, &wrong_tree);
+    let priv_inputs = rational::PrivateInputs::<Tree> {
+        trees: &wrong_trees,
+        comm_cs: &comm_cs,
+        comm_r_lasts: &comm_r_lasts,
+    };
+    let err = RationalPoSt::<Tree>::prove(&pub_params, &pub_inputs, &priv_inputs)
+        .expect_err("proving with a wrong tree succeeded");
+    assert!(matches!(
+        err.downcast::<Error>(),
+        Ok(Error::InvalidCommitment)
+    ));
+
+    // So is a challenged sector without a tree.
+    let mut missing_trees = trees.clone();
+    missing_trees.remove(&challenges[0].sector);
+    let priv_inputs = rational::PrivateInputs::<Tree> {
+        trees: &missing_trees,
+        comm_cs: &comm_cs,
+        comm_r_lasts: &comm_r_lasts,
+    };
+    let err = RationalPoSt::<Tree>::prove(&pub_params, &pub_inputs, &priv_inputs)
+        .expect_err("proving without a tree succeeded");
+    assert!(matches!(err.downcast::<Error>(), Ok(Error::MalformedInput)));
+
+    let priv_inputs = rational::PrivateInputs::<Tree> {
+        trees: &trees,
+        comm_cs: &comm_cs,
+        comm_r_lasts: &comm_r_lasts,
+    };
+    let proof = RationalPoSt::<Tree>::prove(&pub_params, &pub_inputs, &priv_inputs)
+        .expect("proving failed");
+    assert!(
+        RationalPoSt::<Tree>::verify(&pub_params, &pub_inputs, &proof)
+            .expect("verification failed")
+    );
+
+    // A valid proof does not verify against a different comm_r.
+    let mut wrong_pub_inputs = pub_inputs.clone();
+    wrong_pub_inputs.comm_rs[0] = <Tree::Hasher as Hasher>::Domain::random(rng);
+    let verified = RationalPoSt::<Tree>::verify(&pub_params, &wrong_pub_inputs, &proof)
+        .expect("verification failed");
+    assert!(!verified, "proof verified with a wrong comm_r");
+
+    // Nor against a sector size whose trees have a different height.
+    let wrong_pub_params = rational::PublicParams {
+        sector_size: sector_size * 8,
+        challenges_count,
+    };
+    let verified = RationalPoSt::<Tree>::verify(&wrong_pub_params, &pub_inputs, &proof)
+        .expect("verification failed");
+    assert!(!verified, "proof verified with a wrong sector size");
+}